@@ -10,6 +10,9 @@ cargo check --lib --tests
 echo "***** --features load"
 cargo check --lib --tests --features load
 
+echo "***** --features serde"
+cargo check --lib --tests --features serde
+
 echo "*****  --features _bench"
 cargo check --lib --tests --features _bench
 
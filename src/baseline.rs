@@ -0,0 +1,396 @@
+//! Persistence of benchmark results as named baselines and regression comparison of fresh results against them.
+//!
+//! Gated by feature **"serde"**.
+//!
+//! A [`BaselineStore`] saves [`BenchOut`] objects under a directory, keyed by benchmark id and baseline name.
+//! A stored baseline can later be compared against fresh results with [`BaselineStore::compare`], which returns
+//! a [`BaselineComparison`] providing a [`Comp`] between the fresh and stored results, together with a [`Verdict`].
+
+use crate::{BenchOut, Comp, stats_types::PositionWrtCi};
+use std::{
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// Extension of baseline files.
+const BASELINE_EXT: &str = "json";
+
+/// Errors that can occur when saving or loading baselines.
+#[derive(Debug)]
+pub enum BaselineError {
+    /// The benchmark id or baseline name is empty or can't be used as a path component.
+    InvalidName(String),
+    /// The requested baseline doesn't exist.
+    NotFound(PathBuf),
+    /// I/O error reading or writing a baseline file.
+    Io(io::Error),
+    /// Error serializing or deserializing a baseline.
+    Serde(serde_json::Error),
+}
+
+impl Display for BaselineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidName(name) => write!(f, "invalid benchmark id or baseline name: {name:?}"),
+            Self::NotFound(path) => write!(f, "baseline not found: {}", path.display()),
+            Self::Io(e) => write!(f, "baseline I/O error: {e}"),
+            Self::Serde(e) => write!(f, "baseline serialization error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for BaselineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Serde(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BaselineError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for BaselineError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Serde(value)
+    }
+}
+
+/// Outcome of the comparison of fresh benchmark results against a baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The fresh median latency is lower than the baseline's by more than the noise threshold.
+    Improved,
+    /// The fresh median latency is higher than the baseline's by more than the noise threshold.
+    Regressed,
+    /// No change beyond the noise threshold could be established.
+    NoChange,
+}
+
+impl Verdict {
+    /// Determines the verdict for `comp`, where `comp.out_f1()` holds the fresh results and `comp.out_f2()`
+    /// holds the baseline results.
+    ///
+    /// Uses the Welch confidence interval for `median(latency(fresh)) / median(latency(baseline))`, with
+    /// confidence level `(1 - alpha)`:
+    /// - [`Verdict::Regressed`] if the interval lies entirely above `1 + noise_threshold`;
+    /// - [`Verdict::Improved`] if the interval lies entirely below `1 / (1 + noise_threshold)`;
+    /// - [`Verdict::NoChange`] otherwise.
+    ///
+    /// The thresholds are symmetric on the log scale, so swapping the fresh and baseline results swaps
+    /// [`Verdict::Improved`] and [`Verdict::Regressed`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `noise_threshold < 0`.
+    /// - any of the conditions that cause [`Comp::welch_ratio_ci`] to panic.
+    pub fn from_comp(comp: &Comp, alpha: f64, noise_threshold: f64) -> Self {
+        assert!(
+            noise_threshold >= 0.,
+            "`noise_threshold` must be non-negative"
+        );
+        let hi = 1. + noise_threshold;
+        let lo = 1. / hi;
+
+        if comp.welch_value_position_wrt_ratio_ci(hi, alpha) == PositionWrtCi::Below {
+            Self::Regressed
+        } else if comp.welch_value_position_wrt_ratio_ci(lo, alpha) == PositionWrtCi::Above {
+            Self::Improved
+        } else {
+            Self::NoChange
+        }
+    }
+}
+
+/// Result of comparing fresh benchmark results against a stored baseline.
+///
+/// Owns the loaded baseline and references the fresh results.
+pub struct BaselineComparison<'a> {
+    baseline: BenchOut,
+    fresh: &'a BenchOut,
+    verdict: Verdict,
+}
+
+impl BaselineComparison<'_> {
+    /// Returns a [`Comp`] with the fresh results as `f1` and the baseline results as `f2`.
+    ///
+    /// Ratios greater than `1` thus indicate that the fresh results are slower than the baseline.
+    pub fn comp(&self) -> Comp<'_> {
+        Comp::new(self.fresh, &self.baseline)
+    }
+
+    /// The stored baseline results.
+    pub fn baseline(&self) -> &BenchOut {
+        &self.baseline
+    }
+
+    /// The fresh results.
+    pub fn fresh(&self) -> &BenchOut {
+        self.fresh
+    }
+
+    /// The verdict of the comparison. See [`Verdict::from_comp`].
+    pub fn verdict(&self) -> Verdict {
+        self.verdict
+    }
+}
+
+/// Directory-based store of named benchmark baselines.
+///
+/// Each baseline is stored as a JSON file at `<dir>/<bench_id>/<baseline>.json`.
+#[derive(Debug, Clone)]
+pub struct BaselineStore {
+    dir: PathBuf,
+}
+
+impl BaselineStore {
+    /// Creates a store rooted at `dir`. The directory is created on the first save if it doesn't exist.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Root directory of the store.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn validate_name(name: &str) -> Result<(), BaselineError> {
+        let invalid = name.is_empty()
+            || name == "."
+            || name == ".."
+            || name
+                .chars()
+                .any(|c| std::path::is_separator(c) || c == '\0');
+        if invalid {
+            Err(BaselineError::InvalidName(name.to_owned()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Path of the file for baseline `baseline` of benchmark `bench_id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BaselineError::InvalidName`] if either argument is empty, is `"."` or `".."`, or contains a path
+    /// separator.
+    pub fn path(&self, bench_id: &str, baseline: &str) -> Result<PathBuf, BaselineError> {
+        Self::validate_name(bench_id)?;
+        Self::validate_name(baseline)?;
+        let mut path = self.dir.join(bench_id).join(baseline);
+        path.set_extension(BASELINE_EXT);
+        Ok(path)
+    }
+
+    /// Returns `true` if baseline `baseline` of benchmark `bench_id` exists in the store.
+    pub fn exists(&self, bench_id: &str, baseline: &str) -> bool {
+        self.path(bench_id, baseline)
+            .map(|path| path.is_file())
+            .unwrap_or(false)
+    }
+
+    /// Saves `out` as baseline `baseline` of benchmark `bench_id`, replacing any previously saved baseline
+    /// with the same key.
+    ///
+    /// The file is written to a temporary location first and then renamed, so an interrupted save doesn't
+    /// corrupt an existing baseline.
+    pub fn save(
+        &self,
+        bench_id: &str,
+        baseline: &str,
+        out: &BenchOut,
+    ) -> Result<(), BaselineError> {
+        let path = self.path(bench_id, baseline)?;
+        let parent = path.parent().expect("baseline path always has a parent");
+        fs::create_dir_all(parent)?;
+
+        let tmp_path = path.with_extension(format!("{BASELINE_EXT}.tmp"));
+        {
+            let mut w = BufWriter::new(File::create(&tmp_path)?);
+            serde_json::to_writer(&mut w, out)?;
+            w.flush()?;
+        }
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    /// Loads baseline `baseline` of benchmark `bench_id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BaselineError::NotFound`] if the baseline doesn't exist.
+    pub fn load(&self, bench_id: &str, baseline: &str) -> Result<BenchOut, BaselineError> {
+        let path = self.path(bench_id, baseline)?;
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(BaselineError::NotFound(path));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let out = serde_json::from_reader(BufReader::new(file))?;
+        Ok(out)
+    }
+
+    /// Removes baseline `baseline` of benchmark `bench_id`. Does nothing if the baseline doesn't exist.
+    pub fn remove(&self, bench_id: &str, baseline: &str) -> Result<(), BaselineError> {
+        let path = self.path(bench_id, baseline)?;
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Loads baseline `baseline` of benchmark `bench_id` and compares `fresh` against it.
+    ///
+    /// Arguments:
+    /// - `bench_id` - benchmark id.
+    /// - `baseline` - baseline name.
+    /// - `fresh` - fresh benchmark results.
+    /// - `alpha` - confidence level of the ratio confidence interval is `1 - alpha`.
+    /// - `noise_threshold` - relative change of the median ratio below which changes are considered noise
+    ///   (e.g., `0.02` for 2%). See [`Verdict::from_comp`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the conditions in [`Verdict::from_comp`] is true, or if the recording units of `fresh`
+    /// and the stored baseline differ.
+    pub fn compare<'a>(
+        &self,
+        bench_id: &str,
+        baseline: &str,
+        fresh: &'a BenchOut,
+        alpha: f64,
+        noise_threshold: f64,
+    ) -> Result<BaselineComparison<'a>, BaselineError> {
+        let baseline = self.load(bench_id, baseline)?;
+        let verdict = Verdict::from_comp(&Comp::new(fresh, &baseline), alpha, noise_threshold);
+        Ok(BaselineComparison {
+            baseline,
+            fresh,
+            verdict,
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- baseline::test --nocapture
+mod test {
+    use super::*;
+    use crate::{
+        BenchCfg,
+        test_support::{LO_STDEV_LN, lognormal_out},
+    };
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    const ALPHA: f64 = 0.05;
+    const NOISE: f64 = 0.02;
+
+    fn temp_store() -> BaselineStore {
+        static SEQ: AtomicUsize = AtomicUsize::new(0);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "bench_utils_baseline_{}_{nanos}_{}",
+            std::process::id(),
+            SEQ.fetch_add(1, Ordering::Relaxed)
+        ));
+        BaselineStore::new(dir)
+    }
+
+    #[test]
+    fn test_save_load_roundtrip() {
+        let store = temp_store();
+        let cfg = BenchCfg::default();
+        let out = lognormal_out(&cfg, -9., *LO_STDEV_LN, 1_000);
+
+        assert!(!store.exists("bench_a", "main"));
+        store.save("bench_a", "main", &out).unwrap();
+        assert!(store.exists("bench_a", "main"));
+
+        let loaded = store.load("bench_a", "main").unwrap();
+        assert_eq!(out.summary(), loaded.summary());
+        assert_eq!(out.mean_ln(), loaded.mean_ln());
+        assert_eq!(out.stdev_ln(), loaded.stdev_ln());
+
+        store.remove("bench_a", "main").unwrap();
+        assert!(!store.exists("bench_a", "main"));
+        fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let store = temp_store();
+        assert!(matches!(
+            store.load("bench_a", "missing"),
+            Err(BaselineError::NotFound(_))
+        ));
+        assert!(matches!(
+            store.load("", "main"),
+            Err(BaselineError::InvalidName(_))
+        ));
+        assert!(matches!(
+            store.load("bench_a", "../main"),
+            Err(BaselineError::InvalidName(_))
+        ));
+        assert!(matches!(
+            store.load("..", "main"),
+            Err(BaselineError::InvalidName(_))
+        ));
+    }
+
+    #[test]
+    fn test_compare_verdicts() {
+        let store = temp_store();
+        let cfg = BenchCfg::default();
+        let mu = -9.;
+        let samp_size = 10_000;
+
+        let base = lognormal_out(&cfg, mu, *LO_STDEV_LN, samp_size);
+        store.save("bench_a", "main", &base).unwrap();
+
+        let same = lognormal_out(&cfg, mu, *LO_STDEV_LN, samp_size);
+        let slower = lognormal_out(&cfg, mu + 1.10_f64.ln(), *LO_STDEV_LN, samp_size);
+        let faster = lognormal_out(&cfg, mu - 1.10_f64.ln(), *LO_STDEV_LN, samp_size);
+        let slightly_slower = lognormal_out(&cfg, mu + 1.01_f64.ln(), *LO_STDEV_LN, samp_size);
+
+        let cmp = store
+            .compare("bench_a", "main", &same, ALPHA, NOISE)
+            .unwrap();
+        assert_eq!(Verdict::NoChange, cmp.verdict());
+
+        let cmp = store
+            .compare("bench_a", "main", &slower, ALPHA, NOISE)
+            .unwrap();
+        assert_eq!(Verdict::Regressed, cmp.verdict());
+        assert!(cmp.comp().ratio_medians_f1_f2_from_lns() > 1.);
+
+        let cmp = store
+            .compare("bench_a", "main", &faster, ALPHA, NOISE)
+            .unwrap();
+        assert_eq!(Verdict::Improved, cmp.verdict());
+        assert!(cmp.comp().ratio_medians_f1_f2_from_lns() < 1.);
+
+        // A 1% change is below the 2% noise threshold.
+        let cmp = store
+            .compare("bench_a", "main", &slightly_slower, ALPHA, NOISE)
+            .unwrap();
+        assert_eq!(Verdict::NoChange, cmp.verdict());
+
+        fs::remove_dir_all(store.dir()).unwrap();
+    }
+}
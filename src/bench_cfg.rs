@@ -1,39 +1,193 @@
-use crate::{LatencyUnit, RunLength, latency, multi::LatencySrc};
+use crate::{
+    FpSeconds, LatencyUnit, RunLength, SampleRetention, Timing, WarmupMode, latency, multi,
+    multi::LatencySrc, summary_stats::new_timing_with_bounds,
+};
 use log::{Level, debug, log_enabled};
-use std::time::{Duration, Instant};
+use std::{
+    iter,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
 /// Benchmark configuration, excluding the benchmark run length.
 ///
 /// Encapsulates the following data:
 /// - `warmup_millis`: warm-up duration in milliseconds
+/// - `warmup_mode`: whether the warm-up lasts `warmup_millis` milliseconds or ends once the latencies reach a
+///   steady state (see [`WarmupMode`])
 /// - `status_millis`: milliseconds between status reports during bench execution, if progress status reporting is enabled
 /// - `recording_unit`: time unit for latency recording
 /// - `sigfig`: as data is stored in an [HDR (high dynamic range) histogram](https://docs.rs/hdrhistogram/latest/hdrhistogram/index.html),
 ///   this is the number of significant decimal digits (of `recording_unit`) to which the histogram will maintain
 ///   value resolution and separation
+/// - `hist_bounds`: lowest and highest latencies tracked by the histogram; if unset, derived from the estimated
+///   iteration rate (see [`BenchCfg::hist_bounds`])
+/// - `hist_overflow`: handling of latencies above the highest trackable value (see [`HistOverflow`])
+/// - `paired_diff_hist`: whether benchmarks of two interleaved closures keep a histogram of the paired
+///   log-latency differences, which is required for the Wilcoxon signed-rank test
+///   (see [`DuoOut`](crate::duo::DuoOut))
+/// - `overhead_mode`: whether the timer overhead is calibrated and, if so, whether it is only reported or also
+///   subtracted from the captured latencies (see [`OverheadMode`])
+/// - `auto_batch_multiple`: for auto-batching, the minimum ratio of the latency of a batch to the sum of the clock
+///   resolution and the timer overhead (see [`BenchCfg::auto_batch`])
+/// - `sample_retention`: whether the raw per-iteration latencies are retained for time-series analysis
+///   (see [`SampleRetention`])
+/// - `cancel_token`: optional token through which a running benchmark can be stopped early (see [`CancelToken`])
+///
+/// With feature **"serde"**, this struct can be serialized, except for the `cancel_token`, e.g., for
+/// [`JsonStatus`](crate::status::JsonStatus) events. It can't be deserialized, as that would bypass the validation
+/// done by the `with_*` methods.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct BenchCfg {
     warmup_millis: u64,
+    warmup_mode: WarmupMode,
     status_millis: u64,
     recording_unit: LatencyUnit,
     sigfig: u8,
+    hist_bounds: Option<(FpSeconds, FpSeconds)>,
+    hist_overflow: HistOverflow,
+    paired_diff_hist: bool,
+    overhead_mode: OverheadMode,
+    auto_batch_multiple: f64,
+    sample_retention: SampleRetention,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cancel_token: Option<CancelToken>,
+}
+
+/// Outcome of the probing done by [`BenchCfg::auto_batch`], which auto-batched runs reuse instead of probing again.
+///
+/// The timer overhead estimated to choose the batch size is not kept, as it was measured for unbatched executions;
+/// auto-batched runs calibrate it again with the chosen batch size (see [`OverheadMode`]).
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BatchProbe {
+    /// Chosen batch size.
+    pub(crate) batch: usize,
+    /// Estimated number of iterations per second.
+    pub(crate) execs_per_sec: f64,
+}
+
+/// Determines how the overhead of measuring each latency is handled.
+///
+/// With [`OverheadMode::Report`] or [`OverheadMode::Subtract`], after warm-up, the benchmarking functions
+/// calibrate the overhead by measuring the latency distribution of a no-op timed the same way the benchmarked
+/// [`LatencySrc`] times its closures, i.e., with the same [`Measurement`](crate::Measurement) and batch size (see
+/// [`LatencySrc::empty_latency`]). The median of that distribution is the overhead estimate per execution, available from
+/// [`RunInfo::overhead`](crate::RunInfo::overhead). A warning is logged if the median latency of a benchmarked
+/// closure is less than [`BenchCfg::OVERHEAD_WARN_MULTIPLE`] times the overhead estimate, as the results are then
+/// dominated by measurement overhead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum OverheadMode {
+    /// The overhead is not calibrated.
+    Ignore,
+    /// The overhead is calibrated and reported, but captured latencies are not adjusted.
+    Report,
+    /// The overhead is calibrated, reported, and subtracted from each captured latency, with a floor of zero.
+    /// For batched latency sources, the overhead is spread over the batch. Latencies floored at zero are excluded
+    /// from the statistics of the logarithms of the latencies; their number is available from
+    /// [`RunInfo::overhead_floored`](crate::RunInfo::overhead_floored).
+    Subtract,
+}
+
+/// Determines how latencies above the highest trackable value of the histogram are handled
+/// (see [`BenchCfg::hist_bounds`]).
+///
+/// With [`HistOverflow::Clamp`] or [`HistOverflow::Reject`], the memory used by the histogram is fixed, and the number
+/// of out-of-range observations is available from [`RunInfo::out_of_range`](crate::RunInfo::out_of_range).
+/// Merging outputs (see [`BenchOut::merge`](crate::BenchOut::merge)) never clamps or rejects observations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HistOverflow {
+    /// The histogram is resized to track the latency.
+    #[default]
+    Resize,
+    /// The latency is recorded in the histogram as the highest trackable value. The moment-based statistics
+    /// (e.g., the mean and the `*_ln_*` statistics) and the retained raw samples use the actual latency.
+    Clamp,
+    /// The observation is discarded.
+    Reject,
+}
+
+/// Shared flag used to stop running benchmarks early, e.g., from a UI, a Ctrl-C handler, or a watchdog thread.
+///
+/// Clones share the same flag. Once [`Self::cancel`] is called, benchmarks run with a [`BenchCfg`] holding the token
+/// (see [`BenchCfg::with_cancel_token`]) stop at their next iteration, skipping any remaining phases, and return the
+/// data collected so far. Such outputs are marked as aborted (see [`RunInfo::aborted`](crate::RunInfo::aborted)).
+///
+/// A [`Status`](crate::status::Status) implementation can hold a clone of the token to stop a benchmark from its
+/// status closures.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Instantiates a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests that benchmarks holding `self` or a clone of it stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// `true` if [`Self::cancel`] was called on `self` or a clone of it.
+    #[inline(always)]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
 }
 
 impl BenchCfg {
     /// Default warm-up duration in milliseconds.
     pub const DEFAULT_WARMUP_MILLIS: u64 = 3000;
+    /// Default warm-up mode.
+    pub const DEFAULT_WARMUP_MODE: WarmupMode = WarmupMode::Fixed;
     /// Default status reporting interval in milliseconds.
     pub const DEFAULT_STATUS_MILLIS: u64 = 1000;
     /// Default unit for recording latencies.
     pub const DEFAULT_RECORDING_UNIT: LatencyUnit = LatencyUnit::NANO;
     /// Default number of significant decimal digits for the HDR histogram.
     pub const DEFAULT_SIGFIG: u8 = 3;
+    /// Default lowest and highest latencies tracked by the histogram; `None` means they are derived from the
+    /// estimated iteration rate.
+    pub const DEFAULT_HIST_BOUNDS: Option<(FpSeconds, FpSeconds)> = None;
+    /// Highest latency tracked by the histogram when [`Self::hist_bounds`] is unset and no iteration rate estimate
+    /// is available, e.g., for [`BenchOut::from_iter`](crate::BenchOut::from_iter).
+    pub const HIST_HIGH_FALLBACK: FpSeconds = FpSeconds(0.02);
+    /// Multiple of the mean iteration latency used as the highest latency tracked by the histogram when
+    /// [`Self::hist_bounds`] is unset.
+    pub const HIST_HIGH_MULTIPLE: f64 = 10_000.;
+    /// Default handling of latencies above the highest trackable value of the histogram.
+    pub const DEFAULT_HIST_OVERFLOW: HistOverflow = HistOverflow::Resize;
+    /// Default for keeping a histogram of paired log-latency differences.
+    pub const DEFAULT_PAIRED_DIFF_HIST: bool = true;
+    /// Default handling of the timer overhead.
+    pub const DEFAULT_OVERHEAD_MODE: OverheadMode = OverheadMode::Ignore;
+    /// Multiple of the overhead estimate below which the median latency of a benchmarked closure triggers a warning
+    /// (see [`OverheadMode`]).
+    pub const OVERHEAD_WARN_MULTIPLE: f64 = 5.;
+    /// Default minimum ratio of the latency of a batch to the sum of the clock resolution and the timer overhead
+    /// for auto-batching.
+    pub const DEFAULT_AUTO_BATCH_MULTIPLE: f64 = 100.;
+    /// Largest batch size chosen by auto-batching.
+    pub const AUTO_BATCH_MAX: usize = 1 << 20;
+    /// Default retention of raw per-iteration latencies.
+    pub const DEFAULT_SAMPLE_RETENTION: SampleRetention = SampleRetention::Off;
 
     /// The number of milliseconds used to "warm-up" the benchmark.
     pub fn warmup_millis(&self) -> u64 {
         self.warmup_millis
     }
 
+    /// Warm-up mode.
+    pub fn warmup_mode(&self) -> WarmupMode {
+        self.warmup_mode
+    }
+
     /// Status reporting interval in milliseconds.
     pub fn status_millis(&self) -> u64 {
         self.status_millis
@@ -51,12 +205,73 @@ impl BenchCfg {
         self.sigfig
     }
 
+    /// Lowest and highest latencies tracked by the histogram, if set.
+    ///
+    /// If unset, the benchmarking functions derive the highest trackable latency as [`Self::HIST_HIGH_MULTIPLE`]
+    /// times the mean latency of an iteration, estimated by the probing done before the warm-up, and the lowest as
+    /// one [`Self::recording_unit`]. For [`bench_run_open_loop`](crate::bench_run_open_loop), the arrival rate is
+    /// used as the estimated iteration rate. Without an estimate, e.g., for
+    /// [`BenchOut::from_iter`](crate::BenchOut::from_iter), the highest is [`Self::HIST_HIGH_FALLBACK`], so the bounds
+    /// should be set when using [`HistOverflow::Clamp`] or [`HistOverflow::Reject`].
+    ///
+    /// The same bounds, and the same [`Self::hist_overflow`], apply to the latencies by position
+    /// (see [`multi::BenchOut::position_medians`]). They don't apply to the histograms of paired log-latency
+    /// differences (see [`Self::paired_diff_hist`]), which track differences of logarithms rather than latencies and
+    /// are always auto-resizable.
+    pub fn hist_bounds(&self) -> Option<(FpSeconds, FpSeconds)> {
+        self.hist_bounds
+    }
+
+    /// Handling of latencies above the highest trackable value of the histogram.
+    pub fn hist_overflow(&self) -> HistOverflow {
+        self.hist_overflow
+    }
+
+    /// Whether benchmarks of two interleaved closures keep a histogram of the paired differences
+    /// `ln(latency(f1)) - ln(latency(f2))`.
+    pub fn paired_diff_hist(&self) -> bool {
+        self.paired_diff_hist
+    }
+
+    /// Handling of the timer overhead.
+    pub fn overhead_mode(&self) -> OverheadMode {
+        self.overhead_mode
+    }
+
+    /// For auto-batching, the minimum ratio of the latency of a batch to the sum of the clock resolution and the
+    /// timer overhead.
+    pub fn auto_batch_multiple(&self) -> f64 {
+        self.auto_batch_multiple
+    }
+
+    /// Retention of raw per-iteration latencies.
+    pub fn sample_retention(&self) -> SampleRetention {
+        self.sample_retention
+    }
+
+    /// Token through which running benchmarks can be stopped early, if any.
+    pub fn cancel_token(&self) -> Option<&CancelToken> {
+        self.cancel_token.as_ref()
+    }
+
+    /// `true` if `self` holds a cancelled [`CancelToken`].
+    #[inline(always)]
+    pub(crate) fn cancelled(&self) -> bool {
+        self.cancel_token.as_ref().is_some_and(|t| t.is_cancelled())
+    }
+
     /// Sets the number of milliseconds used to "warm-up" the benchmark.
     pub fn with_warmup_millis(mut self, warmup_millis: u64) -> Self {
         self.warmup_millis = warmup_millis;
         self
     }
 
+    /// Sets the warm-up mode.
+    pub fn with_warmup_mode(mut self, warmup_mode: WarmupMode) -> Self {
+        self.warmup_mode = warmup_mode;
+        self
+    }
+
     /// Sets the status reporting interval in milliseconds.
     pub fn with_status_millis(mut self, status_millis: u64) -> Self {
         self.status_millis = status_millis;
@@ -75,6 +290,78 @@ impl BenchCfg {
         self
     }
 
+    /// Sets the lowest and highest latencies tracked by the histogram.
+    ///
+    /// Both are rounded to the [`Self::recording_unit`]; the lowest is at least one unit and the highest is at least
+    /// twice the lowest.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 <= low < high` and `high` is finite.
+    pub fn with_hist_bounds(mut self, low: FpSeconds, high: FpSeconds) -> Self {
+        assert!(
+            low.0 >= 0. && low < high && high.0.is_finite(),
+            "must have `0 <= low < high` and `high` finite"
+        );
+        self.hist_bounds = Some((low, high));
+        self
+    }
+
+    /// Sets the handling of latencies above the highest trackable value of the histogram.
+    pub fn with_hist_overflow(mut self, hist_overflow: HistOverflow) -> Self {
+        self.hist_overflow = hist_overflow;
+        self
+    }
+
+    /// Sets whether benchmarks of two interleaved closures keep a histogram of the paired log-latency differences.
+    pub fn with_paired_diff_hist(mut self, paired_diff_hist: bool) -> Self {
+        self.paired_diff_hist = paired_diff_hist;
+        self
+    }
+
+    /// Sets the handling of the timer overhead.
+    pub fn with_overhead_mode(mut self, overhead_mode: OverheadMode) -> Self {
+        self.overhead_mode = overhead_mode;
+        self
+    }
+
+    /// Sets the minimum ratio of the latency of a batch to the sum of the clock resolution and the timer overhead
+    /// for auto-batching.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `auto_batch_multiple` is not positive and finite.
+    pub fn with_auto_batch_multiple(mut self, auto_batch_multiple: f64) -> Self {
+        assert!(
+            auto_batch_multiple > 0. && auto_batch_multiple.is_finite(),
+            "`auto_batch_multiple` must be positive and finite"
+        );
+        self.auto_batch_multiple = auto_batch_multiple;
+        self
+    }
+
+    /// Sets the retention of raw per-iteration latencies.
+    pub fn with_sample_retention(mut self, sample_retention: SampleRetention) -> Self {
+        self.sample_retention = sample_retention;
+        self
+    }
+
+    /// Sets the token through which running benchmarks can be stopped early.
+    pub fn with_cancel_token(mut self, cancel_token: CancelToken) -> Self {
+        self.cancel_token = Some(cancel_token);
+        self
+    }
+
+    /// Run length of the warm-up phase: [`Self::warmup_millis`] for [`WarmupMode::Fixed`], or the maximum duration
+    /// of an adaptive warm-up, which may end earlier.
+    pub(crate) fn warmup_run_length(&self) -> RunLength {
+        let millis = match self.warmup_mode {
+            WarmupMode::Fixed => self.warmup_millis,
+            WarmupMode::Adaptive(params) => params.max_millis(),
+        };
+        RunLength::Time(Duration::from_millis(millis))
+    }
+
     fn execs_per_sec_budget(&self, exec_run_length: RunLength) -> RunLength {
         const WARMUP_DIVISOR: u32 = 3;
         const EXEC_DIVISOR: u32 = 30;
@@ -91,18 +378,28 @@ impl BenchCfg {
             }
         }
 
-        let adj_warmup_run_length = RunLength::Time(Duration::from_millis(
-            self.warmup_millis / WARMUP_DIVISOR as u64,
-        ));
-        let adj_exec_run_length = match exec_run_length {
+        // An adaptive warm-up is assumed to last its minimum duration.
+        let warmup_millis = match self.warmup_mode {
+            WarmupMode::Fixed => self.warmup_millis,
+            WarmupMode::Adaptive(params) => params.min_millis(),
+        };
+        let adj_warmup_run_length =
+            RunLength::Time(Duration::from_millis(warmup_millis / WARMUP_DIVISOR as u64));
+        // An uncapped `RunLength::Precision` doesn't contribute to the budget.
+        let adj_exec_run_length = exec_run_length.cap().map(|run_length| match run_length {
             RunLength::Count(count) => RunLength::Count(count / EXEC_DIVISOR as usize),
             RunLength::Time(dur) => RunLength::Time(dur / EXEC_DIVISOR),
             RunLength::CountWithTimeout(count, dur) => {
                 RunLength::CountWithTimeout(count / EXEC_DIVISOR as usize, dur / EXEC_DIVISOR)
             }
-        };
+            RunLength::Precision { .. } => {
+                unreachable!("`cap` never returns `RunLength::Precision`")
+            }
+        });
 
-        let run_lengths = [adj_warmup_run_length, adj_exec_run_length];
+        let run_lengths = iter::once(adj_warmup_run_length)
+            .chain(adj_exec_run_length)
+            .collect::<Vec<_>>();
         debug!("execs_per_sec_budget >>> run_lengths[warmup, exec]={run_lengths:?}");
 
         let counts = run_lengths
@@ -155,6 +452,16 @@ impl BenchCfg {
         &self,
         src: &mut impl LatencySrc<K>,
         exec_run_length: RunLength,
+    ) -> f64 {
+        self.execs_per_sec_from_aggregate(src.aggregate(), exec_run_length)
+    }
+
+    /// Same as [`Self::execs_per_sec`], but from the aggregate latencies of a source's iterations, i.e., the sums of
+    /// the latencies of the closures in each iteration.
+    pub(crate) fn execs_per_sec_from_aggregate(
+        &self,
+        aggregate: impl Iterator<Item = FpSeconds>,
+        exec_run_length: RunLength,
     ) -> f64 {
         let start = if log_enabled!(Level::Debug) {
             Some(Instant::now())
@@ -163,7 +470,7 @@ impl BenchCfg {
         };
 
         let budget = self.execs_per_sec_budget(exec_run_length);
-        let eps = latency::execs_per_sec(src.aggregate(), budget);
+        let eps = latency::execs_per_sec(aggregate, budget);
 
         debug!(
             "execs_per_sec >>> execs_per_sec={eps:?}, elapsed={:?}",
@@ -173,6 +480,87 @@ impl BenchCfg {
         eps
     }
 
+    /// Chooses the smallest batch size for which the latency of a batch of executions of each closure of `src` is at
+    /// least [`Self::auto_batch_multiple`] times the sum of the clock resolution and the timer overhead.
+    ///
+    /// The latency of each closure is estimated by the same probing used to estimate status reporting intervals,
+    /// so it takes time proportional to [`Self::warmup_millis`] and to `exec_run_length`. For closures with
+    /// different latencies, the estimate is their mean latency. The result is between `1` and
+    /// [`Self::AUTO_BATCH_MAX`].
+    ///
+    /// The result can be used, for example, as the `batch` argument of [`LatencySrc1b::new`](multi::LatencySrc1b::new).
+    pub fn auto_batch<const K: usize>(
+        &self,
+        src: &mut impl LatencySrc<K>,
+        exec_run_length: RunLength,
+    ) -> usize {
+        self.batch_probe(src, exec_run_length).batch
+    }
+
+    /// Same as [`Self::auto_batch`], but also returns the iteration rate estimated to choose the batch size, so that
+    /// an auto-batched run can reuse it instead of probing again.
+    pub(crate) fn batch_probe<const K: usize>(
+        &self,
+        src: &mut impl LatencySrc<K>,
+        exec_run_length: RunLength,
+    ) -> BatchProbe {
+        let execs_per_sec = self.execs_per_sec(src, exec_run_length);
+        let resolution = latency::clock_resolution();
+        let overhead = multi::calibrate_overhead(self, || src.empty_latency());
+        let min_batch_latency = (resolution + overhead).as_f64() * self.auto_batch_multiple;
+
+        // Mean latency of a single execution of a closure.
+        let latency = 1. / (execs_per_sec * K as f64);
+        let batch = (min_batch_latency / latency).ceil();
+        debug!(
+            "auto_batch >>> resolution={resolution:?}, overhead={overhead:?}, latency={latency}, batch={batch}"
+        );
+
+        let batch = if batch.is_nan() || batch < 1. {
+            1
+        } else {
+            (batch as usize).min(Self::AUTO_BATCH_MAX)
+        };
+
+        BatchProbe {
+            batch,
+            execs_per_sec,
+        }
+    }
+
+    /// Lowest and highest values, in [`Self::recording_unit`], tracked by the histogram of a benchmark whose
+    /// estimated number of iterations per second is `execs_per_sec` (see [`Self::hist_bounds`]).
+    pub(crate) fn hist_range(&self, execs_per_sec: Option<f64>) -> (u64, u64) {
+        let (low, high) = match (self.hist_bounds, execs_per_sec) {
+            (Some(bounds), _) => bounds,
+            (None, Some(eps)) if eps.is_finite() && eps > 0. => {
+                (FpSeconds::ZERO, FpSeconds(Self::HIST_HIGH_MULTIPLE / eps))
+            }
+            _ => (FpSeconds::ZERO, Self::HIST_HIGH_FALLBACK),
+        };
+        let low = self
+            .recording_unit
+            .value_from_fpsecs(low)
+            .clamp(1, u64::MAX / 4);
+        let high = self
+            .recording_unit
+            .value_from_fpsecs(high)
+            .clamp(2 * low, u64::MAX / 2);
+        (low, high)
+    }
+
+    /// Creates a histogram sized for a benchmark whose estimated number of iterations per second is `execs_per_sec`
+    /// (see [`Self::hist_bounds`]).
+    pub(crate) fn new_hist(&self, execs_per_sec: Option<f64>) -> Timing {
+        let (low, high) = self.hist_range(execs_per_sec);
+        new_timing_with_bounds(
+            low,
+            high,
+            self.sigfig,
+            self.hist_overflow == HistOverflow::Resize,
+        )
+    }
+
     /// Number of executions between status updates, derived from `execs_per_second`.
     pub(crate) fn status_count(&self, execs_per_second: f64) -> usize {
         let status_count = self.status_millis as f64 / 1000.0 * execs_per_second;
@@ -184,9 +572,17 @@ impl Default for BenchCfg {
     fn default() -> Self {
         Self {
             warmup_millis: Self::DEFAULT_WARMUP_MILLIS,
+            warmup_mode: Self::DEFAULT_WARMUP_MODE,
             status_millis: Self::DEFAULT_STATUS_MILLIS,
             recording_unit: Self::DEFAULT_RECORDING_UNIT,
             sigfig: Self::DEFAULT_SIGFIG,
+            hist_bounds: Self::DEFAULT_HIST_BOUNDS,
+            hist_overflow: Self::DEFAULT_HIST_OVERFLOW,
+            paired_diff_hist: Self::DEFAULT_PAIRED_DIFF_HIST,
+            overhead_mode: Self::DEFAULT_OVERHEAD_MODE,
+            auto_batch_multiple: Self::DEFAULT_AUTO_BATCH_MULTIPLE,
+            sample_retention: Self::DEFAULT_SAMPLE_RETENTION,
+            cancel_token: None,
         }
     }
 }
@@ -196,7 +592,10 @@ impl Default for BenchCfg {
 mod test {
     use crate::multi::LatencySrc1;
     use crate::multi::test_support::LognormalLatencySrc;
-    use crate::{BenchCfg, FpSeconds, LatencyUnit, RunLength};
+    use crate::{
+        AdaptiveWarmup, BenchCfg, CancelToken, FpSeconds, HistOverflow, LatencyUnit, OverheadMode,
+        PrecisionTarget, RunLength, SampleRetention, WarmupMode,
+    };
     use basic_stats::rel_approx_eq;
     use std::time::Duration;
 
@@ -206,9 +605,27 @@ mod test {
 
         println!("cfg={cfg:?}");
         assert_eq!(cfg.warmup_millis(), BenchCfg::DEFAULT_WARMUP_MILLIS);
+        assert_eq!(cfg.warmup_mode(), BenchCfg::DEFAULT_WARMUP_MODE);
+        assert_eq!(
+            cfg.warmup_run_length().exec_count_and_duration(),
+            (
+                usize::MAX,
+                Duration::from_millis(BenchCfg::DEFAULT_WARMUP_MILLIS)
+            )
+        );
         assert_eq!(cfg.recording_unit(), BenchCfg::DEFAULT_RECORDING_UNIT);
         assert_eq!(cfg.sigfig(), BenchCfg::DEFAULT_SIGFIG);
+        assert_eq!(cfg.hist_bounds(), BenchCfg::DEFAULT_HIST_BOUNDS);
+        assert_eq!(cfg.hist_overflow(), BenchCfg::DEFAULT_HIST_OVERFLOW);
         assert_eq!(cfg.status_millis(), BenchCfg::DEFAULT_STATUS_MILLIS);
+        assert_eq!(cfg.paired_diff_hist(), BenchCfg::DEFAULT_PAIRED_DIFF_HIST);
+        assert_eq!(cfg.overhead_mode(), BenchCfg::DEFAULT_OVERHEAD_MODE);
+        assert_eq!(
+            cfg.auto_batch_multiple(),
+            BenchCfg::DEFAULT_AUTO_BATCH_MULTIPLE
+        );
+        assert_eq!(cfg.sample_retention(), BenchCfg::DEFAULT_SAMPLE_RETENTION);
+        assert!(cfg.cancel_token().is_none());
     }
 
     #[test]
@@ -216,13 +633,43 @@ mod test {
         let cfg = BenchCfg::default()
             .with_recording_unit(LatencyUnit::MICRO)
             .with_warmup_millis(100)
+            .with_warmup_mode(WarmupMode::Adaptive(AdaptiveWarmup::new(50, 500)))
             .with_sigfig(5)
-            .with_status_millis(200);
+            .with_hist_bounds(FpSeconds::from_micros(1), FpSeconds::from_millis(10))
+            .with_hist_overflow(HistOverflow::Clamp)
+            .with_status_millis(200)
+            .with_paired_diff_hist(false)
+            .with_overhead_mode(OverheadMode::Subtract)
+            .with_auto_batch_multiple(20.)
+            .with_sample_retention(SampleRetention::Reservoir(100, 1))
+            .with_cancel_token(CancelToken::new());
 
         assert_eq!(cfg.warmup_millis(), 100);
+        assert_eq!(
+            cfg.warmup_mode(),
+            WarmupMode::Adaptive(AdaptiveWarmup::new(50, 500))
+        );
+        assert_eq!(
+            cfg.warmup_run_length().exec_count_and_duration(),
+            (usize::MAX, Duration::from_millis(500))
+        );
+        assert!(!cfg.paired_diff_hist());
+        assert_eq!(cfg.overhead_mode(), OverheadMode::Subtract);
+        assert_eq!(cfg.auto_batch_multiple(), 20.);
+        assert_eq!(cfg.sample_retention(), SampleRetention::Reservoir(100, 1));
         assert_eq!(cfg.recording_unit(), LatencyUnit::MICRO);
         assert_eq!(cfg.sigfig(), 5);
+        assert_eq!(
+            cfg.hist_bounds(),
+            Some((FpSeconds::from_micros(1), FpSeconds::from_millis(10)))
+        );
+        assert_eq!(cfg.hist_overflow(), HistOverflow::Clamp);
         assert_eq!(200, cfg.status_millis);
+        assert!(!cfg.cancelled());
+
+        // Clones of the token share the cancellation.
+        cfg.cancel_token().unwrap().clone().cancel();
+        assert!(cfg.cancelled());
     }
 
     #[test]
@@ -244,6 +691,50 @@ mod test {
         assert_eq!(dur, Duration::from_secs(5));
     }
 
+    #[test]
+    fn test_run_length_precision_caps() {
+        let target = PrecisionTarget::new(0.01, 0.05);
+        let execs_per_second = 1_000_000.0;
+
+        let run_length = RunLength::Precision {
+            target,
+            max_count: Some(100),
+            max_time: Some(Duration::from_secs(5)),
+        };
+        assert_eq!(
+            (100, Duration::from_secs(5)),
+            run_length.exec_count_and_duration()
+        );
+        assert_eq!(Some(target), run_length.precision_target());
+        assert_eq!(100, run_length.estimated_count(execs_per_second));
+
+        let run_length = RunLength::Precision {
+            target,
+            max_count: None,
+            max_time: Some(Duration::from_secs(3)),
+        };
+        assert_eq!(3_000_000, run_length.estimated_count(execs_per_second));
+        assert_eq!(
+            Duration::from_secs(3),
+            run_length.estimated_time(execs_per_second)
+        );
+
+        let run_length = RunLength::Precision {
+            target,
+            max_count: None,
+            max_time: None,
+        };
+        assert!(run_length.cap().is_none());
+        assert_eq!(
+            (usize::MAX, Duration::MAX),
+            run_length.exec_count_and_duration()
+        );
+        assert_eq!(usize::MAX, run_length.estimated_count(execs_per_second));
+        assert_eq!(Duration::MAX, run_length.estimated_time(execs_per_second));
+
+        assert_eq!(None, RunLength::Count(5).precision_target());
+    }
+
     #[test]
     fn test_run_length_estimated_count() {
         let execs_per_second = 1_000_000.0; // 1 execution per microsecond
@@ -344,6 +835,45 @@ mod test {
         assert_eq!(count, 1);
     }
 
+    #[test]
+    fn test_bench_cfg_hist_range() {
+        // Fallback: 20 ms, consistent with the recording unit.
+        let cfg = BenchCfg::default();
+        assert_eq!((1, 20_000_000), cfg.hist_range(None));
+        assert_eq!((1, 20_000_000), cfg.hist_range(Some(f64::INFINITY)));
+        let cfg = BenchCfg::default().with_recording_unit(LatencyUnit::PICO);
+        assert_eq!((1, 20_000_000_000), cfg.hist_range(None));
+
+        // Derived from the iteration rate: 10_000 iterations of 1 ms.
+        let cfg = BenchCfg::default();
+        assert_eq!((1, 10_000_000_000), cfg.hist_range(Some(1000.)));
+        let cfg = BenchCfg::default().with_recording_unit(LatencyUnit::MICRO);
+        assert_eq!((1, 10_000_000), cfg.hist_range(Some(1000.)));
+
+        // Explicit bounds take precedence and are adjusted to the histogram's constraints.
+        let cfg = BenchCfg::default()
+            .with_hist_bounds(FpSeconds::from_micros(1), FpSeconds::from_millis(10));
+        assert_eq!((1000, 10_000_000), cfg.hist_range(Some(1000.)));
+        let cfg = BenchCfg::default().with_hist_bounds(FpSeconds::ZERO, FpSeconds::from_micros(1));
+        assert_eq!((1, 1000), cfg.hist_range(None));
+        let cfg = BenchCfg::default()
+            .with_recording_unit(LatencyUnit::MILLI)
+            .with_hist_bounds(FpSeconds::from_millis(3), FpSeconds::from_millis(4));
+        assert_eq!((3, 6), cfg.hist_range(None));
+
+        let hist = cfg.new_hist(None);
+        assert!(hist.is_auto_resize());
+        let hist = cfg.with_hist_overflow(HistOverflow::Reject).new_hist(None);
+        assert!(!hist.is_auto_resize());
+        assert_eq!(6, hist.high());
+
+        let result = std::panic::catch_unwind(|| {
+            BenchCfg::default()
+                .with_hist_bounds(FpSeconds::from_millis(2), FpSeconds::from_millis(1))
+        });
+        assert!(result.is_err(), "should panic when `low >= high`");
+    }
+
     #[test]
     // cargo test --package bench_utils --lib --all-features -- bench_cfg::test::test_bench_cfg_execs_per_second --exact --nocapture --include-ignored
     fn test_bench_cfg_execs_per_second() {
@@ -365,6 +895,26 @@ mod test {
         rel_approx_eq!(100.0, eps, 0.05);
     }
 
+    #[test]
+    fn test_auto_batch() {
+        let cfg = BenchCfg::default().with_warmup_millis(30);
+
+        let mut slow =
+            LognormalLatencySrc::<1>::new_with_default_sigmas(1, [FpSeconds::from_millis(10)]);
+        assert_eq!(1, cfg.auto_batch(&mut slow, RunLength::Count(500)));
+
+        let mut fast = LognormalLatencySrc::<2>::new_with_default_sigmas(
+            1,
+            [FpSeconds(1e-9), FpSeconds(1e-9)],
+        );
+        let batch = cfg.auto_batch(&mut fast, RunLength::Count(500));
+        // The clock resolution is at least 1ns.
+        assert!(
+            (100..=BenchCfg::AUTO_BATCH_MAX).contains(&batch),
+            "batch={batch}"
+        );
+    }
+
     #[test]
     fn test_src_execs_per_sec_time_run_length() {
         let cfg = BenchCfg::default();
@@ -1,7 +1,8 @@
 //! Module defining the key data structure produced by [`crate::bench_run`].
 
 use crate::{
-    BenchCfg, FpSeconds, LatencyUnit, SummaryStats, Timing, multi, new_timing, summary_stats,
+    BenchCfg, FpSeconds, HistOverflow, LatencyUnit, RunInfo, Samples, SummaryStats, Timing, multi,
+    stats_support::t_to_p, summary_stats,
 };
 use basic_stats::{
     core::{AltHyp, Ci, HypTestResult, PositionWrtCi, SampleMoments, sample_mean, sample_stdev},
@@ -9,6 +10,9 @@ use basic_stats::{
 };
 use std::{fmt::Debug, iter};
 
+/// Maximum number of synthetic observations recorded by [`BenchOut::capture_corrected`] for one latency.
+pub(crate) const MAX_CORRECTIONS: u64 = 10_000;
+
 /// Contains the latency observations resulting from benchmarking a closure.
 ///
 /// It is returned by the core benchmarking functions in this library.
@@ -19,21 +23,37 @@ use std::{fmt::Debug, iter};
 /// Under the assumption that `latency(f)` is approximately log-normal, `mean(ln(latency(f))) == ln(median(latency(f)))`.
 /// This assumption is widely supported by performance analysis theory and empirical data.
 /// Thus, the `*_ln_*` methods are useful for the analysis of median latencies.
+///
+/// With feature **"serde"**, this struct can be serialized and deserialized. The histogram is encoded with
+/// HdrHistogram's compact V2 (deflate) format, and round-trips are lossless, including whether the histogram is
+/// auto-resizable (see [`BenchCfg::hist_overflow`]).
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "crate::serde_support::BenchOutRepr")
+)]
 pub struct BenchOut {
     pub(crate) recording_unit: LatencyUnit,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::timing_v2"))]
     pub(crate) hist: Timing,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) hist_overflow: HistOverflow,
     pub(crate) sum: f64,
     pub(crate) sum2: f64,
     pub(crate) n_nz: u64,
     pub(crate) sum_ln: f64,
     pub(crate) sum2_ln: f64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) run_info: RunInfo,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) samples: Option<Samples>,
 }
 
 impl BenchOut {
     #[doc(hidden)]
     /// Creates a new empty instance based on `cfg`.
     pub fn new(cfg: &BenchCfg) -> Self {
-        let hist = new_timing(20 * 1000 * 1000, cfg.sigfig());
+        let hist = cfg.new_hist(None);
         let sum = 0.;
         let sum2 = 0.;
         let n_nz = 0;
@@ -43,11 +63,14 @@ impl BenchOut {
         Self {
             recording_unit: cfg.recording_unit(),
             hist,
+            hist_overflow: cfg.hist_overflow(),
             sum,
             sum2,
             n_nz,
             sum_ln,
             sum2_ln,
+            run_info: RunInfo::default(),
+            samples: Samples::new(cfg.sample_retention()),
         }
     }
 
@@ -97,23 +120,45 @@ impl BenchOut {
         self.sum2 = 0.;
         self.n_nz = 0;
         self.sum_ln = 0.;
-        self.sum2_ln = 0.
+        self.sum2_ln = 0.;
+        self.run_info = RunInfo::default();
+        if let Some(samples) = &mut self.samples {
+            samples.reset();
+        }
+    }
+
+    /// Replaces the empty histogram of `self` with one sized for a benchmark whose estimated number of iterations per
+    /// second is `execs_per_sec` (see [`BenchCfg::hist_bounds`]).
+    pub(crate) fn size_hist(&mut self, cfg: &BenchCfg, execs_per_sec: f64) {
+        self.hist = cfg.new_hist(Some(execs_per_sec));
     }
 
     #[inline(always)]
-    /// Updates `self` with an elapsed time observation for the target function.
-    pub(crate) fn capture_data(&mut self, batch_latency: (FpSeconds, usize)) {
+    /// Updates `self` with an elapsed time observation for the target function. Returns `false` if the observation
+    /// was rejected (see [`HistOverflow::Reject`]).
+    pub(crate) fn capture_data(&mut self, batch_latency: (FpSeconds, usize)) -> bool {
         let (mean_latency, batch) = batch_latency;
         let mean_elapsed_u64 = self.recording_unit.value_from_fpsecs(mean_latency);
-        self.hist
-            .record_n(mean_elapsed_u64, batch as u64)
-            .expect("can't happen: histogram is auto-resizable");
+        if !self.record_hist(mean_elapsed_u64, batch as u64) {
+            return false;
+        }
+
+        self.add_moments((mean_latency * batch).as_f64());
+
+        if let Some(samples) = &mut self.samples {
+            samples.capture(mean_latency);
+        }
+        true
+    }
 
-        let total_elapsed_f64 = (mean_latency * batch).as_f64();
+    /// Adds an observed total elapsed time to the sums from which the mean, standard deviation, and `*_ln` statistics
+    /// are computed.
+    #[inline(always)]
+    fn add_moments(&mut self, total_elapsed_f64: f64) {
         self.sum += total_elapsed_f64;
         self.sum2 += total_elapsed_f64.powi(2);
 
-        if batch_latency.0 > FpSeconds::ZERO {
+        if total_elapsed_f64 > 0. {
             let ln = total_elapsed_f64.ln();
             self.n_nz += 1;
             self.sum_ln += ln;
@@ -121,6 +166,100 @@ impl BenchOut {
         }
     }
 
+    /// Records `count` observations of `value` in the histogram, applying [`Self::hist_overflow`] to values above the
+    /// highest trackable value. Returns `false` if the observations were rejected.
+    #[inline(always)]
+    fn record_hist(&mut self, value: u64, count: u64) -> bool {
+        let mut value = value;
+        if value > self.hist.high() {
+            match self.hist_overflow {
+                HistOverflow::Resize => (),
+                HistOverflow::Clamp => {
+                    self.run_info.out_of_range += count;
+                    value = self.hist.high();
+                }
+                HistOverflow::Reject => {
+                    self.run_info.out_of_range += count;
+                    return false;
+                }
+            }
+        }
+        self.hist
+            .record_n(value, count)
+            .expect("can't happen: value is in range or histogram is auto-resizable");
+        true
+    }
+
+    /// Merges the data of `other` into `self`, e.g., to combine the results of several runs of the same benchmark.
+    ///
+    /// - Latency observations are combined. If the recording units differ, the observations of `other` are converted
+    ///   to the recording unit of `self`, rounding to the nearest recording unit.
+    /// - Raw samples, if retained by both, are combined according to the retention policy of `self`, as if the
+    ///   latencies of `other` had been observed after those of `self`. Otherwise, raw samples are discarded.
+    /// - Run information is kept where `self` and `other` agree and cleared elsewhere, except for the counts of
+    ///   out-of-range and overhead-floored observations, which are added (see [`RunInfo::out_of_range`] and
+    ///   [`RunInfo::overhead_floored`]).
+    /// - The histogram of `self` grows as needed to track the observations of `other`, regardless of
+    ///   [`BenchCfg::hist_overflow`].
+    ///
+    /// See also [`RunSet`](crate::RunSet), which keeps track of the individual runs.
+    pub fn merge(&mut self, other: &BenchOut) {
+        self.absorb(other);
+        match (&mut self.samples, &other.samples) {
+            (Some(samples), Some(other_samples)) => samples.merge(other_samples),
+            _ => self.samples = None,
+        }
+        self.run_info.merge(&other.run_info);
+    }
+
+    /// Adds the latency observations of `other` to those of `self`, converting them to the recording unit of `self`
+    /// if needed. Neither the raw samples nor the run information of `other` are added.
+    pub(crate) fn absorb(&mut self, other: &BenchOut) {
+        let auto = self.hist.is_auto_resize();
+        self.hist.auto(true);
+        if self.recording_unit == other.recording_unit {
+            self.hist
+                .add(&other.hist)
+                .expect("can't happen: histogram is auto-resizable");
+        } else {
+            for (value, count) in other.iter_with_counts() {
+                self.hist
+                    .record_n(self.recording_unit.value_from_fpsecs(value), count as u64)
+                    .expect("can't happen: histogram is auto-resizable");
+            }
+        }
+        self.hist.auto(auto);
+        self.sum += other.sum;
+        self.sum2 += other.sum2;
+        self.n_nz += other.n_nz;
+        self.sum_ln += other.sum_ln;
+        self.sum2_ln += other.sum2_ln;
+    }
+
+    /// Updates `self` with a latency observation and, like HdrHistogram's `record_correct`, with the synthetic
+    /// observations `latency - k * interval`, for `k >= 1`, that are at least `interval`. These stand for the
+    /// invocations that would have been issued every `interval` while the observed one was executing.
+    ///
+    /// The synthetic observations are treated as actual ones by all statistics, including [`Self::n`], the
+    /// percentiles, [`Self::mean`], [`Self::stdev`], and the `*_ln` statistics, but are not added to the raw samples,
+    /// which only hold the latencies actually observed. At most [`MAX_CORRECTIONS`] of them, the largest, are recorded
+    /// per observation, and none if the observation itself was rejected.
+    pub(crate) fn capture_corrected(&mut self, latency: FpSeconds, interval: FpSeconds) {
+        if !self.capture_data((latency, 1)) || interval <= FpSeconds::ZERO {
+            return;
+        }
+        let corrections = ((latency.as_f64() / interval.as_f64()) as u64)
+            .saturating_sub(1)
+            .min(MAX_CORRECTIONS);
+        for k in 1..=corrections {
+            let missing = latency.as_f64() - k as f64 * interval.as_f64();
+            let value = self.recording_unit.value_from_fpsecs(FpSeconds(missing));
+            if self.record_hist(value, 1) {
+                self.add_moments(missing);
+            }
+        }
+    }
+
     /// Returns all the latency data collected as an iterator of value-count pairs, where each value is a latency
     /// measurement and each count is the number of occurences of the latency measurment.
     ///
@@ -142,11 +281,26 @@ impl BenchOut {
             .flatten()
     }
 
+    /// Information about the execution of the benchmark run that produced `self`.
+    pub fn run_info(&self) -> &RunInfo {
+        &self.run_info
+    }
+
+    /// Raw per-iteration latencies, if retained (see [`BenchCfg::with_sample_retention`]).
+    pub fn samples(&self) -> Option<&Samples> {
+        self.samples.as_ref()
+    }
+
     /// Latency unit used in data collection.
     pub fn recording_unit(&self) -> LatencyUnit {
         self.recording_unit
     }
 
+    /// Handling of latencies above the highest trackable value of the histogram (see [`BenchCfg::hist_overflow`]).
+    pub fn hist_overflow(&self) -> HistOverflow {
+        self.hist_overflow
+    }
+
     /// Number of observations (sample size) for a function, as an integer.
     #[inline(always)]
     pub fn n(&self) -> u64 {
@@ -354,6 +508,63 @@ impl BenchOut {
         student_1samp_test(&moments, ln_mu0, alt_hyp, alpha).expect("`number of non-zero observations <= 1` or `self.stdev_ln() == 0` or `alpha` not in open interval `(0, 1)`")
     }
 
+    /// Ratio of the effective sample size to the actual sample size, estimated from the retained raw latencies
+    /// (see [`Samples::ess_ratio`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the raw latencies were not retained (see [`BenchCfg::with_sample_retention`]).
+    pub fn ess_ratio(&self) -> f64 {
+        self.samples
+            .as_ref()
+            .expect("raw latencies not retained, see `BenchCfg::with_sample_retention`")
+            .ess_ratio()
+    }
+
+    /// Same as [`Self::student_ln_t`] but with the number of non-zero observations replaced by the effective sample
+    /// size, which accounts for lag-1 autocorrelation of the observations (see [`Self::ess_ratio`]).
+    ///
+    /// Autocorrelated observations carry less information than independent ones, so the unadjusted statistic
+    /// overstates the evidence against the null hypothesis.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - The raw latencies were not retained.
+    /// - `number of non-zero observations <= 1`.
+    /// - `self.stdev_ln() == 0`.
+    pub fn student_ln_t_ess(&self, ln_mu0: f64) -> f64 {
+        self.student_ln_t(ln_mu0) * self.ess_ratio().sqrt()
+    }
+
+    /// Degrees of freedom for [`Self::student_ln_t_ess`], based on the effective sample size, with a minimum of `1`.
+    ///
+    /// The effective sample size of few, strongly autocorrelated observations can be `2` or less, for which the
+    /// degrees of freedom would not be positive; the minimum keeps the test defined, and conservative.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the raw latencies were not retained.
+    pub fn student_ln_df_ess(&self) -> f64 {
+        (self.n_nz as f64 * self.ess_ratio() - 1.).max(1.)
+    }
+
+    /// p-value of the one-sample t-test based on [`Self::student_ln_t_ess`] and [`Self::student_ln_df_ess`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - The raw latencies were not retained.
+    /// - `number of non-zero observations <= 1`.
+    /// - `self.stdev_ln() == 0`.
+    pub fn student_ln_p_ess(&self, ln_mu0: f64, alt_hyp: AltHyp) -> f64 {
+        t_to_p(
+            self.student_ln_t_ess(ln_mu0),
+            self.student_ln_df_ess(),
+            alt_hyp,
+        )
+    }
+
     #[cfg(feature = "_test_support")]
     #[inline(always)]
     /// Reference to the raw HDR histogram. Gated by feature **"_test_support"**.
@@ -743,4 +954,89 @@ mod test {
         out.reset();
         assert_eq!(out.n(), 0);
     }
+
+    #[test]
+    fn test_student_ess() {
+        use crate::SampleRetention;
+
+        // Slowly varying log-latencies are strongly autocorrelated.
+        let src =
+            || (0..1_000).map(|i| FpSeconds::from_micros(10) * (1. + 0.1 * (i as f64 / 50.).sin()));
+        let ln_mu0 = FpSeconds::from_micros(10).ln() + 0.01;
+
+        let cfg = BenchCfg::default().with_sample_retention(SampleRetention::Bounded(1_000));
+        let out = BenchOut::from_iter(&cfg, src());
+        let samples = out.samples().unwrap();
+        assert_eq!(1_000, samples.len());
+        assert!(samples.lag1_autocorr_ln().unwrap() > 0.9);
+
+        let ratio = out.ess_ratio();
+        assert!(ratio < 0.1, "ratio={ratio}");
+        rel_approx_eq!(
+            out.student_ln_t(ln_mu0) * ratio.sqrt(),
+            out.student_ln_t_ess(ln_mu0),
+            1e-12
+        );
+        rel_approx_eq!(1_000. * ratio - 1., out.student_ln_df_ess(), 1e-12);
+        assert!(out.student_ln_p_ess(ln_mu0, AltHyp::Ne) > out.student_ln_p(ln_mu0, AltHyp::Ne));
+
+        // Few strongly autocorrelated observations: the degrees of freedom are clamped.
+        let out = BenchOut::from_iter(&cfg, src().take(20));
+        assert!(20. * out.ess_ratio() - 1. < 1.);
+        assert_eq!(1., out.student_ln_df_ess());
+        let p = out.student_ln_p_ess(ln_mu0, AltHyp::Ne);
+        assert!(0. < p && p <= 1., "p={p}");
+
+        let out = BenchOut::from_iter(&BenchCfg::default(), src());
+        assert!(out.samples().is_none());
+        let result = std::panic::catch_unwind(|| out.student_ln_t_ess(ln_mu0));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_hist_overflow() {
+        use crate::HistOverflow;
+
+        let data = || {
+            [
+                (FpSeconds::from_micros(500), 2),
+                (FpSeconds::from_millis(5), 3),
+            ]
+            .into_iter()
+        };
+        let cfg = |overflow| {
+            BenchCfg::default()
+                .with_hist_bounds(FpSeconds::ZERO, FpSeconds::from_millis(1))
+                .with_hist_overflow(overflow)
+        };
+        let max = |out: &BenchOut| out.iter().last().unwrap();
+
+        let resized = BenchOut::from_iter_with_counts(&cfg(HistOverflow::Resize), data());
+        assert_eq!(5, resized.n());
+        assert_eq!(0, resized.run_info().out_of_range());
+        rel_approx_eq_fpsecs!(FpSeconds::from_millis(5), max(&resized), 0.001);
+
+        // Clamped latencies are recorded at the highest trackable value, but the sums use the actual latencies.
+        let mut clamped = BenchOut::from_iter_with_counts(&cfg(HistOverflow::Clamp), data());
+        assert_eq!(HistOverflow::Clamp, clamped.hist_overflow());
+        assert_eq!(5, clamped.n());
+        assert_eq!(3, clamped.run_info().out_of_range());
+        assert_eq!(1_000_000, clamped.hist.high());
+        rel_approx_eq_fpsecs!(FpSeconds::from_millis(1), max(&clamped), 0.001);
+        rel_approx_eq!(0.016, clamped.sum, 1e-12);
+
+        let rejected = BenchOut::from_iter_with_counts(&cfg(HistOverflow::Reject), data());
+        assert_eq!(2, rejected.n());
+        assert_eq!(1, rejected.n_nz());
+        assert_eq!(3, rejected.run_info().out_of_range());
+        rel_approx_eq!(0.001, rejected.sum, 1e-12);
+
+        // Merging never clamps or rejects; the counts of out-of-range observations add up.
+        clamped.merge(&resized);
+        clamped.merge(&rejected);
+        assert_eq!(12, clamped.n());
+        assert_eq!(6, clamped.run_info().out_of_range());
+        rel_approx_eq_fpsecs!(FpSeconds::from_millis(5), max(&clamped), 0.001);
+        assert!(!clamped.hist.is_auto_resize());
+    }
 }
@@ -2,9 +2,10 @@
 
 use crate::{
     BenchCfg, BenchOut, RunLength,
-    multi::{self, LatencySrc1, LatencySrc1b},
-    status::Status,
+    multi::{self, LatencySrc1, LatencySrc1b, LatencySrc1s, LatencySrc1sb},
+    status::{DefaultStatus, NoStatus, Status},
 };
+use std::io::stderr;
 
 /// Repeatedly executes closure `f`, collects the resulting latency data in a [`BenchOut`] object, and
 /// *optionally* reports progress status during benchmark execution.
@@ -12,6 +13,10 @@ use crate::{
 /// Prior to data collection, the benchmark is "warmed-up" by repeatedly executing `f` for
 /// `cfg.warmup_millis` milliseconds.
 ///
+/// If the [`BenchCfg::cancel_token`] is cancelled, e.g., from a status closure, the run stops before the next
+/// execution of `f` and the data collected so far is returned, marked as aborted
+/// (see [`CancelToken`](crate::CancelToken)).
+///
 /// Arguments:
 /// - `cfg` - bench configuration used to run the benchmark.
 /// - `f` - benchmark target closure.
@@ -101,6 +106,15 @@ pub(crate) fn batch_run_length(run_length: RunLength, batch: Option<usize>) -> R
             RunLength::CountWithTimeout(count, time) => {
                 RunLength::CountWithTimeout(count.div_ceil(batch), time)
             }
+            RunLength::Precision {
+                target,
+                max_count,
+                max_time,
+            } => RunLength::Precision {
+                target,
+                max_count: max_count.map(|count| count.div_ceil(batch)),
+                max_time,
+            },
         },
     }
 }
@@ -181,6 +195,229 @@ pub fn bench_run_with_status_arg_cfg_b(
     multi::bench_run_with_status_arg_cfg(cfg, LatencySrc1b::new(f, batch), run_length).into()
 }
 
+/// Similar to [`bench_run_x_b`] but chooses the batch size automatically.
+///
+/// The batch size is chosen with [`BenchCfg::auto_batch`] and is available from
+/// [`RunInfo::batch`](crate::RunInfo::batch). The iteration rate estimated to choose it is reused by the run rather
+/// than estimated again, whereas the timer overhead, if configured, is calibrated with the chosen batch size.
+pub fn bench_run_x_auto_b<'a, S: Status<'a>>(
+    cfg: &BenchCfg,
+    mut f: impl FnMut(),
+    run_length: RunLength,
+    s: S,
+) -> BenchOut {
+    let probe = cfg.batch_probe(&mut LatencySrc1::new(&mut f), run_length);
+    let batch = probe.batch;
+    let run_length = batch_run_length(run_length, Some(batch));
+    let mut out: BenchOut =
+        multi::bench_run_probed(cfg, LatencySrc1b::new(f, batch), run_length, s, &probe).into();
+    out.run_info.batch = Some(batch);
+    out
+}
+
+/// Similar to [`bench_run_b`] but chooses the batch size automatically.
+///
+/// The batch size is chosen with [`BenchCfg::auto_batch`] and is available from
+/// [`RunInfo::batch`](crate::RunInfo::batch).
+pub fn bench_run_auto_b(f: impl FnMut(), run_length: RunLength) -> BenchOut {
+    let cfg = BenchCfg::default();
+    bench_run_arg_cfg_auto_b(&cfg, f, run_length)
+}
+
+/// Similar to [`bench_run_arg_cfg_b`] but chooses the batch size automatically.
+///
+/// The batch size is chosen with [`BenchCfg::auto_batch`] and is available from
+/// [`RunInfo::batch`](crate::RunInfo::batch).
+pub fn bench_run_arg_cfg_auto_b(
+    cfg: &BenchCfg,
+    f: impl FnMut(),
+    run_length: RunLength,
+) -> BenchOut {
+    bench_run_x_auto_b(cfg, f, run_length, NoStatus)
+}
+
+/// Similar to [`bench_run_with_status_b`] but chooses the batch size automatically.
+///
+/// The batch size is chosen with [`BenchCfg::auto_batch`] and is available from
+/// [`RunInfo::batch`](crate::RunInfo::batch).
+pub fn bench_run_with_status_auto_b(f: impl FnMut(), run_length: RunLength) -> BenchOut {
+    let cfg = BenchCfg::default();
+    bench_run_with_status_arg_cfg_auto_b(&cfg, f, run_length)
+}
+
+/// Similar to [`bench_run_with_status_arg_cfg_b`] but chooses the batch size automatically.
+///
+/// The batch size is chosen with [`BenchCfg::auto_batch`] and is available from
+/// [`RunInfo::batch`](crate::RunInfo::batch).
+pub fn bench_run_with_status_arg_cfg_auto_b(
+    cfg: &BenchCfg,
+    f: impl FnMut(),
+    run_length: RunLength,
+) -> BenchOut {
+    let mut w = stderr();
+    let s = DefaultStatus::new(
+        &mut w,
+        "Warming up".to_owned(),
+        "Executing bench_run".to_owned(),
+    );
+    bench_run_x_auto_b(cfg, f, run_length, s)
+}
+
+/// Similar to [`bench_run_x`] but benchmarks `routine` on inputs created by `setup`.
+///
+/// In each iteration, an input is created with `setup` and passed to `routine`. Only the invocation of `routine` is
+/// timed; neither `setup` nor the dropping of the output of `routine` are included in the latencies, but the time
+/// spent in `setup` counts towards the run length.
+pub fn bench_run_setup_x<'a, S: Status<'a>, I, O>(
+    cfg: &BenchCfg,
+    setup: impl FnMut() -> I,
+    routine: impl FnMut(I) -> O,
+    run_length: RunLength,
+    s: S,
+) -> BenchOut {
+    multi::bench_run_x(cfg, LatencySrc1s::new(setup, routine), run_length, s).into()
+}
+
+/// Similar to [`bench_run`] but benchmarks `routine` on inputs created by `setup`.
+///
+/// In each iteration, an input is created with `setup` and passed to `routine`. Only the invocation of `routine` is
+/// timed; neither `setup` nor the dropping of the output of `routine` are included in the latencies, but the time
+/// spent in `setup` counts towards the run length.
+pub fn bench_run_setup<I, O>(
+    setup: impl FnMut() -> I,
+    routine: impl FnMut(I) -> O,
+    run_length: RunLength,
+) -> BenchOut {
+    multi::bench_run(LatencySrc1s::new(setup, routine), run_length).into()
+}
+
+/// Similar to [`bench_run_arg_cfg`] but benchmarks `routine` on inputs created by `setup`.
+///
+/// In each iteration, an input is created with `setup` and passed to `routine`. Only the invocation of `routine` is
+/// timed; neither `setup` nor the dropping of the output of `routine` are included in the latencies, but the time
+/// spent in `setup` counts towards the run length.
+pub fn bench_run_setup_arg_cfg<I, O>(
+    cfg: &BenchCfg,
+    setup: impl FnMut() -> I,
+    routine: impl FnMut(I) -> O,
+    run_length: RunLength,
+) -> BenchOut {
+    multi::bench_run_arg_cfg(cfg, LatencySrc1s::new(setup, routine), run_length).into()
+}
+
+/// Similar to [`bench_run_with_status`] but benchmarks `routine` on inputs created by `setup`.
+///
+/// In each iteration, an input is created with `setup` and passed to `routine`. Only the invocation of `routine` is
+/// timed; neither `setup` nor the dropping of the output of `routine` are included in the latencies, but the time
+/// spent in `setup` counts towards the run length.
+pub fn bench_run_setup_with_status<I, O>(
+    setup: impl FnMut() -> I,
+    routine: impl FnMut(I) -> O,
+    run_length: RunLength,
+) -> BenchOut {
+    multi::bench_run_with_status(LatencySrc1s::new(setup, routine), run_length).into()
+}
+
+/// Similar to [`bench_run_with_status_arg_cfg`] but benchmarks `routine` on inputs created by `setup`.
+///
+/// In each iteration, an input is created with `setup` and passed to `routine`. Only the invocation of `routine` is
+/// timed; neither `setup` nor the dropping of the output of `routine` are included in the latencies, but the time
+/// spent in `setup` counts towards the run length.
+pub fn bench_run_setup_with_status_arg_cfg<I, O>(
+    cfg: &BenchCfg,
+    setup: impl FnMut() -> I,
+    routine: impl FnMut(I) -> O,
+    run_length: RunLength,
+) -> BenchOut {
+    multi::bench_run_with_status_arg_cfg(cfg, LatencySrc1s::new(setup, routine), run_length).into()
+}
+
+/// Similar to [`bench_run_setup_x`] but batches the executions of `routine` into groups of size `batch`,
+/// as in [`bench_run_x_b`].
+///
+/// Before each batch, `batch` inputs are created with `setup`. Only the invocations of `routine` are timed; neither
+/// `setup` nor the dropping of the outputs of `routine` are included in the latencies.
+pub fn bench_run_setup_x_b<'a, S: Status<'a>, I, O>(
+    cfg: &BenchCfg,
+    setup: impl FnMut() -> I,
+    routine: impl FnMut(I) -> O,
+    run_length: RunLength,
+    s: S,
+    batch: usize,
+) -> BenchOut {
+    let run_length = batch_run_length(run_length, Some(batch));
+    multi::bench_run_x(
+        cfg,
+        LatencySrc1sb::new(setup, routine, batch),
+        run_length,
+        s,
+    )
+    .into()
+}
+
+/// Similar to [`bench_run_setup`] but batches the executions of `routine` into groups of size `batch`,
+/// as in [`bench_run_b`].
+///
+/// Before each batch, `batch` inputs are created with `setup`. Only the invocations of `routine` are timed; neither
+/// `setup` nor the dropping of the outputs of `routine` are included in the latencies.
+pub fn bench_run_setup_b<I, O>(
+    setup: impl FnMut() -> I,
+    routine: impl FnMut(I) -> O,
+    run_length: RunLength,
+    batch: usize,
+) -> BenchOut {
+    let run_length = batch_run_length(run_length, Some(batch));
+    multi::bench_run(LatencySrc1sb::new(setup, routine, batch), run_length).into()
+}
+
+/// Similar to [`bench_run_setup_arg_cfg`] but batches the executions of `routine` into groups of size `batch`,
+/// as in [`bench_run_arg_cfg_b`].
+///
+/// Before each batch, `batch` inputs are created with `setup`. Only the invocations of `routine` are timed; neither
+/// `setup` nor the dropping of the outputs of `routine` are included in the latencies.
+pub fn bench_run_setup_arg_cfg_b<I, O>(
+    cfg: &BenchCfg,
+    setup: impl FnMut() -> I,
+    routine: impl FnMut(I) -> O,
+    run_length: RunLength,
+    batch: usize,
+) -> BenchOut {
+    let run_length = batch_run_length(run_length, Some(batch));
+    multi::bench_run_arg_cfg(cfg, LatencySrc1sb::new(setup, routine, batch), run_length).into()
+}
+
+/// Similar to [`bench_run_setup_with_status`] but batches the executions of `routine` into groups of size `batch`,
+/// as in [`bench_run_with_status_b`].
+///
+/// Before each batch, `batch` inputs are created with `setup`. Only the invocations of `routine` are timed; neither
+/// `setup` nor the dropping of the outputs of `routine` are included in the latencies.
+pub fn bench_run_setup_with_status_b<I, O>(
+    setup: impl FnMut() -> I,
+    routine: impl FnMut(I) -> O,
+    run_length: RunLength,
+    batch: usize,
+) -> BenchOut {
+    let run_length = batch_run_length(run_length, Some(batch));
+    multi::bench_run_with_status(LatencySrc1sb::new(setup, routine, batch), run_length).into()
+}
+
+/// Similar to [`bench_run_setup_with_status_arg_cfg`] but batches the executions of `routine` into groups of size `batch`,
+/// as in [`bench_run_with_status_arg_cfg_b`].
+///
+/// Before each batch, `batch` inputs are created with `setup`. Only the invocations of `routine` are timed; neither
+/// `setup` nor the dropping of the outputs of `routine` are included in the latencies.
+pub fn bench_run_setup_with_status_arg_cfg_b<I, O>(
+    cfg: &BenchCfg,
+    setup: impl FnMut() -> I,
+    routine: impl FnMut(I) -> O,
+    run_length: RunLength,
+    batch: usize,
+) -> BenchOut {
+    let run_length = batch_run_length(run_length, Some(batch));
+    multi::bench_run_with_status_arg_cfg(cfg, LatencySrc1sb::new(setup, routine, batch), run_length)
+        .into()
+}
+
 #[cfg(feature = "_test_support")]
 pub fn bench_run_x_o<'a, S: Status<'a>>(
     cfg: &BenchCfg,
@@ -297,4 +534,33 @@ mod simple_tests {
         let out = bench_run_x(&cfg, || (), RunLength::Count(5), status);
         assert_eq!(out.n(), 5);
     }
+
+    #[test]
+    fn test_bench_run_auto_b() {
+        let cfg = quick_cfg();
+        let out = bench_run_arg_cfg_auto_b(&cfg, || (), RunLength::Count(1000));
+        let batch = out.run_info().batch().expect("batch should be recorded");
+        assert!(batch >= 1);
+        assert_eq!(out.n(), 1000_usize.div_ceil(batch) as u64 * batch as u64);
+
+        let out = bench_run_arg_cfg_b(&cfg, || (), RunLength::Count(10), 2);
+        assert_eq!(None, out.run_info().batch());
+    }
+
+    #[test]
+    fn test_bench_run_setup() {
+        let cfg = quick_cfg();
+        let out =
+            bench_run_setup_arg_cfg(&cfg, || vec![3_u8; 16], |v| v.len(), RunLength::Count(5));
+        assert_eq!(out.n(), 5);
+
+        let out = bench_run_setup_arg_cfg_b(
+            &cfg,
+            || vec![3_u8; 16],
+            |v| v.len(),
+            RunLength::Count(20),
+            4,
+        );
+        assert_eq!(out.n(), 20);
+    }
 }
@@ -0,0 +1,284 @@
+//! Distribution-free bootstrap confidence intervals for latency quantiles.
+//!
+//! Unlike the `student_*` and `welch_*` methods, these confidence intervals make no assumption about the shape of the
+//! latency distribution, so they remain valid for multi-modal latencies (e.g., cache hits and misses).
+//!
+//! The bootstrap resamples are drawn from the value/count pairs recorded in the [`BenchOut`] histogram. For a quantile
+//! statistic, only the distribution of the relevant order statistic of a resample matters, and that distribution is
+//! computed exactly from the recorded counts. Each resample is then a single draw from it, which is equivalent to
+//! resampling all `n` observations but at a cost independent of `n`.
+
+use crate::{BenchOut, Comp, FpSeconds, rng::SplitMix64, stats_support::beta_reg};
+use basic_stats::core::Ci;
+
+/// Configuration of the number of resamples and the random seed for bootstrap confidence intervals.
+///
+/// Results are reproducible: the same configuration applied to the same data always yields the same confidence
+/// interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BootstrapCfg {
+    resamples: usize,
+    seed: u64,
+}
+
+impl BootstrapCfg {
+    /// Default number of resamples.
+    pub const DEFAULT_RESAMPLES: usize = 10_000;
+    /// Default random seed.
+    pub const DEFAULT_SEED: u64 = 0;
+
+    /// Number of resamples.
+    pub fn resamples(&self) -> usize {
+        self.resamples
+    }
+
+    /// Random seed.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Sets the number of resamples.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `resamples` is zero.
+    pub fn with_resamples(mut self, resamples: usize) -> Self {
+        assert!(resamples > 0, "`resamples` must be positive");
+        self.resamples = resamples;
+        self
+    }
+
+    /// Sets the random seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Default for BootstrapCfg {
+    fn default() -> Self {
+        Self {
+            resamples: Self::DEFAULT_RESAMPLES,
+            seed: Self::DEFAULT_SEED,
+        }
+    }
+}
+
+/// Bootstrap distribution of the quantile `q` of a [`BenchOut`]'s latencies.
+///
+/// The sample quantile is the order statistic of rank `j = max(1, ceil(q * n))`. The `j`-th order statistic of a
+/// resample is at most the `i`-th distinct recorded value if and only if at least `j` of the `n` resampled
+/// observations are at most that value, which has probability `I(F_i; j, n - j + 1)`, where `F_i` is the empirical
+/// cumulative distribution function at the `i`-th value and `I` is the regularized incomplete beta function.
+struct QuantileDist {
+    values: Vec<FpSeconds>,
+    cdf: Vec<f64>,
+}
+
+impl QuantileDist {
+    fn new(out: &BenchOut, q: f64) -> Self {
+        assert!((0. ..=1.).contains(&q), "`q` not in interval `[0, 1]`");
+        let n = out.n();
+        assert!(n > 0, "number of observations is zero");
+        let j = ((q * n as f64).ceil() as u64).clamp(1, n);
+
+        let mut cum = 0;
+        let (values, cdf) = out
+            .iter_with_counts()
+            .map(|(value, count)| {
+                cum += count as u64;
+                let p = if cum >= n {
+                    1.
+                } else {
+                    beta_reg(j as f64, (n - j + 1) as f64, cum as f64 / n as f64)
+                };
+                (value, p)
+            })
+            .unzip();
+        Self { values, cdf }
+    }
+
+    fn draw(&self, rng: &mut SplitMix64) -> FpSeconds {
+        let u = rng.next_f64();
+        let i = self.cdf.partition_point(|&p| p <= u);
+        self.values[i.min(self.values.len() - 1)]
+    }
+}
+
+/// Percentile confidence interval with confidence level `(1 - alpha)` from bootstrap `draws`.
+fn percentile_ci(mut draws: Vec<f64>, alpha: f64) -> Ci {
+    assert!(
+        0. < alpha && alpha < 1.,
+        "`alpha` not in open interval `(0, 1)`"
+    );
+    draws.sort_by(f64::total_cmp);
+    let b = draws.len();
+    let low = ((alpha / 2. * b as f64).floor() as usize).min(b - 1);
+    let high = (((1. - alpha / 2.) * b as f64).ceil() as usize).clamp(1, b) - 1;
+    Ci(draws[low], draws[high])
+}
+
+impl BenchOut {
+    /// Bootstrap confidence interval for the quantile `q` of the latencies (e.g., `0.5` for the median, `0.99` for
+    /// p99), with confidence level `(1 - alpha)`.
+    ///
+    /// Makes no assumption about the shape of the latency distribution. Note that the interval can't extend beyond
+    /// the recorded latencies, so it is unreliable for extreme quantiles with few observations beyond them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.n() == 0`.
+    /// - `q` not in interval `[0, 1]`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    pub fn bootstrap_quantile_ci(
+        &self,
+        q: f64,
+        alpha: f64,
+        bcfg: &BootstrapCfg,
+    ) -> (FpSeconds, FpSeconds) {
+        let dist = QuantileDist::new(self, q);
+        let mut rng = SplitMix64::new(bcfg.seed);
+        let draws = (0..bcfg.resamples)
+            .map(|_| dist.draw(&mut rng).as_f64())
+            .collect();
+        let Ci(low, high) = percentile_ci(draws, alpha);
+        (FpSeconds(low), FpSeconds(high))
+    }
+}
+
+impl Comp<'_> {
+    fn bootstrap_quantile_draws(
+        &self,
+        q: f64,
+        bcfg: &BootstrapCfg,
+    ) -> impl Iterator<Item = (FpSeconds, FpSeconds)> {
+        let dist1 = QuantileDist::new(self.0, q);
+        let dist2 = QuantileDist::new(self.1, q);
+        let mut rng = SplitMix64::new(bcfg.seed);
+        (0..bcfg.resamples).map(move |_| (dist1.draw(&mut rng), dist2.draw(&mut rng)))
+    }
+
+    /// Bootstrap confidence interval for `quantile(latency(f1), q) / quantile(latency(f2), q)`,
+    /// with confidence level `(1 - alpha)`.
+    ///
+    /// Makes no assumption about the shape of the latency distributions. Resamples with a zero `f2` quantile yield
+    /// an infinite ratio, which may make the upper bound infinite.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.out_f1().n() == 0`.
+    /// - `self.out_f2().n() == 0`.
+    /// - `q` not in interval `[0, 1]`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    pub fn bootstrap_ratio_quantiles_ci(&self, q: f64, alpha: f64, bcfg: &BootstrapCfg) -> Ci {
+        let draws = self
+            .bootstrap_quantile_draws(q, bcfg)
+            .map(|(x1, x2)| x1.as_f64() / x2.as_f64())
+            .collect();
+        percentile_ci(draws, alpha)
+    }
+
+    /// Bootstrap confidence interval for `quantile(latency(f1), q) - quantile(latency(f2), q)`,
+    /// with confidence level `(1 - alpha)`.
+    ///
+    /// Makes no assumption about the shape of the latency distributions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.out_f1().n() == 0`.
+    /// - `self.out_f2().n() == 0`.
+    /// - `q` not in interval `[0, 1]`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    pub fn bootstrap_diff_quantiles_ci(
+        &self,
+        q: f64,
+        alpha: f64,
+        bcfg: &BootstrapCfg,
+    ) -> (FpSeconds, FpSeconds) {
+        let draws = self
+            .bootstrap_quantile_draws(q, bcfg)
+            .map(|(x1, x2)| (x1 - x2).as_f64())
+            .collect();
+        let Ci(low, high) = percentile_ci(draws, alpha);
+        (FpSeconds(low), FpSeconds(high))
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- bootstrap::test --nocapture
+mod test {
+    use super::*;
+    use crate::{
+        BenchCfg,
+        test_support::{LO_STDEV_LN, lognormal_samp},
+    };
+
+    const ALPHA: f64 = 0.05;
+
+    /// Bimodal latencies: 70% "hits" around 1µs and 30% "misses" around 50µs.
+    fn bimodal_out(scale: f64) -> BenchOut {
+        let cfg = BenchCfg::default();
+        let hits = lognormal_samp((1e-6 * scale).ln(), *LO_STDEV_LN, 7_000);
+        let misses = lognormal_samp((50e-6 * scale).ln(), *LO_STDEV_LN, 3_000);
+        BenchOut::from_iter(&cfg, hits.chain(misses))
+    }
+
+    #[test]
+    fn test_bootstrap_quantile_ci() {
+        let out = bimodal_out(1.);
+        let bcfg = BootstrapCfg::default().with_resamples(2_000).with_seed(7);
+
+        // The histogram's quantiles may differ slightly from the order statistics used by the bootstrap.
+        for (q, value) in [(0.5, out.summary().median), (0.99, out.summary().p99)] {
+            let (low, high) = out.bootstrap_quantile_ci(q, ALPHA, &bcfg);
+            println!("q={q}: ({low:?}, {high:?}), value={value:?}");
+            assert!(low <= high);
+            assert!(low * 0.99 <= value && value <= high * 1.01);
+        }
+
+        // Reproducible with the same seed.
+        assert_eq!(
+            out.bootstrap_quantile_ci(0.999, ALPHA, &bcfg),
+            out.bootstrap_quantile_ci(0.999, ALPHA, &bcfg)
+        );
+
+        // Wider with a higher confidence level.
+        let (low95, high95) = out.bootstrap_quantile_ci(0.99, ALPHA, &bcfg);
+        let (low99, high99) = out.bootstrap_quantile_ci(0.99, 0.01, &bcfg);
+        assert!(low99 <= low95 && high95 <= high99);
+    }
+
+    #[test]
+    fn test_bootstrap_comp_ci() {
+        let out1 = bimodal_out(2.);
+        let out2 = bimodal_out(1.);
+        let comp = Comp::new(&out1, &out2);
+        let bcfg = BootstrapCfg::default().with_resamples(2_000);
+
+        for q in [0.5, 0.99] {
+            let ci = comp.bootstrap_ratio_quantiles_ci(q, ALPHA, &bcfg);
+            println!("q={q}: ratio ci={ci:?}");
+            assert!(ci.0 <= 2. && 2. <= ci.1, "q={q}: {ci:?}");
+
+            let (low, high) = comp.bootstrap_diff_quantiles_ci(q, ALPHA, &bcfg);
+            println!("q={q}: diff ci=({low:?}, {high:?})");
+            assert!(low > FpSeconds::ZERO && low <= high);
+        }
+
+        let same = Comp::new(&out2, &out2);
+        let ci = same.bootstrap_ratio_quantiles_ci(0.5, ALPHA, &bcfg);
+        assert!(ci.0 <= 1. && 1. <= ci.1, "{ci:?}");
+    }
+
+    #[test]
+    fn test_percentile_ci() {
+        let draws = (0..1000).map(|i| i as f64).collect();
+        let Ci(low, high) = percentile_ci(draws, 0.1);
+        assert_eq!(50., low);
+        assert_eq!(949., high);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{BenchOut, FpSeconds};
 use basic_stats::{
-    core::{AltHyp, Ci, HypTestResult, PositionWrtCi, SampleMoments},
+    core::{AcceptedHyp, AltHyp, Ci, HypTestResult, PositionWrtCi, SampleMoments},
     normal::{welch_ci, welch_df, welch_p, welch_t, welch_test},
 };
 
@@ -260,6 +260,50 @@ impl<'a> Comp<'a> {
         ).expect("`number of non-zero observations <= 1` for either sample, `both standard deviations == 0`, or `alpha` not in open interval `(0, 1)`")
     }
 
+    /// Welch two one-sided tests (TOST) of the hypothesis that
+    /// `median(latency(f1)) / median(latency(f2))` lies within the equivalence `margin`,
+    /// with significance level `alpha` for each one-sided test.
+    ///
+    /// The null hypothesis is that the ratio is at most `margin.low()` or at least `margin.high()`; the alternative
+    /// hypothesis is that it is strictly between them. Each bound is tested with a one-sided Welch test on
+    /// `mean(ln(latency(f1))) - mean(ln(latency(f2)))`, and equivalence is accepted only if both tests reject.
+    /// This is the same as checking that [`Comp::welch_ratio_ci`] with confidence level `(1 - 2 * alpha)` lies
+    /// within the margin.
+    ///
+    /// Assumes that both `latency(f1)` and `latency(f2)` are approximately log-normal.
+    /// This assumption is widely supported by performance analysis theory and empirical data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.out_f1().n_nz <= 1`.
+    /// - `self.out_f2().n_nz <= 1`.
+    /// - `self.out_f1().stdev_ln() == 0` and `self.out_f2().stdev_ln() == 0`.
+    /// - `alpha` not in open interval `(0, 0.5)`.
+    pub fn welch_tost(&self, margin: EquivMargin, alpha: f64) -> TostResult {
+        assert!(
+            0. < alpha && alpha < 0.5,
+            "`alpha` not in open interval `(0, 0.5)`"
+        );
+        let p_low = if margin.low > 0. {
+            self.welch_ln_p(margin.low.ln(), AltHyp::Gt)
+        } else {
+            0.
+        };
+        let p_high = if margin.high.is_finite() {
+            self.welch_ln_p(margin.high.ln(), AltHyp::Lt)
+        } else {
+            0.
+        };
+        TostResult {
+            margin,
+            alpha,
+            p_low,
+            p_high,
+            ratio_ci: self.welch_ratio_ci(2. * alpha),
+        }
+    }
+
     #[cfg(feature = "_experimental")]
     /// Wilcoxon rank sum struct.
     fn rank_sum(&self) -> RankSum {
@@ -336,6 +380,107 @@ impl<'a> Comp<'a> {
     }
 }
 
+/// Equivalence margin for the ratio `median(latency(f1)) / median(latency(f2))`, used by [`Comp::welch_tost`].
+///
+/// The ratio is considered equivalent to `1` if it is strictly between [`EquivMargin::low`] and
+/// [`EquivMargin::high`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EquivMargin {
+    low: f64,
+    high: f64,
+}
+
+impl EquivMargin {
+    /// Margin with ratio bounds `low` and `high`. Use `low == 0` for a one-sided (non-inferiority) margin, e.g.,
+    /// `EquivMargin::new(0., 1.03)` for "`f1` is not slower than `f2` by more than 3%".
+    ///
+    /// # Panics
+    ///
+    /// Panics if `low` is negative or `low >= high`.
+    pub fn new(low: f64, high: f64) -> Self {
+        assert!(low >= 0., "`low` must not be negative");
+        assert!(low < high, "`low` must be less than `high`");
+        Self { low, high }
+    }
+
+    /// Margin that is symmetric on the logarithmic scale, with ratio bounds `1 / high` and `high`,
+    /// e.g., `EquivMargin::symmetric(1.03)` for "within 3% of each other".
+    ///
+    /// # Panics
+    ///
+    /// Panics if `high <= 1`.
+    pub fn symmetric(high: f64) -> Self {
+        assert!(high > 1., "`high` must be greater than 1");
+        Self::new(1. / high, high)
+    }
+
+    /// Lower bound of the ratio.
+    pub fn low(&self) -> f64 {
+        self.low
+    }
+
+    /// Upper bound of the ratio.
+    pub fn high(&self) -> f64 {
+        self.high
+    }
+}
+
+/// Result of the two one-sided tests (TOST) of [`Comp::welch_tost`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TostResult {
+    margin: EquivMargin,
+    alpha: f64,
+    p_low: f64,
+    p_high: f64,
+    ratio_ci: Ci,
+}
+
+impl TostResult {
+    /// Equivalence margin tested.
+    pub fn margin(&self) -> EquivMargin {
+        self.margin
+    }
+
+    /// Significance level of each one-sided test.
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// p-value of the one-sided test against the lower bound of the margin (`0` if the lower bound is `0`).
+    pub fn p_low(&self) -> f64 {
+        self.p_low
+    }
+
+    /// p-value of the one-sided test against the upper bound of the margin.
+    pub fn p_high(&self) -> f64 {
+        self.p_high
+    }
+
+    /// p-value of the equivalence test, i.e., the larger of the two one-sided p-values.
+    pub fn p(&self) -> f64 {
+        self.p_low.max(self.p_high)
+    }
+
+    /// Confidence interval for the ratio of medians with confidence level `(1 - 2 * alpha)`.
+    pub fn ratio_ci(&self) -> Ci {
+        self.ratio_ci
+    }
+
+    /// Accepted hypothesis: [`AcceptedHyp::Alt`] if equivalence is established, [`AcceptedHyp::Null`] otherwise.
+    pub fn accepted(&self) -> AcceptedHyp {
+        if self.p() < self.alpha {
+            AcceptedHyp::Alt
+        } else {
+            AcceptedHyp::Null
+        }
+    }
+
+    /// Whether equivalence is established, i.e., whether both one-sided tests reject their null hypotheses.
+    pub fn is_equivalent(&self) -> bool {
+        self.accepted() == AcceptedHyp::Alt
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "_test")]
 mod test {
@@ -383,6 +528,55 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_welch_tost() {
+        let cfg = BenchCfg::default();
+        let samp_size = 12_800;
+        let mu_b = 8.;
+        let median_ratio_a_b: f64 = 1.01;
+        let mu_a = mu_b + median_ratio_a_b.ln();
+        let out_a = lognormal_out(&cfg, mu_a, *LO_STDEV_LN, samp_size);
+        let out_b = lognormal_out(&cfg, mu_b, *LO_STDEV_LN, samp_size);
+        let comp = Comp::new(&out_a, &out_b);
+
+        let run_test = |margin: EquivMargin, equivalent: bool| {
+            let result = comp.welch_tost(margin, ALPHA);
+            println!("{result:?}");
+            assert_eq!(equivalent, result.is_equivalent());
+            assert_eq!(comp.welch_ratio_ci(2. * ALPHA), result.ratio_ci());
+            let Ci(low, high) = result.ratio_ci();
+            assert_eq!(equivalent, margin.low() < low && high < margin.high());
+            assert_eq!(result.p_low().max(result.p_high()), result.p());
+        };
+
+        run_test(EquivMargin::symmetric(1.03), true);
+        run_test(EquivMargin::new(0.99, 1.02), true);
+        run_test(EquivMargin::new(0., 1.03), true);
+        run_test(EquivMargin::symmetric(1.005), false);
+        run_test(EquivMargin::new(0., 1.005), false);
+
+        let result = comp.welch_tost(EquivMargin::new(0., 1.03), ALPHA);
+        assert_eq!(0., result.p_low());
+        approx_eq!(
+            comp.welch_ln_p(1.03_f64.ln(), AltHyp::Lt),
+            result.p_high(),
+            1e-15
+        );
+
+        // Comparison in the other direction is not within a non-inferiority margin of 0.995.
+        let comp_rev = Comp::new(&out_b, &out_a);
+        assert!(
+            !comp_rev
+                .welch_tost(EquivMargin::new(0.995, f64::INFINITY), ALPHA)
+                .is_equivalent()
+        );
+        assert!(
+            comp_rev
+                .welch_tost(EquivMargin::new(0.98, f64::INFINITY), ALPHA)
+                .is_equivalent()
+        );
+    }
+
     #[test]
     // cargo test --package bench_utils --lib --all-features -- comp::test::test_comp --exact --nocapture --include-ignored
     fn test_comp() {
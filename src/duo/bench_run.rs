@@ -1,10 +1,15 @@
 use crate::{
     BenchCfg, RunLength, batch_run_length,
     duo::DuoOut,
-    multi::{self, BenchOut, LatencySrc, LatencySrc1, LatencySrc1b, LatencySrc2, LatencySrc2b},
-    status::Status,
+    multi::{
+        self, BenchOut, LatencySrc, LatencySrc1, LatencySrc1b, LatencySrc2, LatencySrc2b,
+        LatencySrc2s, LatencySrc2sb,
+    },
+    status::{DefaultStatus, NoStatus, Status},
 };
-use std::thread;
+use std::{io::stderr, sync::Barrier, thread};
+
+use super::parallel_status::SharedStatus;
 
 /// Executes both closures `f1` and `f2` in each iteration, collects the resulting latency data in a [`BenchOut<2>`]
 /// object, and *optionally* reports progress status during benchmark execution. Closure executions are interleaved.
@@ -195,6 +200,284 @@ pub fn bench_run_with_status_arg_cfg_b(
     multi::bench_run_with_status_arg_cfg(cfg, LatencySrc2b::new(f1, f2, batch), run_length).into()
 }
 
+/// Similar to [`bench_run_x_b`] but chooses the batch size automatically.
+///
+/// The batch size is chosen with [`BenchCfg::auto_batch`] and is available from
+/// [`RunInfo::batch`](crate::RunInfo::batch). The iteration rate estimated to choose it is reused by the run rather
+/// than estimated again, whereas the timer overhead, if configured, is calibrated with the chosen batch size.
+pub fn bench_run_x_auto_b<'a, S: Status<'a>>(
+    cfg: &BenchCfg,
+    mut f1: impl FnMut(),
+    mut f2: impl FnMut(),
+    run_length: RunLength,
+    s: S,
+) -> DuoOut {
+    let probe = cfg.batch_probe(&mut LatencySrc2::new(&mut f1, &mut f2), run_length);
+    let batch = probe.batch;
+    let run_length = batch_run_length(run_length, Some(batch));
+    let mut out =
+        multi::bench_run_probed(cfg, LatencySrc2b::new(f1, f2, batch), run_length, s, &probe);
+    out.set_auto_batch(batch);
+    out
+}
+
+/// Similar to [`bench_run_b`] but chooses the batch size automatically.
+///
+/// The batch size is chosen with [`BenchCfg::auto_batch`] and is available from
+/// [`RunInfo::batch`](crate::RunInfo::batch).
+pub fn bench_run_auto_b(f1: impl FnMut(), f2: impl FnMut(), run_length: RunLength) -> DuoOut {
+    let cfg = BenchCfg::default();
+    bench_run_arg_cfg_auto_b(&cfg, f1, f2, run_length)
+}
+
+/// Similar to [`bench_run_arg_cfg_b`] but chooses the batch size automatically.
+///
+/// The batch size is chosen with [`BenchCfg::auto_batch`] and is available from
+/// [`RunInfo::batch`](crate::RunInfo::batch).
+pub fn bench_run_arg_cfg_auto_b(
+    cfg: &BenchCfg,
+    f1: impl FnMut(),
+    f2: impl FnMut(),
+    run_length: RunLength,
+) -> DuoOut {
+    bench_run_x_auto_b(cfg, f1, f2, run_length, NoStatus)
+}
+
+/// Similar to [`bench_run_with_status_b`] but chooses the batch size automatically.
+///
+/// The batch size is chosen with [`BenchCfg::auto_batch`] and is available from
+/// [`RunInfo::batch`](crate::RunInfo::batch).
+pub fn bench_run_with_status_auto_b(
+    f1: impl FnMut(),
+    f2: impl FnMut(),
+    run_length: RunLength,
+) -> DuoOut {
+    let cfg = BenchCfg::default();
+    bench_run_with_status_arg_cfg_auto_b(&cfg, f1, f2, run_length)
+}
+
+/// Similar to [`bench_run_with_status_arg_cfg_b`] but chooses the batch size automatically.
+///
+/// The batch size is chosen with [`BenchCfg::auto_batch`] and is available from
+/// [`RunInfo::batch`](crate::RunInfo::batch).
+pub fn bench_run_with_status_arg_cfg_auto_b(
+    cfg: &BenchCfg,
+    f1: impl FnMut(),
+    f2: impl FnMut(),
+    run_length: RunLength,
+) -> DuoOut {
+    let mut w = stderr();
+    let s = DefaultStatus::new(
+        &mut w,
+        "Warming up".to_owned(),
+        "Executing bench_run".to_owned(),
+    );
+    bench_run_x_auto_b(cfg, f1, f2, run_length, s)
+}
+
+/// Similar to [`bench_run_x`] but benchmarks `routine1` and `routine2` on inputs created by `setup`.
+///
+/// In each iteration, an input is created with `setup`, cloned, and passed to each of `routine1` and `routine2`, so
+/// that both routines are benchmarked on identical inputs. Only the invocations of the routines are timed; neither
+/// `setup`, the cloning of the input, nor the dropping of the outputs of the routines are included in the latencies.
+pub fn bench_run_setup_x<'a, S: Status<'a>, I: Clone, O1, O2>(
+    cfg: &BenchCfg,
+    setup: impl FnMut() -> I,
+    routine1: impl FnMut(I) -> O1,
+    routine2: impl FnMut(I) -> O2,
+    run_length: RunLength,
+    s: S,
+) -> DuoOut {
+    multi::bench_run_x(
+        cfg,
+        LatencySrc2s::new(setup, routine1, routine2),
+        run_length,
+        s,
+    )
+    .into()
+}
+
+/// Similar to [`bench_run`] but benchmarks `routine1` and `routine2` on inputs created by `setup`.
+///
+/// In each iteration, an input is created with `setup`, cloned, and passed to each of `routine1` and `routine2`, so
+/// that both routines are benchmarked on identical inputs. Only the invocations of the routines are timed; neither
+/// `setup`, the cloning of the input, nor the dropping of the outputs of the routines are included in the latencies.
+pub fn bench_run_setup<I: Clone, O1, O2>(
+    setup: impl FnMut() -> I,
+    routine1: impl FnMut(I) -> O1,
+    routine2: impl FnMut(I) -> O2,
+    run_length: RunLength,
+) -> DuoOut {
+    multi::bench_run(LatencySrc2s::new(setup, routine1, routine2), run_length).into()
+}
+
+/// Similar to [`bench_run_arg_cfg`] but benchmarks `routine1` and `routine2` on inputs created by `setup`.
+///
+/// In each iteration, an input is created with `setup`, cloned, and passed to each of `routine1` and `routine2`, so
+/// that both routines are benchmarked on identical inputs. Only the invocations of the routines are timed; neither
+/// `setup`, the cloning of the input, nor the dropping of the outputs of the routines are included in the latencies.
+pub fn bench_run_setup_arg_cfg<I: Clone, O1, O2>(
+    cfg: &BenchCfg,
+    setup: impl FnMut() -> I,
+    routine1: impl FnMut(I) -> O1,
+    routine2: impl FnMut(I) -> O2,
+    run_length: RunLength,
+) -> DuoOut {
+    multi::bench_run_arg_cfg(
+        cfg,
+        LatencySrc2s::new(setup, routine1, routine2),
+        run_length,
+    )
+    .into()
+}
+
+/// Similar to [`bench_run_with_status`] but benchmarks `routine1` and `routine2` on inputs created by `setup`.
+///
+/// In each iteration, an input is created with `setup`, cloned, and passed to each of `routine1` and `routine2`, so
+/// that both routines are benchmarked on identical inputs. Only the invocations of the routines are timed; neither
+/// `setup`, the cloning of the input, nor the dropping of the outputs of the routines are included in the latencies.
+pub fn bench_run_setup_with_status<I: Clone, O1, O2>(
+    setup: impl FnMut() -> I,
+    routine1: impl FnMut(I) -> O1,
+    routine2: impl FnMut(I) -> O2,
+    run_length: RunLength,
+) -> DuoOut {
+    multi::bench_run_with_status(LatencySrc2s::new(setup, routine1, routine2), run_length).into()
+}
+
+/// Similar to [`bench_run_with_status_arg_cfg`] but benchmarks `routine1` and `routine2` on inputs created by `setup`.
+///
+/// In each iteration, an input is created with `setup`, cloned, and passed to each of `routine1` and `routine2`, so
+/// that both routines are benchmarked on identical inputs. Only the invocations of the routines are timed; neither
+/// `setup`, the cloning of the input, nor the dropping of the outputs of the routines are included in the latencies.
+pub fn bench_run_setup_with_status_arg_cfg<I: Clone, O1, O2>(
+    cfg: &BenchCfg,
+    setup: impl FnMut() -> I,
+    routine1: impl FnMut(I) -> O1,
+    routine2: impl FnMut(I) -> O2,
+    run_length: RunLength,
+) -> DuoOut {
+    multi::bench_run_with_status_arg_cfg(
+        cfg,
+        LatencySrc2s::new(setup, routine1, routine2),
+        run_length,
+    )
+    .into()
+}
+
+/// Similar to [`bench_run_setup_x`] but batches the executions of the routines into groups of size `batch`,
+/// as in [`bench_run_x_b`].
+///
+/// Before each batch, `batch` inputs are created with `setup` and cloned, so that both routines are benchmarked on
+/// identical inputs. Only the invocations of the routines are timed; neither `setup`, the cloning of the inputs, nor
+/// the dropping of the outputs of the routines are included in the latencies.
+pub fn bench_run_setup_x_b<'a, S: Status<'a>, I: Clone, O1, O2>(
+    cfg: &BenchCfg,
+    setup: impl FnMut() -> I,
+    routine1: impl FnMut(I) -> O1,
+    routine2: impl FnMut(I) -> O2,
+    run_length: RunLength,
+    s: S,
+    batch: usize,
+) -> DuoOut {
+    let run_length = batch_run_length(run_length, Some(batch));
+    multi::bench_run_x(
+        cfg,
+        LatencySrc2sb::new(setup, routine1, routine2, batch),
+        run_length,
+        s,
+    )
+    .into()
+}
+
+/// Similar to [`bench_run_setup`] but batches the executions of the routines into groups of size `batch`,
+/// as in [`bench_run_b`].
+///
+/// Before each batch, `batch` inputs are created with `setup` and cloned, so that both routines are benchmarked on
+/// identical inputs. Only the invocations of the routines are timed; neither `setup`, the cloning of the inputs, nor
+/// the dropping of the outputs of the routines are included in the latencies.
+pub fn bench_run_setup_b<I: Clone, O1, O2>(
+    setup: impl FnMut() -> I,
+    routine1: impl FnMut(I) -> O1,
+    routine2: impl FnMut(I) -> O2,
+    run_length: RunLength,
+    batch: usize,
+) -> DuoOut {
+    let run_length = batch_run_length(run_length, Some(batch));
+    multi::bench_run(
+        LatencySrc2sb::new(setup, routine1, routine2, batch),
+        run_length,
+    )
+    .into()
+}
+
+/// Similar to [`bench_run_setup_arg_cfg`] but batches the executions of the routines into groups of size `batch`,
+/// as in [`bench_run_arg_cfg_b`].
+///
+/// Before each batch, `batch` inputs are created with `setup` and cloned, so that both routines are benchmarked on
+/// identical inputs. Only the invocations of the routines are timed; neither `setup`, the cloning of the inputs, nor
+/// the dropping of the outputs of the routines are included in the latencies.
+pub fn bench_run_setup_arg_cfg_b<I: Clone, O1, O2>(
+    cfg: &BenchCfg,
+    setup: impl FnMut() -> I,
+    routine1: impl FnMut(I) -> O1,
+    routine2: impl FnMut(I) -> O2,
+    run_length: RunLength,
+    batch: usize,
+) -> DuoOut {
+    let run_length = batch_run_length(run_length, Some(batch));
+    multi::bench_run_arg_cfg(
+        cfg,
+        LatencySrc2sb::new(setup, routine1, routine2, batch),
+        run_length,
+    )
+    .into()
+}
+
+/// Similar to [`bench_run_setup_with_status`] but batches the executions of the routines into groups of size `batch`,
+/// as in [`bench_run_with_status_b`].
+///
+/// Before each batch, `batch` inputs are created with `setup` and cloned, so that both routines are benchmarked on
+/// identical inputs. Only the invocations of the routines are timed; neither `setup`, the cloning of the inputs, nor
+/// the dropping of the outputs of the routines are included in the latencies.
+pub fn bench_run_setup_with_status_b<I: Clone, O1, O2>(
+    setup: impl FnMut() -> I,
+    routine1: impl FnMut(I) -> O1,
+    routine2: impl FnMut(I) -> O2,
+    run_length: RunLength,
+    batch: usize,
+) -> DuoOut {
+    let run_length = batch_run_length(run_length, Some(batch));
+    multi::bench_run_with_status(
+        LatencySrc2sb::new(setup, routine1, routine2, batch),
+        run_length,
+    )
+    .into()
+}
+
+/// Similar to [`bench_run_setup_with_status_arg_cfg`] but batches the executions of the routines into groups of size `batch`,
+/// as in [`bench_run_with_status_arg_cfg_b`].
+///
+/// Before each batch, `batch` inputs are created with `setup` and cloned, so that both routines are benchmarked on
+/// identical inputs. Only the invocations of the routines are timed; neither `setup`, the cloning of the inputs, nor
+/// the dropping of the outputs of the routines are included in the latencies.
+pub fn bench_run_setup_with_status_arg_cfg_b<I: Clone, O1, O2>(
+    cfg: &BenchCfg,
+    setup: impl FnMut() -> I,
+    routine1: impl FnMut(I) -> O1,
+    routine2: impl FnMut(I) -> O2,
+    run_length: RunLength,
+    batch: usize,
+) -> DuoOut {
+    let run_length = batch_run_length(run_length, Some(batch));
+    multi::bench_run_with_status_arg_cfg(
+        cfg,
+        LatencySrc2sb::new(setup, routine1, routine2, batch),
+        run_length,
+    )
+    .into()
+}
+
 /// Runs benchmarks of `f1` and `f2` on two separate threads, using [bench_run_parallel_arg_cfg],
 /// with the default [`BenchCfg`].
 ///
@@ -271,6 +554,7 @@ pub fn bench_run_parallel_arg_cfg_b(
 
 #[doc(hidden)]
 /// Runs benchmarks of `src1` and `src2` on two separate threads, using [multi::bench_run_arg_cfg] on each thread.
+/// The warm-up and execution phases start together on both threads.
 ///
 /// Arguments:
 /// - `cfg` - bench configuration used to run the benchmark.
@@ -284,9 +568,10 @@ pub fn bench_run_parallel_src_arg_cfg(
     src2: impl LatencySrc<1> + Send,
     run_length: RunLength,
 ) -> DuoOut {
+    let barrier = Barrier::new(2);
     let (out1, out2) = thread::scope(|s| {
-        let h1 = s.spawn(|| multi::bench_run_arg_cfg(&cfg, src1, run_length));
-        let h2 = s.spawn(|| multi::bench_run_arg_cfg(&cfg, src2, run_length));
+        let h1 = s.spawn(|| multi::bench_run_synced(&cfg, src1, run_length, NoStatus, &barrier));
+        let h2 = s.spawn(|| multi::bench_run_synced(&cfg, src2, run_length, NoStatus, &barrier));
 
         let out1 = h1.join().expect("thread running bench for `f1` panicked");
         let out2 = h2.join().expect("thread running bench for `f2` panicked");
@@ -296,9 +581,190 @@ pub fn bench_run_parallel_src_arg_cfg(
 
     BenchOut {
         arr: [out1.into(), out2.into()],
+        // Executions on separate threads are not paired.
+        paired: multi::PairedDiff::default(),
+        positions: multi::PositionStats::default(),
     }
 }
 
+/// Runs benchmarks of `f1` and `f2` on two separate threads, as [`bench_run_parallel_arg_cfg`] does, and reports
+/// progress status during benchmark execution.
+///
+/// The benchmark threads don't write status output. Instead, the calling thread invokes the closures of `s` with the
+/// combined progress of both threads: the iteration count and timing of the thread that is further behind, and the
+/// latency data collected so far by each thread, in the order `f1`, `f2`
+/// (see [`Progress`](crate::status::Progress)). Status output is thus never interleaved.
+///
+/// Arguments:
+/// - `cfg` - bench configuration used to run the benchmark.
+/// - `f1` - first benchmark target.
+/// - `f2` - second benchmark target.
+/// - `run_length` - target run length (iteration count and/or duration) for data collection. Applies to
+///   each thread.
+/// - `s` - status handler for reporting warm-up and execution progress.
+pub fn bench_run_parallel_x<'a, S: Status<'a>>(
+    cfg: &BenchCfg,
+    f1: impl FnMut() + Send,
+    f2: impl FnMut() + Send,
+    run_length: RunLength,
+    s: S,
+) -> DuoOut {
+    let src1 = LatencySrc1::new(f1);
+    let src2 = LatencySrc1::new(f2);
+    bench_run_parallel_src_x(cfg, src1, src2, run_length, s)
+}
+
+/// Similar to [`bench_run_parallel_x`] but batches the executions of `f1` and `f2` into groups of size `batch`.
+///
+/// Batching may reduce measurement overhead.
+/// Each batch results in the batch average being collected `batch` times, so the number of captured
+/// latency values is not impacted by grouping.
+/// However, a potential consequence is that the statistical tests provided by [`DuoOut`] may be somewhat
+/// distorted as the resulting distributions may no longer be approximately logormal.
+pub fn bench_run_parallel_x_b<'a, S: Status<'a>>(
+    cfg: &BenchCfg,
+    f1: impl FnMut() + Send,
+    f2: impl FnMut() + Send,
+    run_length: RunLength,
+    s: S,
+    batch: usize,
+) -> DuoOut {
+    let run_length = batch_run_length(run_length, Some(batch));
+    let src1 = LatencySrc1b::new(f1, batch);
+    let src2 = LatencySrc1b::new(f2, batch);
+    bench_run_parallel_src_x(cfg, src1, src2, run_length, s)
+}
+
+/// Runs benchmarks of `f1` and `f2` on two separate threads and outputs their combined execution status to
+/// `stderr`. Runs with the default [`BenchCfg`].
+/// This function calls [`bench_run_parallel_with_status_arg_cfg`] with the default bench configuration.
+///
+/// Arguments:
+/// - `f1` - first benchmark target.
+/// - `f2` - second benchmark target.
+/// - `run_length` - target run length (iteration count and/or duration) for data collection. Applies to
+///   each thread.
+pub fn bench_run_parallel_with_status(
+    f1: impl FnMut() + Send,
+    f2: impl FnMut() + Send,
+    run_length: RunLength,
+) -> DuoOut {
+    let cfg = BenchCfg::default();
+    bench_run_parallel_with_status_arg_cfg(&cfg, f1, f2, run_length)
+}
+
+/// Runs benchmarks of `f1` and `f2` on two separate threads and outputs their combined execution status to
+/// `stderr`.
+/// This function calls [`bench_run_parallel_x`] with a pre-defined status handler that outputs benchmark status to
+/// `stderr`.
+///
+/// Arguments:
+/// - `cfg` - bench configuration used to run the benchmark.
+/// - `f1` - first benchmark target.
+/// - `f2` - second benchmark target.
+/// - `run_length` - target run length (iteration count and/or duration) for data collection. Applies to
+///   each thread.
+pub fn bench_run_parallel_with_status_arg_cfg(
+    cfg: &BenchCfg,
+    f1: impl FnMut() + Send,
+    f2: impl FnMut() + Send,
+    run_length: RunLength,
+) -> DuoOut {
+    let mut w = stderr();
+    let s = DefaultStatus::new(
+        &mut w,
+        "Warming up".to_owned(),
+        "Executing bench_run_parallel".to_owned(),
+    );
+    bench_run_parallel_x(cfg, f1, f2, run_length, s)
+}
+
+/// Similar to [`bench_run_parallel_with_status`] but batches the executions of `f1` and `f2` into groups of size
+/// `batch`.
+///
+/// Batching may reduce measurement overhead.
+/// Each batch results in the batch average being collected `batch` times, so the number of captured
+/// latency values is not impacted by grouping.
+/// However, a potential consequence is that the statistical tests provided by [`DuoOut`] may be somewhat
+/// distorted as the resulting distributions may no longer be approximately logormal.
+pub fn bench_run_parallel_with_status_b(
+    f1: impl FnMut() + Send,
+    f2: impl FnMut() + Send,
+    run_length: RunLength,
+    batch: usize,
+) -> DuoOut {
+    let cfg = BenchCfg::default();
+    bench_run_parallel_with_status_arg_cfg_b(&cfg, f1, f2, run_length, batch)
+}
+
+/// Similar to [`bench_run_parallel_with_status_arg_cfg`] but batches the executions of `f1` and `f2` into groups of
+/// size `batch`.
+///
+/// Batching may reduce measurement overhead.
+/// Each batch results in the batch average being collected `batch` times, so the number of captured
+/// latency values is not impacted by grouping.
+/// However, a potential consequence is that the statistical tests provided by [`DuoOut`] may be somewhat
+/// distorted as the resulting distributions may no longer be approximately logormal.
+pub fn bench_run_parallel_with_status_arg_cfg_b(
+    cfg: &BenchCfg,
+    f1: impl FnMut() + Send,
+    f2: impl FnMut() + Send,
+    run_length: RunLength,
+    batch: usize,
+) -> DuoOut {
+    let mut w = stderr();
+    let s = DefaultStatus::new(
+        &mut w,
+        "Warming up".to_owned(),
+        "Executing bench_run_parallel".to_owned(),
+    );
+    bench_run_parallel_x_b(cfg, f1, f2, run_length, s, batch)
+}
+
+#[doc(hidden)]
+/// Same as [`bench_run_parallel_src_arg_cfg`], but reports the combined progress of both threads to `s` from the
+/// calling thread (see [`bench_run_parallel_x`]).
+///
+/// # Panics
+///
+/// Panics if a benchmark thread panics.
+pub fn bench_run_parallel_src_x<'a, S: Status<'a>>(
+    cfg: &BenchCfg,
+    src1: impl LatencySrc<1> + Send,
+    src2: impl LatencySrc<1> + Send,
+    run_length: RunLength,
+    mut s: S,
+) -> DuoOut {
+    let barrier = Barrier::new(2);
+    let shared = SharedStatus::new(cfg);
+    let (out1, out2) = thread::scope(|sc| {
+        let h1 = sc.spawn(|| {
+            multi::bench_run_synced(cfg, src1, run_length, shared.thread_status(0), &barrier)
+        });
+        let h2 = sc.spawn(|| {
+            multi::bench_run_synced(cfg, src2, run_length, shared.thread_status(1), &barrier)
+        });
+
+        shared.report(cfg, run_length, &mut s, || {
+            h1.is_finished() && h2.is_finished()
+        });
+
+        let out1 = h1.join().expect("thread running bench for `f1` panicked");
+        let out2 = h2.join().expect("thread running bench for `f2` panicked");
+
+        (out1, out2)
+    });
+
+    let out = BenchOut {
+        arr: [out1.into(), out2.into()],
+        // Executions on separate threads are not paired.
+        paired: multi::PairedDiff::default(),
+        positions: multi::PositionStats::default(),
+    };
+    s.end_status(&out.arr);
+    out
+}
+
 #[cfg(feature = "_test_support")]
 pub fn bench_run_x_o<'a, S: Status<'a>>(
     cfg: &BenchCfg,
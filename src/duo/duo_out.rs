@@ -1,7 +1,12 @@
 use crate::{
-    BenchOut, Comp, FpSeconds, multi,
+    BenchOut, Comp, EquivMargin, FpSeconds, TostResult, multi,
+    stats_support::z_to_p,
     stats_types::{AltHyp, Ci, HypTestResult, PositionWrtCi},
 };
+use basic_stats::{
+    core::{sample_mean, sample_stdev},
+    normal::{student_1samp_ci, student_1samp_p, student_1samp_t, student_1samp_test},
+};
 
 /// Alias for [`multi::BenchOut<2>`](crate::multi::BenchOut<2>).
 pub type DuoOut = multi::BenchOut<2>;
@@ -210,6 +215,229 @@ impl DuoOut {
         self.comp().welch_ln_test(ln_d0, alt_hyp, alpha)
     }
 
+    /// Welch two one-sided tests (TOST) of the hypothesis that
+    /// `median(latency(f1)) / median(latency(f2))` lies within the equivalence `margin`,
+    /// with significance level `alpha` for each one-sided test.
+    ///
+    /// See [`Comp::welch_tost`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.out_f1().n_nz <= 1`.
+    /// - `self.out_f2().n_nz <= 1`.
+    /// - `self.out_f1().stdev_ln() == 0` and `self.out_f2().stdev_ln() == 0`.
+    /// - `alpha` not in open interval `(0, 0.5)`.
+    pub fn welch_tost(&self, margin: EquivMargin, alpha: f64) -> TostResult {
+        self.comp().welch_tost(margin, alpha)
+    }
+
+    /// Number of paired observations, i.e., iterations (or batches) in which both `f1` and `f2` had non-zero
+    /// latencies.
+    ///
+    /// Pairing is only available when `f1` and `f2` are executed in the same iterations, i.e., it is not available
+    /// for the outputs of the `bench_run_parallel*` functions, for which this method returns `0`.
+    pub fn paired_n(&self) -> u64 {
+        self.paired.n
+    }
+
+    /// Sample mean of the paired differences `ln(latency(f1)) - ln(latency(f2))` (where `ln` is the natural
+    /// logarithm), taken over the iterations in which both closures were executed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self.paired_n() == 0`.
+    pub fn paired_mean_diff_ln(&self) -> f64 {
+        sample_mean(self.paired.n, self.paired.sum).expect("number of paired observations is zero")
+    }
+
+    /// Sample standard deviation of the paired differences `ln(latency(f1)) - ln(latency(f2))`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self.paired_n() == 0`.
+    pub fn paired_stdev_diff_ln(&self) -> f64 {
+        sample_stdev(self.paired.n, self.paired.sum, self.paired.sum2)
+            .expect("number of paired observations is zero")
+    }
+
+    /// Estimated ratio of the median `f1` latency to the median `f2` latency,
+    /// computed as the `exp()` of [`Self::paired_mean_diff_ln`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self.paired_n() == 0`.
+    pub fn paired_ratio_medians_f1_f2(&self) -> f64 {
+        self.paired_mean_diff_ln().exp()
+    }
+
+    /// Paired Student's t statistic for the hypothesis that
+    /// `mean(ln(latency(f1)) - ln(latency(f2))) == ln_d0` (where `ln` is the natural logarithm, in the recording unit),
+    /// or equivalently, `median(latency(f1)) / median(latency(f2)) == exp(ln_d0)`.
+    ///
+    /// Unlike Welch's statistic, the paired statistic is based on the per-iteration differences, which cancels
+    /// time-dependent noise common to both closures.
+    ///
+    /// Arguments:
+    /// - `ln_d0`: hypothesized value of `mean(ln(latency(f1)) - ln(latency(f2)))`, or equivalently,
+    ///   `ln(median(latency(f1)) / median(latency(f2)))`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.paired_n() <= 1`.
+    /// - `self.paired_stdev_diff_ln() == 0`.
+    pub fn paired_ln_t(&self, ln_d0: f64) -> f64 {
+        student_1samp_t(&self.paired.moments(), ln_d0)
+            .expect("`number of paired observations <= 1` or `self.paired_stdev_diff_ln() == 0`")
+    }
+
+    /// Degrees of freedom for the paired Student's t statistic for `mean(ln(latency(f1)) - ln(latency(f2)))`.
+    pub fn paired_ln_df(&self) -> f64 {
+        self.paired.n as f64 - 1.
+    }
+
+    /// p-value of the paired Student's t-test of the hypothesis that
+    /// `mean(ln(latency(f1)) - ln(latency(f2))) == ln_d0` (where `ln` is the natural logarithm, in the recording unit),
+    /// or equivalently, `median(latency(f1)) / median(latency(f2)) == exp(ln_d0)`.
+    ///
+    /// Arguments:
+    /// - `ln_d0`: hypothesized value of `mean(ln(latency(f1)) - ln(latency(f2)))`.
+    /// - `alt_hyp`: alternative hypothesis.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.paired_n() <= 1`.
+    /// - `self.paired_stdev_diff_ln() == 0`.
+    pub fn paired_ln_p(&self, ln_d0: f64, alt_hyp: AltHyp) -> f64 {
+        student_1samp_p(&self.paired.moments(), ln_d0, alt_hyp)
+            .expect("`number of paired observations <= 1` or `self.paired_stdev_diff_ln() == 0`")
+    }
+
+    /// Paired Student's confidence interval for
+    /// `mean(ln(latency(f1)) - ln(latency(f2)))` (where `ln` is the natural logarithm, in the recording unit),
+    /// with confidence level `(1 - alpha)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.paired_n() <= 1`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    pub fn paired_ln_ci(&self, alpha: f64) -> Ci {
+        student_1samp_ci(&self.paired.moments(), alpha)
+            .expect("`number of paired observations <= 1` or `alpha` not in open interval `(0, 1)`")
+    }
+
+    /// Paired Student's confidence interval for
+    /// `median(latency(f1)) / median(latency(f2))`,
+    /// with confidence level `(1 - alpha)`.
+    ///
+    /// Assumes that both `latency(f1)` and `latency(f2)` are approximately log-normal.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.paired_n() <= 1`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    pub fn paired_ratio_ci(&self, alpha: f64) -> Ci {
+        let Ci(log_low, log_high) = self.paired_ln_ci(alpha);
+        Ci(log_low.exp(), log_high.exp())
+    }
+
+    /// Position of `value` with respect to the
+    /// paired Student's confidence interval for
+    /// `median(latency(f1)) / median(latency(f2))`,
+    /// with confidence level `(1 - alpha)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.paired_n() <= 1`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    pub fn paired_value_position_wrt_ratio_ci(&self, value: f64, alpha: f64) -> PositionWrtCi {
+        self.paired_ratio_ci(alpha).position_of(value)
+    }
+
+    /// Paired Student's t-test of the hypothesis that
+    /// `mean(ln(latency(f1)) - ln(latency(f2))) == ln_d0` (where `ln` is the natural logarithm, in the recording unit),
+    /// or equivalently, `median(latency(f1)) / median(latency(f2)) == exp(ln_d0)`.
+    ///
+    /// Arguments:
+    /// - `ln_d0`: hypothesized value of `mean(ln(latency(f1)) - ln(latency(f2)))`.
+    /// - `alt_hyp`: alternative hypothesis.
+    /// - `alpha`: confidence level is `1 - alpha`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.paired_n() <= 1`.
+    /// - `self.paired_stdev_diff_ln() == 0`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    pub fn paired_ln_test(&self, ln_d0: f64, alt_hyp: AltHyp, alpha: f64) -> HypTestResult {
+        student_1samp_test(&self.paired.moments(), ln_d0, alt_hyp, alpha).expect("`number of paired observations <= 1` or `self.paired_stdev_diff_ln() == 0` or `alpha` not in open interval `(0, 1)`")
+    }
+
+    /// Wilcoxon signed-rank *W+* statistic (sum of the ranks of the positive differences) for the paired
+    /// differences `ln(latency(f1)) - ln(latency(f2))`.
+    ///
+    /// Zero differences are dropped. Absolute differences are ranked at the resolution of the paired-difference
+    /// histogram, with ties receiving their average rank.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the paired-difference histogram is not available, see [`BenchCfg::with_paired_diff_hist`](crate::BenchCfg::with_paired_diff_hist).
+    pub fn wilcoxon_signed_rank_w(&self) -> f64 {
+        self.paired.signed_rank().w_plus
+    }
+
+    /// Wilcoxon signed-rank normal approximation *z* value for the paired differences
+    /// `ln(latency(f1)) - ln(latency(f2))`, with tie correction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - the paired-difference histogram is not available.
+    /// - there are no non-zero paired differences.
+    pub fn wilcoxon_signed_rank_z(&self) -> f64 {
+        self.paired
+            .signed_rank()
+            .z()
+            .expect("no non-zero paired differences")
+    }
+
+    /// Wilcoxon signed-rank normal approximation *p* value for the paired differences
+    /// `ln(latency(f1)) - ln(latency(f2))`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - the paired-difference histogram is not available.
+    /// - there are no non-zero paired differences.
+    pub fn wilcoxon_signed_rank_p(&self, alt_hyp: AltHyp) -> f64 {
+        z_to_p(self.wilcoxon_signed_rank_z(), alt_hyp)
+    }
+
+    /// Wilcoxon signed-rank test for the paired differences `ln(latency(f1)) - ln(latency(f2))`,
+    /// with alternative hypothesis `alt_hyp` and confidence level `(1 - alpha)`.
+    ///
+    /// As the logarithm is monotonic, this is equivalent to the signed-rank test for
+    /// `latency(f1) / latency(f2)` against `1`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - the paired-difference histogram is not available.
+    /// - there are no non-zero paired differences.
+    /// - `alpha` not in open interval `(0, 1)`.
+    pub fn wilcoxon_signed_rank_test(&self, alt_hyp: AltHyp, alpha: f64) -> HypTestResult {
+        assert!(
+            0. < alpha && alpha < 1.,
+            "`alpha` must be in open interval `(0, 1)`"
+        );
+        HypTestResult::new(self.wilcoxon_signed_rank_p(alt_hyp), alpha, alt_hyp)
+    }
+
     #[cfg(feature = "_experimental")]
     /// Wilcoxon rank sum *W* statistic for `latency(f1)` and `latency(f2)`.
     /// Gated by feature **"_experimental"**.
@@ -258,3 +486,165 @@ impl DuoOut {
         self.comp().wilcoxon_rank_sum_test(alt_hyp, alpha)
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- duo::duo_out::test --nocapture
+mod test {
+    use super::*;
+    use crate::{
+        BenchCfg,
+        test_support::{HI_STDEV_LN, lognormal_samp},
+    };
+    use basic_stats::{approx_eq, core::AcceptedHyp, normal::normal_detm_samp, rel_approx_eq};
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+
+    const ALPHA: f64 = 0.05;
+    const NOISE_STDEV_LN: f64 = 0.01;
+
+    /// Pairs `[x * ratio * exp(e), x]`, where `x` is lognormal with a large log-stdev, shared by both closures,
+    /// and `e` is small independent noise.
+    fn paired_samp(
+        mu: f64,
+        sigma: f64,
+        ratio: f64,
+        samp_size: usize,
+    ) -> impl Iterator<Item = [FpSeconds; 2]> {
+        let noise = normal_detm_samp(0., NOISE_STDEV_LN, samp_size)
+            .unwrap()
+            .collect::<Vec<_>>();
+        lognormal_samp(mu, sigma, samp_size)
+            .zip(noise.into_iter().rev())
+            .map(move |(x, e)| [x * (ratio * e.exp()), x])
+    }
+
+    /// Reference Wilcoxon signed-rank W+ computed directly from the differences.
+    fn signed_rank_w_plus(diffs: &[f64]) -> f64 {
+        let mut nz = diffs
+            .iter()
+            .cloned()
+            .filter(|d| *d != 0.)
+            .collect::<Vec<_>>();
+        nz.sort_by(|a, b| a.abs().total_cmp(&b.abs()));
+
+        let mut w_plus = 0.;
+        let mut i = 0;
+        while i < nz.len() {
+            let mut j = i;
+            while j + 1 < nz.len() && nz[j + 1].abs() == nz[i].abs() {
+                j += 1;
+            }
+            let avg_rank = (i + j) as f64 / 2. + 1.;
+            w_plus += nz[i..=j].iter().filter(|d| **d > 0.).count() as f64 * avg_rank;
+            i = j + 1;
+        }
+        w_plus
+    }
+
+    #[test]
+    fn test_paired_moments() {
+        let cfg = BenchCfg::default();
+        let ratio = 1.05;
+        let samp_size = 1_000;
+        let out = DuoOut::from_iter(&cfg, paired_samp(-9., *HI_STDEV_LN, ratio, samp_size));
+
+        assert_eq!(samp_size as u64, out.paired_n());
+        assert_eq!(samp_size as f64 - 1., out.paired_ln_df());
+        approx_eq!(ratio.ln(), out.paired_mean_diff_ln(), 1e-6);
+        rel_approx_eq!(ratio, out.paired_ratio_medians_f1_f2(), 1e-6);
+        rel_approx_eq!(NOISE_STDEV_LN, out.paired_stdev_diff_ln(), 0.01);
+
+        let exp_t = (out.paired_mean_diff_ln() - ratio.ln())
+            / (out.paired_stdev_diff_ln() / (samp_size as f64).sqrt());
+        approx_eq!(exp_t, out.paired_ln_t(ratio.ln()), 1e-9);
+
+        let Ci(low, high) = out.paired_ratio_ci(ALPHA);
+        assert!(low < ratio && ratio < high);
+        assert_eq!(
+            PositionWrtCi::In,
+            out.paired_value_position_wrt_ratio_ci(ratio, ALPHA)
+        );
+        assert_eq!(
+            PositionWrtCi::Below,
+            out.paired_value_position_wrt_ratio_ci(1., ALPHA)
+        );
+    }
+
+    #[test]
+    fn test_paired_more_powerful_than_welch() {
+        let cfg = BenchCfg::default();
+        let out = DuoOut::from_iter(&cfg, paired_samp(-9., *HI_STDEV_LN, 1.01, 1_000));
+
+        // The common noise swamps a 1% difference for Welch but cancels out in the paired differences.
+        assert_eq!(
+            AcceptedHyp::Null,
+            out.welch_ln_test(0., AltHyp::Gt, ALPHA).accepted()
+        );
+        assert_eq!(
+            AcceptedHyp::Alt,
+            out.paired_ln_test(0., AltHyp::Gt, ALPHA).accepted()
+        );
+        assert_eq!(
+            AcceptedHyp::Alt,
+            out.wilcoxon_signed_rank_test(AltHyp::Gt, ALPHA).accepted()
+        );
+        assert_eq!(
+            AcceptedHyp::Null,
+            out.wilcoxon_signed_rank_test(AltHyp::Lt, ALPHA).accepted()
+        );
+    }
+
+    #[test]
+    fn test_welch_tost() {
+        let cfg = BenchCfg::default();
+        let out = DuoOut::from_iter(&cfg, paired_samp(-9., *HI_STDEV_LN, 1.01, 1_000));
+        for margin in [EquivMargin::symmetric(1.5), EquivMargin::new(0., 1.02)] {
+            let result = out.welch_tost(margin, ALPHA);
+            assert_eq!(out.comp().welch_tost(margin, ALPHA), result);
+        }
+        // The common noise is too large to establish equivalence within 2% with Welch.
+        assert!(
+            !out.welch_tost(EquivMargin::new(0., 1.02), ALPHA)
+                .is_equivalent()
+        );
+    }
+
+    #[test]
+    fn test_wilcoxon_signed_rank() {
+        let cfg = BenchCfg::default();
+        let samp_size = 1_000;
+        let samp = paired_samp(-9., *HI_STDEV_LN, 1.001, samp_size).collect::<Vec<_>>();
+        let out = DuoOut::from_iter(&cfg, samp.iter().cloned());
+
+        let diffs = samp
+            .iter()
+            .map(|[l1, l2]| l1.as_f64().ln() - l2.as_f64().ln())
+            .collect::<Vec<_>>();
+        let exp_w = signed_rank_w_plus(&diffs);
+        let n = samp_size as f64;
+        let exp_z = (exp_w - n * (n + 1.) / 4.) / (n * (n + 1.) * (2. * n + 1.) / 24.).sqrt();
+
+        // Ranks are computed at histogram resolution, so only approximate agreement is expected.
+        rel_approx_eq!(exp_w, out.wilcoxon_signed_rank_w(), 0.005);
+        approx_eq!(exp_z, out.wilcoxon_signed_rank_z(), 0.1);
+        approx_eq!(
+            z_to_p(exp_z, AltHyp::Ne),
+            out.wilcoxon_signed_rank_p(AltHyp::Ne),
+            0.02
+        );
+    }
+
+    #[test]
+    fn test_paired_unavailable() {
+        let cfg = BenchCfg::default().with_paired_diff_hist(false);
+        let out = DuoOut::from_iter(&cfg, paired_samp(-9., *HI_STDEV_LN, 1.01, 100));
+        assert_eq!(100, out.paired_n());
+        assert!(catch_unwind(AssertUnwindSafe(|| out.wilcoxon_signed_rank_w())).is_err());
+
+        let mut out = DuoOut::from_iter(&BenchCfg::default(), paired_samp(-9., 0.1, 1.01, 100));
+        out.reset();
+        assert_eq!(0, out.paired_n());
+        assert!(catch_unwind(AssertUnwindSafe(|| out.paired_mean_diff_ln())).is_err());
+        assert!(catch_unwind(AssertUnwindSafe(|| out.wilcoxon_signed_rank_z())).is_err());
+    }
+}
@@ -2,6 +2,7 @@
 
 mod bench_run;
 mod duo_out;
+mod parallel_status;
 
 pub use bench_run::*;
 pub use duo_out::*;
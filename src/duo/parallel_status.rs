@@ -0,0 +1,499 @@
+//! Combined status reporting for the two threads of a parallel duo run.
+//!
+//! Each benchmark thread reports its progress to a [`SharedStatus`] through a [`ThreadStatus`]. The calling thread
+//! follows the shared state and drives the user's [`Status`] with the combined progress of both threads, so all
+//! status output is written from a single thread.
+
+use crate::{
+    BenchCfg, BenchOut, RunLength,
+    status::{Progress, Status},
+};
+use std::{
+    sync::{Condvar, Mutex, MutexGuard},
+    time::Duration,
+};
+
+/// Phase of a benchmark thread, in lifecycle order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+enum Phase {
+    #[default]
+    Estimating,
+    Warmup,
+    WarmupEnded,
+    Exec,
+    ExecEnded,
+}
+
+/// Latest progress reported by a thread in a phase.
+struct Snapshot {
+    est_time: Duration,
+    est_count: usize,
+    i: usize,
+    elapsed: Duration,
+    out: BenchOut,
+}
+
+impl Snapshot {
+    fn new(cfg: &BenchCfg) -> Self {
+        Self {
+            est_time: Duration::ZERO,
+            est_count: 0,
+            i: 0,
+            elapsed: Duration::ZERO,
+            out: empty_out(cfg),
+        }
+    }
+
+    fn update(&mut self, p: &Progress<'_>) {
+        self.est_time = p.est_time;
+        self.est_count = p.est_count;
+        self.i = p.i;
+        self.elapsed = p.elapsed;
+        self.out.reset();
+        for out in p.outs {
+            self.out.absorb(out);
+        }
+    }
+
+    fn reported(&self) -> bool {
+        self.i > 0
+    }
+
+    fn fraction(&self) -> f64 {
+        Progress {
+            est_time: self.est_time,
+            est_count: self.est_count,
+            i: self.i,
+            elapsed: self.elapsed,
+            outs: &[],
+        }
+        .fraction()
+    }
+}
+
+struct ThreadState {
+    phase: Phase,
+    execs_per_sec: Option<f64>,
+    warmup: Snapshot,
+    exec: Snapshot,
+}
+
+impl ThreadState {
+    fn warmup(&self) -> &Snapshot {
+        &self.warmup
+    }
+
+    fn exec(&self) -> &Snapshot {
+        &self.exec
+    }
+}
+
+struct SharedState {
+    threads: [ThreadState; 2],
+    /// Incremented on every update.
+    version: u64,
+}
+
+impl SharedState {
+    fn reached(&self, phase: Phase) -> bool {
+        self.threads.iter().all(|t| t.phase >= phase)
+    }
+
+    /// Copies the `select`ed snapshots of both threads into `outs` and returns the progress of the thread that is
+    /// further behind, as `(est_time, est_count, i, elapsed)`; `None` if neither thread has reported.
+    fn combine(
+        &self,
+        select: fn(&ThreadState) -> &Snapshot,
+        outs: &mut [BenchOut; 2],
+    ) -> Option<(Duration, usize, usize, Duration)> {
+        let lead = self
+            .threads
+            .iter()
+            .map(select)
+            .filter(|snap| snap.reported())
+            .min_by(|a, b| a.fraction().total_cmp(&b.fraction()))?;
+        for (out, t) in outs.iter_mut().zip(&self.threads) {
+            out.reset();
+            out.absorb(&select(t).out);
+        }
+        Some((lead.est_time, lead.est_count, lead.i, lead.elapsed))
+    }
+}
+
+/// Progress of the two threads of a parallel duo run, shared between the benchmark threads and the calling thread.
+pub(super) struct SharedStatus {
+    state: Mutex<SharedState>,
+    updated: Condvar,
+}
+
+impl SharedStatus {
+    pub(super) fn new(cfg: &BenchCfg) -> Self {
+        let thread_state = || ThreadState {
+            phase: Phase::Estimating,
+            execs_per_sec: None,
+            warmup: Snapshot::new(cfg),
+            exec: Snapshot::new(cfg),
+        };
+        Self {
+            state: Mutex::new(SharedState {
+                threads: [thread_state(), thread_state()],
+                version: 0,
+            }),
+            updated: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SharedState> {
+        self.state.lock().expect("status mutex poisoned")
+    }
+
+    fn update(&self, thread: usize, f: impl FnOnce(&mut ThreadState)) {
+        let mut state = self.lock();
+        f(&mut state.threads[thread]);
+        state.version += 1;
+        self.updated.notify_all();
+    }
+
+    /// Status handler for benchmark thread `thread` (`0` or `1`).
+    pub(super) fn thread_status(&self, thread: usize) -> ThreadStatus<'_> {
+        ThreadStatus {
+            shared: self,
+            thread,
+        }
+    }
+
+    /// Follows the progress of the benchmark threads and reports it to `s`, until both threads end their execution
+    /// phase or `finished` returns `true`. Does not invoke [`Status::end_status`], which requires the final outputs.
+    pub(super) fn report<'a, S: Status<'a>>(
+        &self,
+        cfg: &BenchCfg,
+        run_length: RunLength,
+        s: &mut S,
+        finished: impl Fn() -> bool,
+    ) {
+        let interval = Duration::from_millis(cfg.status_millis().max(1));
+        let mut outs = [empty_out(cfg), empty_out(cfg)];
+
+        s.start_status(cfg, run_length);
+        self.follow(
+            Phase::Warmup,
+            interval,
+            &finished,
+            ThreadState::warmup,
+            &mut outs,
+            None::<fn(&Progress<'_>)>,
+        );
+        let execs_per_sec = self
+            .lock()
+            .threads
+            .iter()
+            .filter_map(|t| t.execs_per_sec)
+            .reduce(f64::min);
+        if let Some(execs_per_sec) = execs_per_sec {
+            s.execs_per_sec_status(execs_per_sec);
+        }
+
+        let warmup_status = s.warmup_progress();
+        self.follow(
+            Phase::WarmupEnded,
+            interval,
+            &finished,
+            ThreadState::warmup,
+            &mut outs,
+            warmup_status,
+        );
+        if let Some(end_warmup_status) = s.end_warmup_status() {
+            end_warmup_status();
+        }
+
+        let exec_status = s.exec_progress();
+        self.follow(
+            Phase::ExecEnded,
+            interval,
+            &finished,
+            ThreadState::exec,
+            &mut outs,
+            exec_status,
+        );
+        if let Some(end_exec_status) = s.end_exec_status() {
+            end_exec_status();
+        }
+    }
+
+    /// Waits until both threads reach `phase` or `finished` returns `true`, invoking `status` with the combined
+    /// progress of the `select`ed snapshots whenever the shared state changes.
+    fn follow(
+        &self,
+        phase: Phase,
+        interval: Duration,
+        finished: &impl Fn() -> bool,
+        select: fn(&ThreadState) -> &Snapshot,
+        outs: &mut [BenchOut; 2],
+        mut status: Option<impl FnMut(&Progress<'_>)>,
+    ) {
+        let mut seen = None;
+        loop {
+            // The lock is released before `status` is invoked, so that slow writers don't delay the benchmarks.
+            let (done, progress) = {
+                let state = self.lock();
+                let (state, _) = self
+                    .updated
+                    .wait_timeout_while(state, interval, |state| {
+                        seen == Some(state.version) && !state.reached(phase)
+                    })
+                    .expect("status mutex poisoned");
+                let done = state.reached(phase) || finished();
+                let progress = match &status {
+                    Some(_) if seen != Some(state.version) => state.combine(select, outs),
+                    _ => None,
+                };
+                seen = Some(state.version);
+                (done, progress)
+            };
+
+            if let (Some(status), Some((est_time, est_count, i, elapsed))) = (&mut status, progress)
+            {
+                status(&Progress {
+                    est_time,
+                    est_count,
+                    i,
+                    elapsed,
+                    outs: &outs[..],
+                });
+            }
+            if done {
+                break;
+            }
+        }
+    }
+}
+
+/// Status handler of one benchmark thread, which records the thread's progress in a [`SharedStatus`].
+pub(super) struct ThreadStatus<'m> {
+    shared: &'m SharedStatus,
+    thread: usize,
+}
+
+impl<'a> Status<'a> for ThreadStatus<'_> {
+    // Superseded by `warmup_progress`.
+    fn warmup_status<'b>(&'b mut self) -> Option<impl FnMut(Duration, usize, usize) + 'b>
+    where
+        'a: 'b,
+    {
+        None::<fn(Duration, usize, usize)>
+    }
+
+    fn end_warmup_status<'b>(&'b mut self) -> Option<impl FnOnce() + 'b>
+    where
+        'a: 'b,
+    {
+        Some(|| {
+            self.shared
+                .update(self.thread, |t| t.phase = Phase::WarmupEnded)
+        })
+    }
+
+    // Superseded by `exec_progress`.
+    fn exec_status<'b>(&'b mut self) -> Option<impl FnMut(Duration, usize, usize) + 'b>
+    where
+        'a: 'b,
+    {
+        None::<fn(Duration, usize, usize)>
+    }
+
+    fn end_exec_status<'b>(&'b mut self) -> Option<impl FnOnce() + 'b>
+    where
+        'a: 'b,
+    {
+        Some(|| {
+            self.shared
+                .update(self.thread, |t| t.phase = Phase::ExecEnded)
+        })
+    }
+
+    fn warmup_progress<'b>(&'b mut self) -> Option<impl FnMut(&Progress<'_>) + 'b>
+    where
+        'a: 'b,
+    {
+        Some(|p: &Progress<'_>| self.shared.update(self.thread, |t| t.warmup.update(p)))
+    }
+
+    fn exec_progress<'b>(&'b mut self) -> Option<impl FnMut(&Progress<'_>) + 'b>
+    where
+        'a: 'b,
+    {
+        Some(|p: &Progress<'_>| {
+            self.shared.update(self.thread, |t| {
+                t.phase = Phase::Exec;
+                t.exec.update(p)
+            })
+        })
+    }
+
+    fn execs_per_sec_status(&mut self, execs_per_sec: f64) {
+        self.shared.update(self.thread, |t| {
+            t.execs_per_sec = Some(execs_per_sec);
+            t.phase = Phase::Warmup;
+        });
+    }
+}
+
+/// Empty output without raw samples, used to hold copies of in-progress outputs.
+fn empty_out(cfg: &BenchCfg) -> BenchOut {
+    let mut out = BenchOut::new(cfg);
+    out.samples = None;
+    out
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- duo::parallel_status::test --nocapture
+mod test {
+    use crate::{
+        BenchCfg, BenchOut, RunLength,
+        duo::bench_run_parallel_x,
+        status::{Progress, ProgressStatus, Status},
+        test_support::StringWriter,
+    };
+    use std::{
+        thread::{self, ThreadId},
+        time::Duration,
+    };
+
+    fn quick_cfg() -> BenchCfg {
+        BenchCfg::default()
+            .with_warmup_millis(20)
+            .with_status_millis(5)
+    }
+
+    fn sleeper(micros: u64) -> impl FnMut() + Send {
+        move || thread::sleep(Duration::from_micros(micros))
+    }
+
+    /// Records the status calls, with the thread each was made from.
+    struct Recorder<'r> {
+        calls: &'r mut Vec<(String, ThreadId)>,
+    }
+
+    impl Recorder<'_> {
+        fn record(calls: &mut Vec<(String, ThreadId)>, call: String) {
+            calls.push((call, thread::current().id()));
+        }
+    }
+
+    impl<'a> Status<'a> for Recorder<'_> {
+        fn warmup_status<'b>(&'b mut self) -> Option<impl FnMut(Duration, usize, usize) + 'b>
+        where
+            'a: 'b,
+        {
+            None::<fn(Duration, usize, usize)>
+        }
+
+        fn end_warmup_status<'b>(&'b mut self) -> Option<impl FnOnce() + 'b>
+        where
+            'a: 'b,
+        {
+            Some(|| Self::record(self.calls, "end_warmup".to_owned()))
+        }
+
+        fn exec_status<'b>(&'b mut self) -> Option<impl FnMut(Duration, usize, usize) + 'b>
+        where
+            'a: 'b,
+        {
+            None::<fn(Duration, usize, usize)>
+        }
+
+        fn end_exec_status<'b>(&'b mut self) -> Option<impl FnOnce() + 'b>
+        where
+            'a: 'b,
+        {
+            Some(|| Self::record(self.calls, "end_exec".to_owned()))
+        }
+
+        fn warmup_progress<'b>(&'b mut self) -> Option<impl FnMut(&Progress<'_>) + 'b>
+        where
+            'a: 'b,
+        {
+            Some(|p: &Progress<'_>| {
+                assert_eq!(2, p.outs.len());
+                Self::record(self.calls, "warmup".to_owned())
+            })
+        }
+
+        fn exec_progress<'b>(&'b mut self) -> Option<impl FnMut(&Progress<'_>) + 'b>
+        where
+            'a: 'b,
+        {
+            Some(|p: &Progress<'_>| {
+                let ns = p.outs.iter().map(BenchOut::n).collect::<Vec<_>>();
+                Self::record(self.calls, format!("exec {} {ns:?}", p.i))
+            })
+        }
+
+        fn start_status(&mut self, _cfg: &BenchCfg, _run_length: RunLength) {
+            Self::record(self.calls, "start".to_owned());
+        }
+
+        fn execs_per_sec_status(&mut self, _execs_per_sec: f64) {
+            Self::record(self.calls, "execs_per_sec".to_owned());
+        }
+
+        fn end_status(&mut self, outs: &[BenchOut]) {
+            let ns = outs.iter().map(BenchOut::n).collect::<Vec<_>>();
+            Self::record(self.calls, format!("end {ns:?}"));
+        }
+    }
+
+    #[test]
+    fn test_parallel_status_lifecycle() {
+        let mut calls = Vec::new();
+        let recorder = Recorder { calls: &mut calls };
+        let out = bench_run_parallel_x(
+            &quick_cfg(),
+            sleeper(100),
+            sleeper(300),
+            RunLength::Count(100),
+            recorder,
+        );
+        assert_eq!(100, out.out_f1().n());
+        assert_eq!(100, out.out_f2().n());
+
+        let names = calls.iter().map(|(c, _)| c.as_str()).collect::<Vec<_>>();
+        println!("{names:?}");
+
+        // All calls are made from the calling thread.
+        assert!(calls.iter().all(|(_, id)| *id == thread::current().id()));
+
+        let pos = |prefix: &str| names.iter().position(|c| c.starts_with(prefix)).unwrap();
+        assert_eq!(0, pos("start"));
+        assert_eq!(1, pos("execs_per_sec"));
+        assert!(pos("warmup") < pos("end_warmup"));
+        assert!(pos("end_warmup") < pos("exec "));
+        assert_eq!(names.len() - 2, pos("end_exec"));
+        assert_eq!("end [100, 100]", names[names.len() - 1]);
+
+        // The final combined progress shows both threads' complete outputs.
+        assert_eq!("exec 100 [100, 100]", names[names.len() - 3]);
+    }
+
+    #[test]
+    fn test_parallel_progress_status() {
+        let mut w = StringWriter::new();
+        let status = ProgressStatus::new(&mut w, "Warm".to_owned(), "Exec".to_owned(), false);
+        bench_run_parallel_x(
+            &quick_cfg(),
+            sleeper(100),
+            sleeper(300),
+            RunLength::Count(50),
+            status,
+        );
+
+        let output = w.as_str().unwrap();
+        println!("{output}");
+        let last = output.lines().last().unwrap();
+        assert!(last.starts_with("Exec: 100% (50 of"), "{last}");
+        let medians = last.split("median ").nth(1).unwrap();
+        assert_eq!(2, medians.split(", ").count());
+        assert!(!medians.contains('-'));
+    }
+}
@@ -35,9 +35,26 @@ pub fn median_batch_latency(mut f: impl FnMut(), batch: usize, n_batches: usize)
     midpoint_value(&latencies)
 }
 
+/// Estimates the resolution of [`Instant`] as the smallest positive difference between consecutive readings.
+pub(crate) fn clock_resolution() -> FpSeconds {
+    const TRIES: usize = 1_000;
+
+    let mut resolution = Duration::MAX;
+    for _ in 0..TRIES {
+        let start = Instant::now();
+        let mut end = Instant::now();
+        while end == start {
+            end = Instant::now();
+        }
+        resolution = resolution.min(end - start);
+    }
+    resolution.into()
+}
+
 /// A floating point duration of seconds. Useful for representing duration values or fractions with
 /// finer granularity than 1ns.
 #[derive(Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FpSeconds(pub f64);
 
 impl FpSeconds {
@@ -193,7 +210,15 @@ impl Debug for FpSeconds {
 }
 
 /// Unit of time used to record latencies. Used as an argument in benchmarking functions.
+///
+/// With feature **"serde"**, it is serialized as the exponent `n` of [`Self::sub_sec`]; deserialization only accepts
+/// the exponents of the unit constants, i.e., `0`, `3`, `6`, `9`, and `12` (see [`LatencyUnit::try_from`]).
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "u8", try_from = "u8")
+)]
 pub struct LatencyUnit(u8);
 
 impl LatencyUnit {
@@ -264,17 +289,63 @@ impl LatencyUnit {
     }
 }
 
+impl TryFrom<u8> for LatencyUnit {
+    type Error = String;
+
+    /// Converts the exponent `n` of [`LatencyUnit::sub_sec`] to the corresponding unit constant.
+    ///
+    /// Returns an error unless `n` is the exponent of one of [`LatencyUnit::SEC`], [`LatencyUnit::MILLI`],
+    /// [`LatencyUnit::MICRO`], [`LatencyUnit::NANO`], or [`LatencyUnit::PICO`].
+    fn try_from(n: u8) -> Result<Self, Self::Error> {
+        match n {
+            0 | 3 | 6 | 9 | 12 => Ok(Self(n)),
+            _ => Err(format!(
+                "invalid latency unit exponent {n}: expected 0, 3, 6, 9, or 12"
+            )),
+        }
+    }
+}
+
+impl From<LatencyUnit> for u8 {
+    fn from(unit: LatencyUnit) -> Self {
+        unit.0
+    }
+}
+
 /// Specifies how long a benchmark should run for. Encapsulates a target number of iterations for the benchmark to run
-/// and a time duration. The benchmark run length can be set as a number of iterations, a time duration, or
-/// a number of iterations with a timeout duration.
+/// and a time duration. The benchmark run length can be set as a number of iterations, a time duration,
+/// a number of iterations with a timeout duration, or a target precision capped by a number of iterations and/or
+/// a time duration.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum RunLength {
     /// Run for a fixed number of iterations.
     Count(usize),
     /// Run for a fixed duration.
+    ///
+    /// The run ends when either the wall-clock time elapsed or the sum of the measured latencies reaches the
+    /// duration. With a [`Measurement`](crate::Measurement) other than [`WallTime`](crate::WallTime), the latter
+    /// is not wall-clock time: it is usually less (e.g., [`ThreadCpuTime`](crate::ThreadCpuTime) of a closure that
+    /// blocks), so the wall clock ends the run, but it can be more (e.g., [`ProcessCpuTime`](crate::ProcessCpuTime)
+    /// with other busy threads), which ends the run early. The iteration rate used for status estimates and batch
+    /// sizing is derived from the measured latencies, so it is also in terms of the configured measurement.
     Time(Duration),
     /// Run for a fixed number of iterations, but stop early if the given duration is exceeded.
     CountWithTimeout(usize, Duration),
+    /// Run until the target precision is reached, but stop early if the given number of iterations or the given
+    /// duration is reached, whichever comes first. With neither cap, the run continues until the target is reached,
+    /// the latency source is exhausted, or the run is cancelled (see [`CancelToken`](crate::CancelToken)).
+    ///
+    /// See [`PrecisionTarget`] for how precision is measured and [`RunInfo::precision`](crate::RunInfo::precision)
+    /// for whether the target was reached.
+    Precision {
+        /// Target precision.
+        target: PrecisionTarget,
+        /// Maximum number of iterations; `None` for no count cap.
+        max_count: Option<usize>,
+        /// Maximum duration; `None` for no duration cap.
+        max_time: Option<Duration>,
+    },
 }
 
 impl RunLength {
@@ -282,41 +353,137 @@ impl RunLength {
     ///
     /// The benchmark ends when the specified number of iterations is reached (or exceeded)
     /// or when the time duration is reached (or exceeded), whichever comes first.
+    /// For [`RunLength::Precision`], these are the caps and the benchmark may end earlier.
+    /// An unset limit is returned as `usize::MAX` or `Duration::MAX`.
     pub fn exec_count_and_duration(&self) -> (usize, Duration) {
         match self {
             Self::Count(count) => (*count, Duration::MAX),
             Self::Time(duration) => (usize::MAX, *duration),
             Self::CountWithTimeout(count, duration) => (*count, *duration),
+            Self::Precision {
+                max_count,
+                max_time,
+                ..
+            } => (
+                max_count.unwrap_or(usize::MAX),
+                max_time.unwrap_or(Duration::MAX),
+            ),
+        }
+    }
+
+    /// Target precision, if `self` is a [`RunLength::Precision`].
+    pub fn precision_target(&self) -> Option<PrecisionTarget> {
+        match self {
+            Self::Precision { target, .. } => Some(*target),
+            _ => None,
         }
     }
 
-    /// Estimated number of iterations.
+    /// Non-adaptive run length corresponding to the caps of a [`RunLength::Precision`]; `self` for other variants.
+    ///
+    /// Returns `None` if `self` is a [`RunLength::Precision`] with neither a count nor a duration cap.
+    pub(crate) fn cap(&self) -> Option<RunLength> {
+        match *self {
+            Self::Precision {
+                max_count,
+                max_time,
+                ..
+            } => match (max_count, max_time) {
+                (Some(count), Some(duration)) => Some(Self::CountWithTimeout(count, duration)),
+                (Some(count), None) => Some(Self::Count(count)),
+                (None, Some(duration)) => Some(Self::Time(duration)),
+                (None, None) => None,
+            },
+            run_length => Some(run_length),
+        }
+    }
+
+    /// Estimated number of iterations. For [`RunLength::Precision`], this is an upper bound, and it is `usize::MAX`
+    /// if there is no cap.
     pub(crate) fn estimated_count(&self, execs_per_second: f64) -> usize {
         assert!(execs_per_second > 0.0, "execs_per_second must be positive");
-        match self {
-            Self::Count(count) => *count,
-            Self::Time(duration) => (duration.as_secs_f64() * execs_per_second).round() as usize,
-            Self::CountWithTimeout(count, duration) => {
+        match self.cap() {
+            None => usize::MAX,
+            Some(Self::Count(count)) => count,
+            Some(Self::Time(duration)) => {
+                (duration.as_secs_f64() * execs_per_second).round() as usize
+            }
+            Some(Self::CountWithTimeout(count, duration)) => {
                 let count_from_duration =
                     (duration.as_secs_f64() * execs_per_second).round() as usize;
-                *count.min(&count_from_duration)
+                count.min(count_from_duration)
+            }
+            Some(Self::Precision { .. }) => {
+                unreachable!("`cap` never returns `RunLength::Precision`")
             }
         }
     }
 
-    /// Estimated run duration.
+    /// Estimated run duration. For [`RunLength::Precision`], this is an upper bound, and it is `Duration::MAX` if
+    /// there is no cap.
     pub(crate) fn estimated_time(&self, execs_per_second: f64) -> Duration {
-        match self {
-            Self::Count(count) => Duration::from_secs_f64(*count as f64 / execs_per_second),
-            Self::Time(duration) => *duration,
-            Self::CountWithTimeout(count, duration) => {
-                let duration_from_count = Duration::from_secs_f64(*count as f64 / execs_per_second);
-                *duration.min(&duration_from_count)
+        match self.cap() {
+            None => Duration::MAX,
+            Some(Self::Count(count)) => Duration::from_secs_f64(count as f64 / execs_per_second),
+            Some(Self::Time(duration)) => duration,
+            Some(Self::CountWithTimeout(count, duration)) => {
+                let duration_from_count = Duration::from_secs_f64(count as f64 / execs_per_second);
+                duration.min(duration_from_count)
+            }
+            Some(Self::Precision { .. }) => {
+                unreachable!("`cap` never returns `RunLength::Precision`")
             }
         }
     }
 }
 
+/// Target precision for [`RunLength::Precision`].
+///
+/// Precision is measured as the relative half-width of a confidence interval with confidence level `1 - alpha`:
+/// - for benchmarks of two closures, the Welch confidence interval for `median(latency(f1)) / median(latency(f2))`
+///   (see [`Comp::welch_ratio_ci`](crate::Comp::welch_ratio_ci)), relative to the estimated ratio;
+/// - otherwise, the Student confidence interval for `median(latency(f))`
+///   (see [`BenchOut::student_median_ci`](crate::BenchOut::student_median_ci)), relative to the estimated median,
+///   with the largest value among all closures being used.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PrecisionTarget {
+    rel_half_width: f64,
+    alpha: f64,
+}
+
+impl PrecisionTarget {
+    /// Creates a target with relative half-width `rel_half_width` (e.g., `0.01` for ±1%) at confidence level
+    /// `1 - alpha`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `rel_half_width <= 0`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    pub fn new(rel_half_width: f64, alpha: f64) -> Self {
+        assert!(rel_half_width > 0., "`rel_half_width` must be positive");
+        assert!(
+            0. < alpha && alpha < 1.,
+            "`alpha` must be in open interval `(0, 1)`"
+        );
+        Self {
+            rel_half_width,
+            alpha,
+        }
+    }
+
+    /// Target relative half-width of the confidence interval.
+    pub fn rel_half_width(&self) -> f64 {
+        self.rel_half_width
+    }
+
+    /// Confidence level of the confidence interval is `1 - alpha`.
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+}
+
 /// Estimates how many iterations of `src` can be done in one second by iterating one or more times
 /// and doing a proportionality calculation.
 /// The iterator `src` is expected to encapsulate closure invocations such that each
@@ -37,6 +37,7 @@
 //! |---------|---------|
 //! | `default` | For access to all of the library's benchmarking functions and types.
 //! | `load` | Enables synthetic loads: `fake_work(Duration)` (thread sleep) and arithmetic-loop CPU work via [`BusyWork`] |
+//! | `serde` | Enables serialization and deserialization of [`BenchOut`], [`multi::BenchOut`], [`SummaryStats`], [`FpSeconds`], and [`LatencyUnit`], as well as the [`baseline`] store |
 //!
 //! # Log-normal assumption
 //!
@@ -45,26 +46,50 @@
 //! assumption that latency distributions are approximately log-normal. Under this
 //! assumption `mean(ln(latency)) == ln(median(latency))`, so confidence intervals
 //! and hypothesis tests on log-latencies translate directly to statements about
-//! median latencies.
+//! median latencies. When a run contains stalls or other extreme latencies, [`BenchOut::tukey_outliers`] and
+//! [`BenchOut::mad_outliers`] classify them, and [`BenchOut::robust_ln`] provides trimmed or winsorized
+//! counterparts of these statistics. For distributions that are far from log-normal (e.g., bimodal), use the
+//! distribution-free `bootstrap_*` confidence intervals of [`BenchOut`] and [`Comp`] instead.
 
 #![allow(clippy::new_without_default)]
 
 mod bench_cfg;
 mod bench_out;
 mod bench_run;
+mod bootstrap;
 mod comp;
 mod latency;
+mod measurement;
+mod open_loop;
+mod plan;
+mod rng;
+mod robust;
+mod run_info;
+mod run_set;
+mod samples;
+mod stats_support;
 mod summary_stats;
+mod warmup;
 
 pub use bench_cfg::*;
 pub use bench_out::*;
 pub use bench_run::*;
+pub use bootstrap::*;
 pub use comp::*;
 pub use latency::*;
+pub use measurement::*;
+pub use open_loop::*;
+pub use plan::*;
+pub use robust::*;
+pub use run_info::*;
+pub use run_set::*;
+pub use samples::*;
 pub use summary_stats::*;
+pub use warmup::*;
 
 pub mod duo;
 pub mod multi;
+pub mod parallel;
 pub mod status;
 
 #[doc(hidden)]
@@ -73,6 +98,12 @@ pub mod dev_support;
 #[cfg(feature = "load")]
 pub mod load;
 
+#[cfg(feature = "serde")]
+pub mod baseline;
+
+#[cfg(feature = "serde")]
+mod serde_support;
+
 /// Structs and enums for confidence intervals and hypothesis tests.
 pub mod stats_types {
     pub use basic_stats::core::{AcceptedHyp, AltHyp, Ci, HypTestResult, PositionWrtCi};
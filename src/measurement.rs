@@ -0,0 +1,216 @@
+//! Clocks used to measure the latencies of benchmarked closures.
+
+use crate::FpSeconds;
+#[cfg(unix)]
+use std::time::Duration;
+use std::time::Instant;
+
+/// A clock used to measure latencies.
+///
+/// [`LatencySrc1`](crate::multi::LatencySrc1), [`LatencySrc2`](crate::multi::LatencySrc2), their setup
+/// counterparts, and their batched counterparts are generic over this trait, with [`WallTime`] as the default.
+/// The latencies measured with any implementation are recorded in a [`BenchOut`](crate::BenchOut) as usual.
+pub trait Measurement {
+    /// Reading of the clock at the start of a measurement.
+    type Start: Copy;
+
+    /// Reads the clock at the start of a measurement.
+    fn start(&self) -> Self::Start;
+
+    /// Returns the time elapsed since `start` according to this clock.
+    fn end(&self, start: Self::Start) -> FpSeconds;
+
+    /// Invokes `f` once and returns its latency according to this clock.
+    #[inline(always)]
+    fn latency(&self, f: impl FnOnce()) -> FpSeconds {
+        let start = self.start();
+        f();
+        self.end(start)
+    }
+
+    /// Invokes `f` `n` times and returns the total latency according to this clock.
+    #[inline(always)]
+    fn latency_n(&self, mut f: impl FnMut(), n: usize) -> FpSeconds {
+        let start = self.start();
+        for _ in 0..n {
+            f();
+        }
+        self.end(start)
+    }
+}
+
+/// Wall-clock time, measured with [`Instant`]. This is the clock used by [`latency`](crate::latency) and
+/// [`latency_n`](crate::latency_n).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WallTime;
+
+impl Measurement for WallTime {
+    type Start = Instant;
+
+    #[inline(always)]
+    fn start(&self) -> Self::Start {
+        Instant::now()
+    }
+
+    #[inline(always)]
+    fn end(&self, start: Self::Start) -> FpSeconds {
+        start.elapsed().into()
+    }
+}
+
+/// Reads the POSIX clock `clock_id`.
+///
+/// # Panics
+///
+/// Panics if the clock can't be read, which can only happen if `clock_id` is not supported by the platform.
+#[cfg(unix)]
+#[inline(always)]
+fn clock_gettime(clock_id: libc::clockid_t) -> Duration {
+    // SAFETY: `timespec` is a plain C struct for which all-zero bytes is a valid value.
+    let mut ts: libc::timespec = unsafe { std::mem::zeroed() };
+    // SAFETY: `ts` is a valid, writable `timespec`.
+    let ret = unsafe { libc::clock_gettime(clock_id, &mut ts) };
+    assert_eq!(ret, 0, "clock_gettime({clock_id}) failed");
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+/// CPU time consumed by the calling thread (`CLOCK_THREAD_CPUTIME_ID`). Available on Unix platforms.
+///
+/// Excludes time during which the thread is not scheduled, e.g., due to preemption or blocking (including sleeps),
+/// which makes it useful to separate scheduling noise from the work actually done by the benchmarked closure.
+/// Work done by the closure on other threads is not included.
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadCpuTime;
+
+#[cfg(unix)]
+impl Measurement for ThreadCpuTime {
+    type Start = Duration;
+
+    #[inline(always)]
+    fn start(&self) -> Self::Start {
+        clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID)
+    }
+
+    #[inline(always)]
+    fn end(&self, start: Self::Start) -> FpSeconds {
+        (clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID).saturating_sub(start)).into()
+    }
+}
+
+/// CPU time consumed by all threads of the calling process (`CLOCK_PROCESS_CPUTIME_ID`). Available on Unix platforms.
+///
+/// Unlike [`ThreadCpuTime`], includes work done on other threads, including threads unrelated to the benchmark.
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessCpuTime;
+
+#[cfg(unix)]
+impl Measurement for ProcessCpuTime {
+    type Start = Duration;
+
+    #[inline(always)]
+    fn start(&self) -> Self::Start {
+        clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID)
+    }
+
+    #[inline(always)]
+    fn end(&self, start: Self::Start) -> FpSeconds {
+        (clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID).saturating_sub(start)).into()
+    }
+}
+
+/// Raw monotonic hardware-based time (`CLOCK_MONOTONIC_RAW`), not subject to NTP adjustments.
+/// Available on Linux, Android, macOS, and iOS.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios"
+))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MonotonicRaw;
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios"
+))]
+impl Measurement for MonotonicRaw {
+    type Start = Duration;
+
+    #[inline(always)]
+    fn start(&self) -> Self::Start {
+        clock_gettime(libc::CLOCK_MONOTONIC_RAW)
+    }
+
+    #[inline(always)]
+    fn end(&self, start: Self::Start) -> FpSeconds {
+        (clock_gettime(libc::CLOCK_MONOTONIC_RAW).saturating_sub(start)).into()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- measurement::test --nocapture
+mod test {
+    use super::*;
+    use std::{hint::black_box, thread, time::Duration};
+
+    const SLEEP: Duration = Duration::from_millis(20);
+
+    fn busy_work() {
+        let mut acc = 0_u64;
+        for i in 0..2_000_000_u64 {
+            acc = black_box(acc.wrapping_mul(31).wrapping_add(i));
+        }
+        black_box(acc);
+    }
+
+    #[test]
+    fn test_wall_time() {
+        let lat = WallTime.latency(|| thread::sleep(SLEEP));
+        assert!(lat >= SLEEP.into(), "lat={lat:?}");
+
+        let lat_n = WallTime.latency_n(|| thread::sleep(SLEEP), 2);
+        assert!(lat_n >= (SLEEP * 2).into(), "lat_n={lat_n:?}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cpu_time_excludes_sleep() {
+        let thread_sleep = ThreadCpuTime.latency(|| thread::sleep(SLEEP));
+        assert!(
+            thread_sleep < (SLEEP / 4).into(),
+            "thread_sleep={thread_sleep:?}"
+        );
+
+        let process_sleep = ProcessCpuTime.latency(|| thread::sleep(SLEEP));
+        assert!(
+            process_sleep < SLEEP.into(),
+            "process_sleep={process_sleep:?}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cpu_time_includes_work() {
+        let thread_work = ThreadCpuTime.latency(busy_work);
+        assert!(thread_work > FpSeconds::ZERO, "thread_work={thread_work:?}");
+
+        let process_work = ProcessCpuTime.latency(busy_work);
+        assert!(
+            process_work > FpSeconds::ZERO,
+            "process_work={process_work:?}"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_monotonic_raw() {
+        let lat = MonotonicRaw.latency(|| thread::sleep(SLEEP));
+        // Allow for a small rate difference between the raw and the NTP-adjusted clocks.
+        assert!(lat >= (SLEEP.mul_f64(0.99)).into(), "lat={lat:?}");
+    }
+}
@@ -1,7 +1,11 @@
 //! Module defining the key data structure produced by the [`multi::bench_run`](super::bench_run) and
 //! related benchmarking functions.
 
-use crate::{BenchCfg, FpSeconds, LatencyUnit, SummaryStats, summary_stats};
+use crate::{
+    BenchCfg, FpSeconds, LatencyUnit, RunInfo, SummaryStats,
+    multi::{PairedDiff, PositionMedians, PositionStats},
+    summary_stats,
+};
 use basic_stats::core::{AltHyp, Ci, HypTestResult, PositionWrtCi};
 use std::{
     array,
@@ -21,9 +25,20 @@ use std::{
 /// Under the assumption that `latency(f)` is approximately log-normal, `mean(ln(latency(f))) == ln(median(latency(f)))`.
 /// This assumption is widely supported by performance analysis theory and empirical data.
 /// Thus, the `*_ln_*` methods are useful for the analysis of median latencies.
+///
+/// When `K == 2`, the paired differences of the natural logarithms of the latencies collected in the same
+/// iteration are also kept (see [`DuoOut`](crate::duo::DuoOut)).
+///
+/// With feature **"serde"**, this struct can be serialized and deserialized (see [`crate::BenchOut`]).
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BenchOut<const K: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::array"))]
     pub(crate) arr: [crate::BenchOut; K],
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) paired: PairedDiff,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) positions: PositionStats,
 }
 
 impl<const K: usize> Index<usize> for BenchOut<K> {
@@ -46,7 +61,11 @@ impl Deref for BenchOut<1> {
 
 impl From<crate::BenchOut> for BenchOut<1> {
     fn from(value: crate::BenchOut) -> Self {
-        Self { arr: [value] }
+        Self {
+            arr: [value],
+            paired: PairedDiff::default(),
+            positions: PositionStats::default(),
+        }
     }
 }
 
@@ -67,6 +86,8 @@ impl<const K: usize> BenchOut<K> {
     pub fn new(cfg: &BenchCfg) -> Self {
         Self {
             arr: array::from_fn(|_| crate::BenchOut::new(cfg)),
+            paired: PairedDiff::new(K == 2 && cfg.paired_diff_hist(), cfg.sigfig()),
+            positions: PositionStats::new(cfg),
         }
     }
 
@@ -86,9 +107,7 @@ impl<const K: usize> BenchOut<K> {
         let mut out = Self::new(cfg);
 
         for lat_arr in src {
-            for (b, fps) in out.arr.iter_mut().zip(lat_arr.iter()) {
-                b.capture_data((*fps, 1));
-            }
+            out.capture_data((lat_arr, 1));
         }
         out
     }
@@ -118,14 +137,72 @@ impl<const K: usize> BenchOut<K> {
         for b in &mut self.arr {
             b.reset();
         }
+        self.paired.reset();
+        self.positions.reset();
     }
 
     #[doc(hidden)]
     // TODO: remove
-    /// Updates `self` with an elapsed time observation for the functions.
-    pub fn capture_data(&mut self, batch_latencies: ([FpSeconds; K], usize)) {
+    /// Updates `self` with an elapsed time observation for the functions. Returns `false` if the observation was
+    /// rejected for any of the functions (see [`HistOverflow::Reject`](crate::HistOverflow::Reject)), in which case
+    /// the paired differences are not updated.
+    pub fn capture_data(&mut self, batch_latencies: ([FpSeconds; K], usize)) -> bool {
+        let mut accepted = true;
         for (i, b) in &mut self.arr.iter_mut().enumerate() {
-            b.capture_data((batch_latencies.0[i], batch_latencies.1));
+            accepted &= b.capture_data((batch_latencies.0[i], batch_latencies.1));
+        }
+        if accepted && let [latency1, latency2] = batch_latencies.0.as_slice() {
+            self.paired.capture(*latency1, *latency2);
+        }
+        accepted
+    }
+
+    /// Merges the data of `other` into `self`, closure by closure (see [`crate::BenchOut::merge`]).
+    /// The paired differences and the latencies by position (see [`Self::position_medians`]) are combined too.
+    pub fn merge(&mut self, other: &BenchOut<K>) {
+        let (unit, other_unit) = (self.recording_unit(), other.recording_unit());
+        for (b, other_b) in self.arr.iter_mut().zip(&other.arr) {
+            b.merge(other_b);
+        }
+        self.paired.merge(&other.paired);
+        self.positions.merge(unit, &other.positions, other_unit);
+    }
+
+    /// Updates `self` with an elapsed time observation for the functions, executed in `order` if known. The
+    /// latencies by position are only updated if the observation was accepted for all the functions.
+    pub(crate) fn capture_ordered(
+        &mut self,
+        batch_latencies: ([FpSeconds; K], usize),
+        order: Option<[usize; K]>,
+    ) {
+        if self.capture_data(batch_latencies)
+            && let Some(order) = order
+        {
+            self.positions.capture(
+                self.recording_unit(),
+                &batch_latencies.0,
+                &order,
+                batch_latencies.1,
+            );
+        }
+    }
+
+    /// Median latencies of each closure when it was executed first and when it was executed last in an iteration.
+    ///
+    /// Returns `None` if the closures were executed in a fixed order (see [`ExecOrder`](super::ExecOrder)).
+    pub fn position_medians(&self) -> Option<[PositionMedians; K]> {
+        let medians = self.positions.medians(self.recording_unit())?;
+        Some(
+            medians
+                .try_into()
+                .expect("can't happen: positions are captured for `K` closures"),
+        )
+    }
+
+    /// Records the batch size chosen by auto-batching in the run information of each closure.
+    pub(crate) fn set_auto_batch(&mut self, batch: usize) {
+        for b in &mut self.arr {
+            b.run_info.batch = Some(batch);
         }
     }
 
@@ -134,6 +211,11 @@ impl<const K: usize> BenchOut<K> {
         self.first().recording_unit()
     }
 
+    /// Information about the execution of the benchmark run that produced `self`.
+    pub fn run_info(&self) -> &RunInfo {
+        self.first().run_info()
+    }
+
     /// Number of observations (sample size) for a function, as an integer.
     #[inline(always)]
     pub fn n(&self) -> u64 {
@@ -317,7 +399,7 @@ mod test {
     use super::*;
     use crate::rel_approx_eq_fpsecs;
     use crate::{
-        BenchCfg,
+        BenchCfg, HistOverflow,
         test_support::{LO_STDEV_LN, lognormal_samp},
     };
     use basic_stats::{
@@ -599,6 +681,66 @@ mod test {
         assert_eq!(out.n(), 0);
     }
 
+    #[test]
+    fn test_bench_out_2_merge() {
+        let cfg = BenchCfg::default().with_paired_diff_hist(true);
+        let samp =
+            |shift: f64| lognormal_samp(-13., *LO_STDEV_LN, 300).map(move |x| [x * shift, x]);
+        let mut out = BenchOut::<2>::from_iter(&cfg, samp(1.5));
+        let other = BenchOut::<2>::from_iter(&cfg, samp(1.5));
+        let all = BenchOut::<2>::from_iter(&cfg, samp(1.5).chain(samp(1.5)));
+
+        out.merge(&other);
+        assert_eq!(600, out.n());
+        assert_eq!(all.medians(), out.medians());
+        assert_eq!(all.paired.n, out.paired.n);
+        approx_eq!(all.paired.sum, out.paired.sum, 1e-9);
+        assert_eq!(
+            all.paired.hist_pos.as_ref().unwrap().len(),
+            out.paired.hist_pos.as_ref().unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_bench_out_2_reject_skips_paired_and_positions() {
+        let cfg = BenchCfg::default()
+            .with_hist_bounds(FpSeconds::from_micros(1), FpSeconds::from_millis(10))
+            .with_hist_overflow(HistOverflow::Reject);
+        let mut out = BenchOut::<2>::new(&cfg);
+        let (in_range, out_of_range) = (FpSeconds::from_millis(1), FpSeconds::from_millis(1_000));
+
+        out.capture_ordered(([in_range, FpSeconds::from_millis(2)], 1), Some([0, 1]));
+        out.capture_ordered(([in_range, out_of_range], 1), Some([1, 0]));
+        assert_eq!([2, 1], [out[0].n(), out[1].n()]);
+        assert_eq!(1, out[1].run_info().out_of_range());
+        assert_eq!(1, out.paired.n);
+        let positions = out.position_medians().expect("order is not fixed");
+        assert_eq!([1, 0], [positions[0].n_first, positions[1].n_first]);
+        assert_eq!([0, 1], [positions[0].n_last, positions[1].n_last]);
+    }
+
+    #[test]
+    fn test_bench_out_2_clamp_positions() {
+        let cfg = BenchCfg::default()
+            .with_hist_bounds(FpSeconds::from_micros(1), FpSeconds::from_millis(10))
+            .with_hist_overflow(HistOverflow::Clamp);
+        let mut out = BenchOut::<2>::new(&cfg);
+
+        out.capture_ordered(
+            (
+                [FpSeconds::from_millis(1), FpSeconds::from_millis(1_000)],
+                1,
+            ),
+            Some([0, 1]),
+        );
+        let positions = out.position_medians().expect("order is not fixed");
+        let median_last = positions[1]
+            .median_last
+            .expect("closure 1 was executed last");
+        assert!(median_last < FpSeconds::from_millis(11), "{median_last:?}");
+        assert_eq!(out[1].median(), median_last);
+    }
+
     #[test]
     fn test_bench_out_2_panics_on_empty() {
         let cfg = BenchCfg::default();
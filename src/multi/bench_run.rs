@@ -1,27 +1,195 @@
 //! Implements functions to collect latency statistics for a closure.
 
 use crate::{
-    BenchCfg, FpSeconds, RunLength,
-    multi::{BenchOut, LatencySrc},
-    status::{DefaultStatus, NoStatus, Status},
+    BatchProbe, BenchCfg, CancelToken, Comp, FpSeconds, OverheadEstimate, OverheadMode,
+    PrecisionOutcome, RunLength, WarmupMode, WarmupTrace,
+    multi::{BenchOut, LatencySrc, PositionStats},
+    status::{DefaultStatus, NoStatus, Progress, Status},
+    warmup::SteadyStateDetector,
 };
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use std::{
+    any::Any,
+    fmt::Debug,
     io::stderr,
+    iter,
+    panic::{self, AssertUnwindSafe},
+    sync::Barrier,
     time::{Duration, Instant},
 };
 
 type BenchState<const K: usize> = BenchOut<K>;
 
-impl<const K: usize> BenchState<K> {
+/// Minimum number of non-zero observations per closure before precision is assessed for [`RunLength::Precision`].
+const PRECISION_MIN_N: u64 = 30;
+
+/// Maximum interval between precision assessments for [`RunLength::Precision`].
+/// Precision is also assessed whenever the iteration count is a power of 2.
+const PRECISION_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Run length used to calibrate the timer overhead (see [`OverheadMode`]). The calibration is run twice, the first
+/// time as a warm-up.
+const OVERHEAD_CALIBRATION_RUN_LENGTH: RunLength =
+    RunLength::CountWithTimeout(10_000, Duration::from_millis(100));
+
+/// Latencies yielded by one invocation of a latency source, together with the order in which the closures were
+/// executed if it isn't fixed (see [`LatencySrc::order`]) and the untimed setup latency of the invocation (see
+/// [`LatencySrc::setup_latency`]).
+#[derive(Debug)]
+pub(crate) struct Ordered<L, O> {
+    pub(crate) latencies: L,
+    pub(crate) order: Option<O>,
+    pub(crate) setup: FpSeconds,
+}
+
+impl<L: AsRef<[FpSeconds]>, O> AsRef<[FpSeconds]> for Ordered<L, O> {
+    fn as_ref(&self) -> &[FpSeconds] {
+        self.latencies.as_ref()
+    }
+}
+
+impl<L: AsMut<[FpSeconds]>, O> AsMut<[FpSeconds]> for Ordered<L, O> {
+    fn as_mut(&mut self) -> &mut [FpSeconds] {
+        self.latencies.as_mut()
+    }
+}
+
+/// Latency source as consumed by [`run_x`], yielding latencies of type `L`.
+pub(crate) trait CollectorSrc<L>: Iterator<Item = (L, usize)> {
+    /// Latency of timing a no-op the same way the source times each closure (see [`LatencySrc::empty_latency`]).
+    fn empty_latency(&self) -> FpSeconds;
+}
+
+/// Adapts a [`LatencySrc`] to yield the execution order of the closures and the setup latency together with their
+/// latencies.
+struct OrderedSrc<const K: usize, S>(S);
+
+impl<const K: usize, S: LatencySrc<K>> Iterator for OrderedSrc<K, S> {
+    type Item = (Ordered<[FpSeconds; K], [usize; K]>, usize);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (latencies, batch) = self.0.next()?;
+        let order = self.0.order();
+        let setup = self.0.setup_latency();
+        Some((
+            Ordered {
+                latencies,
+                order,
+                setup,
+            },
+            batch,
+        ))
+    }
+}
+
+impl<const K: usize, S: LatencySrc<K>> CollectorSrc<Ordered<[FpSeconds; K], [usize; K]>>
+    for OrderedSrc<K, S>
+{
+    fn empty_latency(&self) -> FpSeconds {
+        self.0.empty_latency()
+    }
+}
+
+/// Adapts `src` to yield the execution order of the closures together with their latencies.
+fn ordered<const K: usize, S: LatencySrc<K>>(src: S) -> OrderedSrc<K, S> {
+    OrderedSrc(src)
+}
+
+/// Adapts a [`CollectorSrc`] so that a panic of the source ends it instead of unwinding, and stores the panic payload
+/// in `panic`. A run on one of several threads sharing a [`Barrier`] thus still reaches each barrier, and the panic
+/// can be resumed once the run completes.
+struct CatchUnwindSrc<'p, S> {
+    src: S,
+    panic: &'p mut Option<Box<dyn Any + Send>>,
+}
+
+impl<S: Iterator> Iterator for CatchUnwindSrc<'_, S> {
+    type Item = S::Item;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.panic.is_some() {
+            return None;
+        }
+        panic::catch_unwind(AssertUnwindSafe(|| self.src.next())).unwrap_or_else(|payload| {
+            *self.panic = Some(payload);
+            None
+        })
+    }
+}
+
+impl<L, S: CollectorSrc<L>> CollectorSrc<L> for CatchUnwindSrc<'_, S> {
+    fn empty_latency(&self) -> FpSeconds {
+        self.src.empty_latency()
+    }
+}
+
+/// Latency data collected for the closures of a benchmark run, whether their number is known at compile time
+/// ([`BenchOut<K>`]) or not ([`NamedBenchOut`](super::NamedBenchOut)). Implements the warm-up/execute logic shared by both.
+pub(crate) trait Collector {
+    /// Latencies of the closures yielded by one invocation of the latency source.
+    type Latencies: AsRef<[FpSeconds]> + AsMut<[FpSeconds]> + Debug;
+
+    /// Outputs of the individual closures.
+    fn outs(&self) -> &[crate::BenchOut];
+
+    /// Mutable outputs of the individual closures.
+    fn outs_mut(&mut self) -> &mut [crate::BenchOut];
+
+    /// Mutable latencies of the closures by position in the execution order.
+    fn positions_mut(&mut self) -> &mut PositionStats;
+
+    /// Captures the latencies from one invocation of the latency source.
+    fn capture(&mut self, batch_latencies: (Self::Latencies, usize));
+
+    /// Untimed setup latency of the invocation of the latency source that yielded `latencies`, which counts towards
+    /// the run length but is not captured.
+    fn setup_latency(latencies: &Self::Latencies) -> FpSeconds;
+
+    /// Resets the collected data.
+    fn clear(&mut self);
+
+    /// Relative half-width of the confidence interval used to assess [`RunLength::Precision`] runs,
+    /// with confidence level `1 - alpha`. See [`PrecisionTarget`](crate::PrecisionTarget).
+    ///
+    /// Returns `None` if any closure has fewer than [`PRECISION_MIN_N`] non-zero observations.
+    fn rel_half_width(&self, alpha: f64) -> Option<f64> {
+        if self.outs().iter().any(|b| b.n_nz() < PRECISION_MIN_N) {
+            return None;
+        }
+
+        if let [out1, out2] = self.outs() {
+            if out1.stdev_ln() == 0. && out2.stdev_ln() == 0. {
+                return Some(0.);
+            }
+            let comp = Comp::new(out1, out2);
+            let ci = comp.welch_ratio_ci(alpha);
+            return Some((ci.1 - ci.0) / 2. / comp.ratio_medians_f1_f2_from_lns());
+        }
+
+        let width = self
+            .outs()
+            .iter()
+            .map(|b| {
+                let (low, high) = b.student_median_ci(alpha);
+                (high - low).as_f64() / 2. / b.mean_ln().exp()
+            })
+            .fold(0., f64::max);
+        Some(width)
+    }
+
     /// Executes target closures repeatedly and captures latencies.
-    /// `exec_status` is invoked once for every `status_count` invocations of the closures.
+    /// `status` is invoked once for every `status_count` invocations of the closures, with the invocation count, the
+    /// elapsed time, and the outputs collected so far.
+    /// If `cancel_token` is cancelled, returns before the next invocation and marks the outputs as aborted.
     fn execute(
         &mut self,
-        src: &mut impl LatencySrc<K>,
+        src: &mut impl Iterator<Item = (Self::Latencies, usize)>,
         run_length: RunLength,
         status_count: usize,
-        mut status: Option<impl FnMut(usize)>,
+        mut status: Option<impl FnMut(usize, Duration, &[crate::BenchOut])>,
+        cancel_token: Option<&CancelToken>,
     ) {
         assert!(status_count > 0, "status_count must be > 0");
 
@@ -29,17 +197,53 @@ impl<const K: usize> BenchState<K> {
         debug!("execute >>> exec_count={exec_count}, run_time={run_time:?}");
         assert!(exec_count > 0, "exec_count must be > 0");
 
+        let overhead = self.outs()[0]
+            .run_info
+            .overhead
+            .filter(|o| o.subtracted)
+            .map(|o| o.median);
+
+        let precision_target = run_length.precision_target();
+        let mut converged = false;
+        let mut last_precision_check = Duration::ZERO;
+
         let mut acc_latency = FpSeconds::ZERO; // enables testing with synthetic latency sources
         let start = Instant::now();
 
         for i in 1..=exec_count {
-            let src_finished = if let Some(batch_latencies) = src.next() {
-                acc_latency +=
-                    batch_latencies.0.iter().cloned().sum::<FpSeconds>() * batch_latencies.1;
+            if cancel_token.is_some_and(|t| t.is_cancelled()) {
+                debug!("execute >>> i={i}, cancelled");
+                for b in self.outs_mut() {
+                    b.run_info.aborted = true;
+                }
+                break;
+            }
+
+            let src_finished = if let Some(mut batch_latencies) = src.next() {
+                acc_latency += batch_latencies
+                    .0
+                    .as_ref()
+                    .iter()
+                    .cloned()
+                    .sum::<FpSeconds>()
+                    * batch_latencies.1
+                    + Self::setup_latency(&batch_latencies.0);
                 trace!(
                     "execute >>> i={i}, batch_latencies={batch_latencies:?}, acc_latency={acc_latency:?}"
                 );
-                self.capture_data(batch_latencies);
+                if let Some(overhead) = overhead {
+                    // Both the overhead estimate and the latencies are means per execution of the batch.
+                    let count = batch_latencies.1;
+                    for (k, latency) in batch_latencies.0.as_mut().iter_mut().enumerate() {
+                        *latency = if *latency > overhead {
+                            *latency - overhead
+                        } else {
+                            self.outs_mut()[k].run_info.overhead_floored += count as u64;
+                            FpSeconds::ZERO
+                        };
+                    }
+                }
+                self.capture(batch_latencies);
                 false
             } else {
                 true
@@ -48,32 +252,181 @@ impl<const K: usize> BenchState<K> {
             let elapsed = start.elapsed();
             trace!("execute >>> i={i}, elapsed={elapsed:?}");
 
+            if let Some(target) = precision_target
+                && !src_finished
+                && (i.is_power_of_two()
+                    || elapsed - last_precision_check >= PRECISION_CHECK_INTERVAL)
+            {
+                last_precision_check = elapsed;
+                converged = self
+                    .rel_half_width(target.alpha())
+                    .is_some_and(|width| width <= target.rel_half_width());
+                trace!("execute >>> i={i}, converged={converged}");
+            }
+
             if i == exec_count
                 || elapsed >= run_time
                 || i.is_multiple_of(status_count)
                 || acc_latency.as_duration() >= run_time
                 || src_finished
+                || converged
             {
                 let finished = i == exec_count
                     || elapsed >= run_time
                     || acc_latency.as_duration() >= run_time
-                    || src_finished;
+                    || src_finished
+                    || converged;
 
                 if (i % status_count == 0 || finished)
                     && let Some(exec_status) = &mut status
                 {
-                    exec_status(i);
+                    exec_status(i, elapsed, self.outs());
                 }
 
                 if finished {
                     debug!(
-                        "execute >>> i={i}, elapsed={elapsed:?}, acc_latency={acc_latency:?}, src_finished={src_finished}"
+                        "execute >>> i={i}, elapsed={elapsed:?}, acc_latency={acc_latency:?}, src_finished={src_finished}, converged={converged}"
                     );
                     break;
                 }
             }
         }
+
+        if let Some(target) = precision_target {
+            let outcome = PrecisionOutcome {
+                converged,
+                rel_half_width: self.rel_half_width(target.alpha()),
+            };
+            debug!("execute >>> precision outcome={outcome:?}");
+            for b in self.outs_mut() {
+                b.run_info.precision = Some(outcome);
+            }
+        }
+    }
+
+    /// Records the `execs_per_sec` estimate in the run information of each closure, if it is finite and positive.
+    fn set_execs_per_sec(&mut self, execs_per_sec: f64) {
+        if execs_per_sec.is_finite() && execs_per_sec > 0. {
+            for b in self.outs_mut() {
+                b.run_info.execs_per_sec = Some(execs_per_sec);
+            }
+        }
+    }
+
+    /// Sizes the histogram of each closure, as well as the histograms by position, for the estimated iteration rate
+    /// `execs_per_sec`, unless the bounds are set in `cfg` (see [`BenchCfg::hist_bounds`]).
+    fn size_hists(&mut self, cfg: &BenchCfg, execs_per_sec: f64) {
+        if cfg.hist_bounds().is_none() {
+            for b in self.outs_mut() {
+                b.size_hist(cfg, execs_per_sec);
+            }
+            self.positions_mut().size_hists(cfg, execs_per_sec);
+        }
+    }
+
+    /// Records the trace of an adaptive warm-up in the run information of each closure.
+    fn set_warmup_trace(&mut self, trace: WarmupTrace) {
+        for b in self.outs_mut() {
+            b.run_info.warmup = Some(trace.clone());
+        }
+    }
+
+    /// Records `overhead` in the run information of each closure; it is then subtracted from captured latencies by
+    /// [`Self::execute`] if `subtracted` is `true`.
+    fn set_overhead(&mut self, overhead: FpSeconds, subtracted: bool) {
+        let estimate = OverheadEstimate {
+            median: overhead,
+            subtracted,
+        };
+        for b in self.outs_mut() {
+            b.run_info.overhead = Some(estimate);
+        }
+    }
+
+    /// Logs a warning for each closure whose median latency is less than [`BenchCfg::OVERHEAD_WARN_MULTIPLE`]
+    /// times the overhead estimate.
+    fn warn_if_near_overhead(&self) {
+        for (k, b) in self.outs().iter().enumerate() {
+            let Some(estimate) = b.run_info.overhead else {
+                continue;
+            };
+            if b.n() > 0 && b.median() < estimate.median * BenchCfg::OVERHEAD_WARN_MULTIPLE {
+                warn!(
+                    "median latency {:?} of closure {k} is less than {} times the timer overhead {:?}",
+                    b.median(),
+                    BenchCfg::OVERHEAD_WARN_MULTIPLE,
+                    estimate.median
+                );
+            }
+        }
+    }
+}
+
+impl<const K: usize> Collector for BenchState<K> {
+    type Latencies = Ordered<[FpSeconds; K], [usize; K]>;
+
+    fn outs(&self) -> &[crate::BenchOut] {
+        &self.arr
+    }
+
+    fn outs_mut(&mut self) -> &mut [crate::BenchOut] {
+        &mut self.arr
+    }
+
+    fn positions_mut(&mut self) -> &mut PositionStats {
+        &mut self.positions
     }
+
+    fn capture(&mut self, batch_latencies: (Self::Latencies, usize)) {
+        let (
+            Ordered {
+                latencies, order, ..
+            },
+            batch,
+        ) = batch_latencies;
+        self.capture_ordered((latencies, batch), order);
+    }
+
+    fn setup_latency(latencies: &Self::Latencies) -> FpSeconds {
+        latencies.setup
+    }
+
+    fn clear(&mut self) {
+        self.reset();
+    }
+}
+
+/// Estimates the timer overhead as the median of the latencies returned by `empty_latency`, which times a no-op
+/// the same way the benchmarked latency source times each closure (see [`LatencySrc::empty_latency`]).
+pub(crate) fn calibrate_overhead(
+    cfg: &BenchCfg,
+    empty_latency: impl Fn() -> FpSeconds,
+) -> FpSeconds {
+    let mut state = BenchState::<1>::new(cfg);
+    let mut src = iter::repeat_with(|| {
+        let latencies = [empty_latency()];
+        (
+            Ordered {
+                latencies,
+                order: None,
+                setup: FpSeconds::ZERO,
+            },
+            1,
+        )
+    });
+    for _ in 0..2 {
+        state.reset();
+        state.execute(
+            &mut src,
+            OVERHEAD_CALIBRATION_RUN_LENGTH,
+            usize::MAX,
+            None::<fn(usize, Duration, &[crate::BenchOut])>,
+            None,
+        );
+    }
+    let overhead = state.first().median();
+    debug!("calibrate_overhead >>> overhead={overhead:?}");
+    overhead
 }
 
 /// Repeatedly invokes `src.next()`, collects the resulting latency data in a
@@ -81,7 +434,11 @@ impl<const K: usize> BenchState<K> {
 /// execution.
 ///
 /// Prior to data collection, the benchmark is "warmed-up" by repeatedly invoking
-/// `src.next()` for [`BenchCfg::warmup_millis`] milliseconds.
+/// `src.next()` for [`BenchCfg::warmup_millis`] milliseconds or, with [`WarmupMode::Adaptive`], until the latencies
+/// reach a steady state (see [`BenchCfg::warmup_mode`]).
+///
+/// If the [`BenchCfg::cancel_token`] is cancelled, e.g., from a status closure, the run stops before the next
+/// invocation and the data collected so far is returned, marked as aborted (see [`CancelToken`]).
 ///
 /// Arguments:
 /// - `cfg` - bench configuration used to run the benchmark.
@@ -90,52 +447,213 @@ impl<const K: usize> BenchState<K> {
 /// - `s` - status handler for reporting warm-up and execution progress.
 pub fn bench_run_x<'a, const K: usize, S: Status<'a>>(
     cfg: &BenchCfg,
-    mut src: impl LatencySrc<K>,
+    src: impl LatencySrc<K>,
     run_length: RunLength,
-    mut s: S,
+    s: S,
+) -> BenchOut<K> {
+    run_x(
+        cfg,
+        BenchOut::new(cfg),
+        ordered(src),
+        run_length,
+        s,
+        None,
+        None,
+    )
+}
+
+/// Same as [`bench_run_x`], but reuses the iteration rate estimated by auto-batching instead of probing `src` again.
+/// The timer overhead is still calibrated with `src`, i.e., with the chosen batch size.
+pub(crate) fn bench_run_probed<'a, const K: usize, S: Status<'a>>(
+    cfg: &BenchCfg,
+    src: impl LatencySrc<K>,
+    run_length: RunLength,
+    s: S,
+    probe: &BatchProbe,
 ) -> BenchOut<K> {
-    debug!("bench_run_x >>> run_length={run_length:?}");
-    let mut state = BenchOut::new(cfg);
-    let execs_per_second = cfg.execs_per_sec(&mut src, run_length);
-    debug!("bench_run_x >>> execs_per_second={execs_per_second}");
+    run_x(
+        cfg,
+        BenchOut::new(cfg),
+        ordered(src),
+        run_length,
+        s,
+        None,
+        Some(probe),
+    )
+}
 
-    let warmup_run_length = RunLength::Time(Duration::from_millis(cfg.warmup_millis()));
+/// Same as [`bench_run_x`], but waits on `barrier` before the warm-up and before the execution, so that these
+/// phases start together for runs on different threads that share `barrier`.
+///
+/// If `src` panics, the run ends early but still waits on `barrier` at each phase, so that the other threads don't
+/// block indefinitely; the panic is then resumed.
+pub(crate) fn bench_run_synced<'a, const K: usize, S: Status<'a>>(
+    cfg: &BenchCfg,
+    src: impl LatencySrc<K>,
+    run_length: RunLength,
+    s: S,
+    barrier: &Barrier,
+) -> BenchOut<K> {
+    let mut caught = None;
+    let src = CatchUnwindSrc {
+        src: ordered(src),
+        panic: &mut caught,
+    };
+    let out = run_x(
+        cfg,
+        BenchOut::new(cfg),
+        src,
+        run_length,
+        s,
+        Some(barrier),
+        None,
+    );
+    if let Some(payload) = caught {
+        panic::resume_unwind(payload);
+    }
+    out
+}
+
+/// Adapts a [`Progress`] status closure to the arguments supplied by [`Collector::execute`], capturing the
+/// estimated duration and count of the phase.
+fn progress_status(
+    status: Option<impl FnMut(&Progress<'_>)>,
+    est_time: Duration,
+    est_count: usize,
+) -> Option<impl FnMut(usize, Duration, &[crate::BenchOut])> {
+    status.map(|mut s| {
+        move |i: usize, elapsed: Duration, outs: &[crate::BenchOut]| {
+            s(&Progress {
+                est_time,
+                est_count,
+                i,
+                elapsed,
+                outs,
+            })
+        }
+    })
+}
+
+/// Implements [`bench_run_x`] for any [`Collector`]: estimates the iteration rate, warms up, calibrates the overhead
+/// if configured, and executes. If `barrier` is present, waits on it before the warm-up and before the execution.
+/// If `probe` is present, its iteration rate estimate is used instead of estimating it again.
+pub(crate) fn run_x<'a, C: Collector, S: Status<'a>>(
+    cfg: &BenchCfg,
+    mut state: C,
+    mut src: impl CollectorSrc<C::Latencies>,
+    run_length: RunLength,
+    mut s: S,
+    barrier: Option<&Barrier>,
+    probe: Option<&BatchProbe>,
+) -> C {
+    debug!("run_x >>> run_length={run_length:?}");
+    s.start_status(cfg, run_length);
+    let execs_per_second = match probe {
+        Some(probe) => probe.execs_per_sec,
+        None => {
+            // The setup latency is spread over the executions of the batch, so that the estimated iteration rate
+            // accounts for it.
+            let aggregate = (&mut src).flat_map(|(latencies, count)| {
+                let setup = C::setup_latency(&latencies) / count;
+                iter::repeat_n(
+                    latencies.as_ref().iter().cloned().sum::<FpSeconds>() + setup,
+                    count,
+                )
+            });
+            cfg.execs_per_sec_from_aggregate(aggregate, run_length)
+        }
+    };
+    debug!("run_x >>> execs_per_second={execs_per_second}");
+    s.execs_per_sec_status(execs_per_second);
+    state.size_hists(cfg, execs_per_second);
+
+    let warmup_run_length = cfg.warmup_run_length();
     let warmup_est_time = warmup_run_length.estimated_time(execs_per_second);
     let warmup_est_count = warmup_run_length.estimated_count(execs_per_second);
     let exec_est_time = run_length.estimated_time(execs_per_second);
     let exec_est_count = run_length.estimated_count(execs_per_second);
 
     // Warm-up.
-    let warmup_status = S::part_apply(s.warmup_status(), warmup_est_time, warmup_est_count);
+    if let Some(barrier) = barrier {
+        barrier.wait();
+    }
+    let warmup_status = progress_status(s.warmup_progress(), warmup_est_time, warmup_est_count);
     let warmup_status_count = if warmup_status.is_some() {
         cfg.status_count(execs_per_second)
     } else {
         usize::MAX
     };
-    debug!("bench_run_x >>> warmup_status_count={warmup_status_count}");
+    debug!("run_x >>> warmup_status_count={warmup_status_count}");
+    let mut detector = match cfg.warmup_mode() {
+        WarmupMode::Fixed => None,
+        WarmupMode::Adaptive(params) => Some(SteadyStateDetector::new(params)),
+    };
+    // An adaptive warm-up ends by exhausting the warm-up source once steady state is detected.
+    let mut warmup_src = iter::from_fn(|| {
+        if detector.as_ref().is_some_and(|d| d.steady()) {
+            return None;
+        }
+        let item = src.next()?;
+        if let Some(d) = detector.as_mut() {
+            d.observe(item.0.as_ref().iter().cloned().sum());
+        }
+        Some(item)
+    });
     state.execute(
-        &mut src,
+        &mut warmup_src,
         warmup_run_length,
         warmup_status_count,
         warmup_status,
+        cfg.cancel_token(),
     );
     if let Some(end_warmup_status) = s.end_warmup_status() {
         end_warmup_status();
     }
-    state.reset();
+    state.clear();
+    state.set_execs_per_sec(execs_per_second);
+    if let Some(detector) = detector {
+        let trace = detector.trace();
+        debug!(
+            "run_x >>> warmup cutoff={:?}, iterations={}",
+            trace.cutoff(),
+            trace.iterations()
+        );
+        state.set_warmup_trace(trace);
+    }
+
+    // Calibrate overhead, unless the run was cancelled.
+    match cfg.overhead_mode() {
+        _ if cfg.cancelled() => (),
+        OverheadMode::Ignore => (),
+        mode => {
+            let overhead = calibrate_overhead(cfg, || src.empty_latency());
+            state.set_overhead(overhead, mode == OverheadMode::Subtract);
+        }
+    }
 
     // Execute.
-    let exec_status = S::part_apply(s.exec_status(), exec_est_time, exec_est_count);
+    if let Some(barrier) = barrier {
+        barrier.wait();
+    }
+    let exec_status = progress_status(s.exec_progress(), exec_est_time, exec_est_count);
     let exec_status_count = if exec_status.is_some() {
         cfg.status_count(execs_per_second)
     } else {
         usize::MAX
     };
-    debug!("bench_run_x >>> exec_status_count={exec_status_count}");
-    state.execute(&mut src, run_length, exec_status_count, exec_status);
+    debug!("run_x >>> exec_status_count={exec_status_count}");
+    state.execute(
+        &mut src,
+        run_length,
+        exec_status_count,
+        exec_status,
+        cfg.cancel_token(),
+    );
     if let Some(end_exec_status) = s.end_exec_status() {
         end_exec_status();
     }
+    state.warn_if_near_overhead();
+    s.end_status(state.outs());
 
     state
 }
@@ -259,6 +777,7 @@ mod status {
             RunLength::CountWithTimeout(count, duration) => {
                 RunLength::CountWithTimeout(count, duration * 2)
             }
+            RunLength::Precision { .. } => unreachable!("status tests don't use precision targets"),
         };
 
         println!(
@@ -669,7 +1188,7 @@ Executing bench_run for \(approx.\) (\d+) millis: (\d+) of \(approx.\) (\d+) exe
 mod simple_tests {
     use super::*;
     use crate::multi::test_support::LognormalLatencySrc;
-    use crate::{LatencyUnit, RunLength, multi::LatencySrc1};
+    use crate::{LatencyUnit, PrecisionTarget, RunLength, multi::LatencySrc1};
     use std::{thread, time::Duration};
 
     /// Helper to get a clean config with minimal warmup/calibration for fast tests.
@@ -740,4 +1259,308 @@ mod simple_tests {
         // 5ms per iteration, 500ms timeout → stops well before 200
         assert!(out.n() > 50 && out.n() < 200);
     }
+
+    #[test]
+    fn test_bench_run_precision_converges() {
+        let src =
+            LognormalLatencySrc::<1>::new_with_default_sigmas(1, [FpSeconds::from_micros(10)]);
+        let target = PrecisionTarget::new(0.01, 0.05);
+        let out = bench_run_arg_cfg(
+            &quick_cfg(),
+            src,
+            RunLength::Precision {
+                target,
+                max_count: Some(100_000),
+                max_time: None,
+            },
+        );
+
+        let outcome = out
+            .run_info()
+            .precision()
+            .expect("precision outcome expected");
+        assert!(outcome.converged());
+        assert!(outcome.rel_half_width().unwrap() <= target.rel_half_width());
+        assert!(out.n() < 100_000);
+    }
+
+    #[test]
+    fn test_bench_run_precision_hits_cap() {
+        let src =
+            LognormalLatencySrc::<1>::new_with_default_sigmas(1, [FpSeconds::from_micros(10)]);
+        let target = PrecisionTarget::new(1e-6, 0.05);
+        let out = bench_run_arg_cfg(
+            &quick_cfg(),
+            src,
+            RunLength::Precision {
+                target,
+                max_count: Some(500),
+                max_time: None,
+            },
+        );
+
+        let outcome = out
+            .run_info()
+            .precision()
+            .expect("precision outcome expected");
+        assert!(!outcome.converged());
+        assert!(outcome.rel_half_width().unwrap() > target.rel_half_width());
+        assert_eq!(500, out.n());
+    }
+
+    #[test]
+    fn test_bench_run_precision_duo() {
+        let src = LognormalLatencySrc::<2>::new_with_default_sigmas(
+            1,
+            [FpSeconds::from_micros(10), FpSeconds::from_micros(12)],
+        );
+        let target = PrecisionTarget::new(0.01, 0.05);
+        let out = bench_run_arg_cfg(
+            &quick_cfg(),
+            src,
+            RunLength::Precision {
+                target,
+                max_count: Some(100_000),
+                max_time: Some(Duration::from_secs(60)),
+            },
+        );
+
+        let outcome = out
+            .run_info()
+            .precision()
+            .expect("precision outcome expected");
+        assert!(outcome.converged());
+        let ci = out.welch_ratio_ci(target.alpha());
+        let width = (ci.1 - ci.0) / 2. / out.ratio_medians_f1_f2_from_lns();
+        assert!(width <= target.rel_half_width());
+    }
+
+    #[test]
+    fn test_bench_run_no_precision_outcome() {
+        let out = bench_run_arg_cfg(&quick_cfg(), LatencySrc1::new(|| ()), RunLength::Count(5));
+        assert_eq!(None, out.run_info().precision());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bench_run_thread_cpu_time() {
+        use crate::{ThreadCpuTime, multi::LatencySrc2b};
+
+        let sleep = Duration::from_millis(1);
+        let src = LatencySrc2b::with_measurement(
+            || thread::sleep(sleep),
+            || thread::sleep(sleep),
+            2,
+            ThreadCpuTime,
+        );
+        let out = bench_run_arg_cfg(&quick_cfg(), src, RunLength::Count(20));
+
+        assert_eq!(20, out.n());
+        // Time spent sleeping is not CPU time.
+        for median in out.medians() {
+            assert!(median < FpSeconds::from(sleep / 4), "median={median:?}");
+        }
+    }
+
+    #[test]
+    fn test_bench_run_overhead_ignored_by_default() {
+        let out = bench_run_arg_cfg(&quick_cfg(), LatencySrc1::new(|| ()), RunLength::Count(5));
+        assert_eq!(None, out.run_info().overhead());
+    }
+
+    #[test]
+    fn test_bench_run_overhead_report() {
+        use crate::{OverheadMode, multi::test_support::ConstLatencySrc};
+
+        let latency = FpSeconds::from_micros(1);
+        let cfg = quick_cfg().with_overhead_mode(OverheadMode::Report);
+        let out = bench_run_arg_cfg(
+            &cfg,
+            ConstLatencySrc::new(1, [latency]),
+            RunLength::Count(100),
+        );
+
+        let estimate = out
+            .run_info()
+            .overhead()
+            .expect("overhead should be reported");
+        assert!(!estimate.subtracted());
+        assert!(estimate.median() > FpSeconds::ZERO);
+        assert!((out.median() - latency).as_f64().abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_bench_run_overhead_subtract() {
+        use crate::{OverheadMode, multi::test_support::ConstLatencySrc};
+
+        let latency = FpSeconds::from_micros(1);
+        let cfg = quick_cfg().with_overhead_mode(OverheadMode::Subtract);
+
+        for batch in [1, 10] {
+            let out = bench_run_arg_cfg(
+                &cfg,
+                ConstLatencySrc::new(batch, [latency, latency]),
+                RunLength::Count(100),
+            );
+
+            let estimate = out
+                .run_info()
+                .overhead()
+                .expect("overhead should be reported");
+            assert!(estimate.subtracted());
+            let expected = (latency - estimate.median() / batch).as_f64();
+            for median in out.medians() {
+                let tol = 1e-9; // recording unit
+                assert!(
+                    (median.as_f64() - expected).abs() <= tol,
+                    "batch={batch}, median={median:?}, expected={expected}"
+                );
+            }
+            assert_eq!(0, out.first().run_info().overhead_floored());
+        }
+
+        // Latencies below the overhead are floored at zero and counted.
+        let out = bench_run_arg_cfg(
+            &cfg,
+            ConstLatencySrc::new(1, [FpSeconds(1e-10)]),
+            RunLength::Count(100),
+        );
+        assert_eq!(100, out.first().run_info().overhead_floored());
+        assert_eq!(0, out.first().n_nz());
+    }
+
+    /// Two identical closures, the first of which to execute in each iteration is slow (e.g., a cold cache) and the
+    /// second fast.
+    fn order_sensitive_pair() -> (impl FnMut(), impl FnMut()) {
+        let calls = std::rc::Rc::new(std::cell::Cell::new(0_u64));
+        let calls2 = calls.clone();
+        let f = move |calls: &std::cell::Cell<u64>| {
+            let slow = calls.get().is_multiple_of(2);
+            calls.set(calls.get() + 1);
+            thread::sleep(Duration::from_micros(if slow { 2_000 } else { 200 }));
+        };
+        (move || f(&calls), move || f(&calls2))
+    }
+
+    #[test]
+    fn test_bench_run_exec_order() {
+        use crate::multi::{ExecOrder, LatencySrc2};
+
+        let (f1, f2) = order_sensitive_pair();
+        let out = bench_run_arg_cfg(&quick_cfg(), LatencySrc2::new(f1, f2), RunLength::Count(40));
+        assert!(out.position_medians().is_none());
+        // A fixed order biases the comparison.
+        assert!(out.ratio_medians_f1_f2() > 4.);
+
+        for order in [ExecOrder::Alternating, ExecOrder::Random(3)] {
+            let (f1, f2) = order_sensitive_pair();
+            let src = LatencySrc2::new(f1, f2).with_order(order);
+            let out = bench_run_arg_cfg(&quick_cfg(), src, RunLength::Count(40));
+
+            let positions = out.position_medians().expect("order is not fixed");
+            println!("order={order:?}: positions={positions:?}");
+            for p in positions {
+                assert_eq!(40, p.n_first + p.n_last);
+                assert!(p.n_first >= 8 && p.n_last >= 8);
+                assert!(p.ratio_last_first().unwrap() < 0.5);
+            }
+        }
+
+        let (f1, f2) = order_sensitive_pair();
+        let src = LatencySrc2::new(f1, f2).with_order(ExecOrder::Alternating);
+        let out = bench_run_arg_cfg(&quick_cfg(), src, RunLength::Count(40));
+        let ratio = out.ratio_medians_f1_f2_from_lns();
+        assert!(0.5 < ratio && ratio < 2., "ratio={ratio}");
+    }
+
+    #[test]
+    fn test_bench_run_cancel() {
+        let out = bench_run_arg_cfg(&quick_cfg(), LatencySrc1::new(|| ()), RunLength::Count(50));
+        assert!(!out.first().run_info().aborted());
+
+        // Cancelled before the run: no data is collected.
+        let token = CancelToken::new();
+        token.cancel();
+        let cfg = quick_cfg().with_cancel_token(token);
+        let out = bench_run_arg_cfg(&cfg, LatencySrc1::new(|| ()), RunLength::Count(50));
+        assert!(out.first().run_info().aborted());
+        assert_eq!(0, out.first().n());
+
+        // Cancelled by a watchdog thread during a long run: the partial output is returned.
+        let token = CancelToken::new();
+        let cfg = quick_cfg().with_cancel_token(token.clone());
+        let start = Instant::now();
+        let out = thread::scope(|s| {
+            s.spawn(|| {
+                thread::sleep(Duration::from_millis(500));
+                token.cancel();
+            });
+            let src = LatencySrc1::new(|| thread::sleep(Duration::from_micros(100)));
+            bench_run_arg_cfg(&cfg, src, RunLength::Time(Duration::from_secs(5)))
+        });
+        println!("elapsed={:?}, n={}", start.elapsed(), out.first().n());
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(out.first().run_info().aborted());
+        assert!(out.first().n() > 0);
+    }
+
+    #[test]
+    fn test_bench_run_adaptive_warmup() {
+        use crate::AdaptiveWarmup;
+
+        let out = bench_run_arg_cfg(&quick_cfg(), LatencySrc1::new(|| ()), RunLength::Count(50));
+        assert!(out.first().run_info().warmup().is_none());
+
+        // The latency halves every 20 executions until the 100th, e.g., as a cache warms up.
+        let mut calls: u64 = 0;
+        let src = LatencySrc1::new(move || {
+            calls += 1;
+            let micros = 50 << (100_u64.saturating_sub(calls) / 20);
+            thread::sleep(Duration::from_micros(micros));
+        });
+        let params = AdaptiveWarmup::new(0, 5000)
+            .with_window(20)
+            .with_tolerance(0.2)
+            .with_stable_windows(2);
+        let cfg = quick_cfg().with_warmup_mode(WarmupMode::Adaptive(params));
+        let out = bench_run_arg_cfg(&cfg, src, RunLength::Count(40));
+
+        let trace = out
+            .first()
+            .run_info()
+            .warmup()
+            .expect("warm-up trace should be recorded");
+        println!("trace={trace:?}");
+        let cutoff = trace.cutoff().expect("steady state should be detected");
+        assert!(cutoff >= 80, "cutoff={cutoff}");
+        assert_eq!(cutoff, trace.iterations());
+        assert!(trace.elapsed() < Duration::from_secs(5));
+        let medians = trace.medians();
+        assert!(medians[0].as_f64() > 3. * medians[medians.len() - 1].as_f64());
+        assert_eq!(40, out.first().n());
+    }
+
+    #[test]
+    fn test_bench_run_hist_bounds() {
+        use crate::HistOverflow;
+
+        // Derived from the estimated iteration rate.
+        let cfg = quick_cfg();
+        let src = LatencySrc1::new(|| thread::sleep(Duration::from_micros(100)));
+        let out = bench_run_arg_cfg(&cfg, src, RunLength::Count(20));
+        let (low, high) = cfg.hist_range(out.first().run_info().execs_per_sec());
+        assert_eq!(low, out.first().hist.low());
+        assert_eq!(high, out.first().hist.high());
+        assert!(high >= 100_000_000, "high={high}");
+
+        // Fixed bounds below the latencies: every execution is rejected.
+        let cfg = quick_cfg()
+            .with_hist_bounds(FpSeconds::ZERO, FpSeconds::from_micros(1))
+            .with_hist_overflow(HistOverflow::Reject);
+        let src = LatencySrc1::new(|| thread::sleep(Duration::from_micros(100)));
+        let out = bench_run_arg_cfg(&cfg, src, RunLength::Count(20));
+        assert_eq!(0, out.first().n());
+        assert_eq!(20, out.first().run_info().out_of_range());
+        assert_eq!(1000, out.first().hist.high());
+    }
 }
@@ -0,0 +1,151 @@
+//! Order in which the closures of a multi-closure latency source are executed within each iteration.
+
+use crate::rng::SplitMix64;
+
+/// Policy determining the order in which the closures of a latency source are executed within each benchmarking
+/// iteration (i.e., each invocation of `next()`).
+///
+/// With a fixed order, any order-dependent effect (e.g., caches or the branch predictor warmed by the first closure
+/// for the next one, allocator reuse) biases the comparison of the closures systematically. The other policies
+/// spread such effects evenly across the closures. Regardless of the policy, latencies are always yielded in the
+/// order of the closures.
+///
+/// See [`BenchOut::position_medians`](super::BenchOut::position_medians) for diagnostics of order-dependent effects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecOrder {
+    /// The closures are executed in their declared order in every iteration.
+    #[default]
+    Fixed,
+    /// The closures are executed in declared order in the first iteration, in reverse order in the next two, in
+    /// declared order in the fourth, and so on, repeating every four iterations (the ABBA pattern for two closures).
+    Alternating,
+    /// The closures are executed in a random order drawn independently for each iteration from the given seed.
+    Random(u64),
+}
+
+/// Produces the execution order of each iteration according to an [`ExecOrder`] policy other than
+/// [`ExecOrder::Fixed`], for which latency sources execute the closures in declared order without an `Orderer`.
+#[derive(Debug, Clone)]
+pub(crate) struct Orderer {
+    policy: ExecOrder,
+    iteration: u64,
+    rng: SplitMix64,
+    order: Vec<usize>,
+}
+
+impl Orderer {
+    /// Creates an instance for `k` closures, or `None` for [`ExecOrder::Fixed`].
+    pub(crate) fn new(policy: ExecOrder, k: usize) -> Option<Self> {
+        let seed = match policy {
+            ExecOrder::Fixed => return None,
+            ExecOrder::Random(seed) => seed,
+            ExecOrder::Alternating => 0,
+        };
+        Some(Self {
+            policy,
+            iteration: 0,
+            rng: SplitMix64::new(seed),
+            order: (0..k).collect(),
+        })
+    }
+
+    /// Returns the execution order for the next iteration, as closure indices.
+    #[inline(always)]
+    pub(crate) fn next_order(&mut self) -> &[usize] {
+        match self.policy {
+            ExecOrder::Fixed => unreachable!("no `Orderer` is created for `ExecOrder::Fixed`"),
+            ExecOrder::Alternating => {
+                // Reverses between iterations 0 and 1 and between iterations 2 and 3 of each period of 4.
+                if self.iteration % 2 == 1 {
+                    self.order.reverse();
+                }
+            }
+            ExecOrder::Random(_) => {
+                for i in (1..self.order.len()).rev() {
+                    let j = self.rng.below(i as u64 + 1) as usize;
+                    self.order.swap(i, j);
+                }
+            }
+        }
+        self.iteration += 1;
+        &self.order
+    }
+
+    /// Execution order of the last iteration.
+    pub(crate) fn last_order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// Same as [`Self::last_order`], as an array.
+    pub(crate) fn last_order_arr<const K: usize>(&self) -> [usize; K] {
+        self.order
+            .as_slice()
+            .try_into()
+            .expect("can't happen: number of closures is `K`")
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- multi::exec_order::test --nocapture
+mod test {
+    use super::*;
+
+    fn orders(policy: ExecOrder, k: usize, n: usize) -> Vec<Vec<usize>> {
+        let mut orderer = Orderer::new(policy, k).unwrap();
+        (0..n).map(|_| orderer.next_order().to_vec()).collect()
+    }
+
+    #[test]
+    fn test_fixed() {
+        use crate::multi::{LatencySrc, LatencySrc2};
+
+        assert!(Orderer::new(ExecOrder::Fixed, 3).is_none());
+
+        let mut src = LatencySrc2::new(|| (), || ()).with_order(ExecOrder::Fixed);
+        src.next();
+        assert_eq!(None, src.order());
+    }
+
+    #[test]
+    fn test_alternating() {
+        let exp = [[0, 1], [1, 0], [1, 0], [0, 1]]
+            .repeat(2)
+            .into_iter()
+            .map(Vec::from)
+            .collect::<Vec<_>>();
+        assert_eq!(exp, orders(ExecOrder::Alternating, 2, 8));
+
+        let orders3 = orders(ExecOrder::Alternating, 3, 4);
+        assert_eq!(vec![0, 1, 2], orders3[0]);
+        assert_eq!(vec![2, 1, 0], orders3[1]);
+        assert_eq!(vec![2, 1, 0], orders3[2]);
+        assert_eq!(vec![0, 1, 2], orders3[3]);
+
+        let mut orderer = Orderer::new(ExecOrder::Alternating, 2).unwrap();
+        orderer.next_order();
+        orderer.next_order();
+        assert_eq!([1, 0], orderer.last_order_arr::<2>());
+    }
+
+    #[test]
+    fn test_random() {
+        let n = 6_000;
+        let orders1 = orders(ExecOrder::Random(7), 3, n);
+        assert_eq!(orders1, orders(ExecOrder::Random(7), 3, n));
+        assert_ne!(orders1, orders(ExecOrder::Random(8), 3, n));
+
+        // Each closure runs first about a third of the time, and each order is a permutation.
+        let mut first_counts = [0_usize; 3];
+        for order in &orders1 {
+            let mut sorted = order.clone();
+            sorted.sort();
+            assert_eq!(vec![0, 1, 2], sorted);
+            first_counts[order[0]] += 1;
+        }
+        assert!(
+            first_counts.iter().all(|&c| (1_800..2_200).contains(&c)),
+            "{first_counts:?}"
+        );
+    }
+}
@@ -0,0 +1,527 @@
+//! K-way comparison statistics for [`BenchOut<K>`]: omnibus tests across all closures, pairwise comparisons with
+//! multiple-comparison control, and rankings.
+
+use super::BenchOut;
+use crate::{
+    Comp,
+    stats_support::{chi2_to_p, f_to_p},
+};
+use basic_stats::core::{AltHyp, Ci, HypTestResult};
+
+/// Adjustment of p-values for multiple comparisons, used by [`BenchOut::pairwise_welch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PAdjust {
+    /// No adjustment.
+    None,
+    /// Bonferroni adjustment: each p-value is multiplied by the number of comparisons.
+    Bonferroni,
+    /// Holm's step-down adjustment, which controls the family-wise error rate like Bonferroni's but is uniformly
+    /// more powerful.
+    Holm,
+}
+
+/// Result of the one-way analysis of variance of the natural logarithms of the latencies of `K` closures, computed
+/// by [`BenchOut::anova_ln`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anova {
+    f: f64,
+    df_between: f64,
+    df_within: f64,
+}
+
+impl Anova {
+    /// F statistic.
+    pub fn f(&self) -> f64 {
+        self.f
+    }
+
+    /// Degrees of freedom between closures (number of closures with non-zero observations minus `1`).
+    pub fn df_between(&self) -> f64 {
+        self.df_between
+    }
+
+    /// Degrees of freedom within closures (total number of non-zero observations minus the number of closures with
+    /// non-zero observations).
+    pub fn df_within(&self) -> f64 {
+        self.df_within
+    }
+
+    /// p-value of the hypothesis that all closures have the same `mean(ln(latency))`.
+    pub fn p(&self) -> f64 {
+        f_to_p(self.f, self.df_between, self.df_within)
+    }
+
+    /// Test of the hypothesis that all closures have the same `mean(ln(latency))`, with confidence level
+    /// `(1 - alpha)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` not in open interval `(0, 1)`.
+    pub fn test(&self, alpha: f64) -> HypTestResult {
+        assert!(
+            0. < alpha && alpha < 1.,
+            "`alpha` must be in open interval `(0, 1)`"
+        );
+        HypTestResult::new(self.p(), alpha, AltHyp::Ne)
+    }
+}
+
+/// Result of the Kruskal-Wallis test on the latencies of `K` closures, computed by [`BenchOut::kruskal_wallis`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KruskalWallis {
+    h: f64,
+    df: f64,
+}
+
+impl KruskalWallis {
+    /// H statistic, corrected for ties.
+    pub fn h(&self) -> f64 {
+        self.h
+    }
+
+    /// Degrees of freedom of the chi-squared approximation (`K - 1`).
+    pub fn df(&self) -> f64 {
+        self.df
+    }
+
+    /// p-value of the hypothesis that the latencies of all closures have the same distribution, from the
+    /// chi-squared approximation.
+    pub fn p(&self) -> f64 {
+        chi2_to_p(self.h, self.df)
+    }
+
+    /// Test of the hypothesis that the latencies of all closures have the same distribution, with confidence level
+    /// `(1 - alpha)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` not in open interval `(0, 1)`.
+    pub fn test(&self, alpha: f64) -> HypTestResult {
+        assert!(
+            0. < alpha && alpha < 1.,
+            "`alpha` must be in open interval `(0, 1)`"
+        );
+        HypTestResult::new(self.p(), alpha, AltHyp::Ne)
+    }
+}
+
+/// Welch comparison of closures `i` and `j` of a [`BenchOut<K>`], computed by [`BenchOut::pairwise_welch`].
+///
+/// As with [`Comp`], the ratio is `median(latency(f_i)) / median(latency(f_j))`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairComp {
+    i: usize,
+    j: usize,
+    ratio: f64,
+    ratio_ci: Ci,
+    p: f64,
+    p_adj: f64,
+}
+
+impl PairComp {
+    /// Index of the first closure.
+    pub fn i(&self) -> usize {
+        self.i
+    }
+
+    /// Index of the second closure.
+    pub fn j(&self) -> usize {
+        self.j
+    }
+
+    /// Ratio of medians estimated from the means of the natural logarithms of the latencies.
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Welch confidence interval for the ratio of medians, adjusted for multiple comparisons (see
+    /// [`BenchOut::pairwise_welch`]).
+    pub fn ratio_ci(&self) -> Ci {
+        self.ratio_ci
+    }
+
+    /// Unadjusted p-value of Welch's two-sided test of the hypothesis that the ratio of medians is `1`.
+    pub fn p(&self) -> f64 {
+        self.p
+    }
+
+    /// p-value adjusted for multiple comparisons.
+    pub fn p_adj(&self) -> f64 {
+        self.p_adj
+    }
+
+    /// `true` if the adjusted p-value is less than `alpha`.
+    pub fn is_significant(&self, alpha: f64) -> bool {
+        self.p_adj < alpha
+    }
+}
+
+/// Ranking of the closures of a [`BenchOut<K>`] from fastest to slowest, computed by [`BenchOut::ranking`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ranking {
+    groups: Vec<Vec<usize>>,
+}
+
+impl Ranking {
+    /// Groups of statistically tied closure indices, from the fastest group to the slowest. Within each group, the
+    /// indices are in increasing order of median latency.
+    pub fn groups(&self) -> &[Vec<usize>] {
+        &self.groups
+    }
+
+    /// Zero-based rank of the group containing closure `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not the index of a closure.
+    pub fn rank_of(&self, i: usize) -> usize {
+        self.groups
+            .iter()
+            .position(|group| group.contains(&i))
+            .expect("`i` must be the index of a closure")
+    }
+}
+
+/// Adjusts the p-values `ps` for multiple comparisons.
+fn adjust(ps: &[f64], p_adjust: PAdjust) -> Vec<f64> {
+    let m = ps.len() as f64;
+    match p_adjust {
+        PAdjust::None => ps.to_vec(),
+        PAdjust::Bonferroni => ps.iter().map(|p| (p * m).min(1.)).collect(),
+        PAdjust::Holm => {
+            let mut order = (0..ps.len()).collect::<Vec<_>>();
+            order.sort_by(|&a, &b| ps[a].total_cmp(&ps[b]));
+            let mut adj = vec![0.; ps.len()];
+            let mut running_max = 0_f64;
+            for (rank, &idx) in order.iter().enumerate() {
+                running_max = running_max.max(((m - rank as f64) * ps[idx]).min(1.));
+                adj[idx] = running_max;
+            }
+            adj
+        }
+    }
+}
+
+impl<const K: usize> BenchOut<K> {
+    /// One-way analysis of variance (ANOVA) of the natural logarithms of the latencies of the `K` closures.
+    ///
+    /// Under the assumption that latencies are approximately log-normal, this tests whether all closures have the
+    /// same median latency. Like Student's t-test, it assumes equal variances, so prefer
+    /// [`Self::pairwise_welch`] when the closures have very different variability.
+    ///
+    /// Closures without non-zero observations are left out of the analysis, including its degrees of freedom.
+    ///
+    /// # Panics
+    ///
+    /// Panics if fewer than 2 closures have non-zero observations or the total number of non-zero observations is not
+    /// greater than the number of such closures.
+    pub fn anova_ln(&self) -> Anova {
+        let groups = self.arr.iter().filter(|b| b.n_nz > 0).count() as u64;
+        assert!(
+            groups >= 2,
+            "at least 2 closures must have non-zero observations"
+        );
+        let n_tot = self.arr.iter().map(|b| b.n_nz).sum::<u64>();
+        assert!(
+            n_tot > groups,
+            "total number of non-zero observations must be greater than the number of closures with any"
+        );
+        let grand_mean = self.arr.iter().map(|b| b.sum_ln).sum::<f64>() / n_tot as f64;
+
+        let (ss_between, ss_within) =
+            self.arr
+                .iter()
+                .filter(|b| b.n_nz > 0)
+                .fold((0., 0.), |(ssb, ssw), b| {
+                    let n = b.n_nz as f64;
+                    let mean = b.sum_ln / n;
+                    (
+                        ssb + n * (mean - grand_mean).powi(2),
+                        ssw + (b.sum2_ln - n * mean.powi(2)).max(0.),
+                    )
+                });
+
+        let df_between = (groups - 1) as f64;
+        let df_within = (n_tot - groups) as f64;
+        Anova {
+            f: (ss_between / df_between) / (ss_within / df_within),
+            df_between,
+            df_within,
+        }
+    }
+
+    /// Kruskal-Wallis test on the latencies of the `K` closures, a distribution-free alternative to
+    /// [`Self::anova_ln`]. Ranks are computed from the values recorded in the histograms, with ties assigned
+    /// mid-ranks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `K < 2` or any closure has no observations.
+    pub fn kruskal_wallis(&self) -> KruskalWallis {
+        assert!(K >= 2, "`K` must be at least 2");
+        assert!(
+            self.arr.iter().all(|b| b.n() > 0),
+            "every closure must have observations"
+        );
+
+        let mut recorded = self
+            .arr
+            .iter()
+            .enumerate()
+            .flat_map(|(k, b)| {
+                b.hist
+                    .iter_recorded()
+                    .map(move |x| (x.value_iterated_to(), k, x.count_at_value()))
+            })
+            .collect::<Vec<_>>();
+        recorded.sort_unstable_by_key(|&(value, _, _)| value);
+
+        let mut rank_sums = [0_f64; K];
+        let mut tie_sum = 0.;
+        let mut rank_base = 0_f64;
+        for tied in recorded.chunk_by(|a, b| a.0 == b.0) {
+            let t = tied.iter().map(|&(_, _, count)| count as f64).sum::<f64>();
+            let mid_rank = rank_base + (t + 1.) / 2.;
+            for &(_, k, count) in tied {
+                rank_sums[k] += mid_rank * count as f64;
+            }
+            tie_sum += t.powi(3) - t;
+            rank_base += t;
+        }
+
+        let n_tot = rank_base;
+        let h = 12. / (n_tot * (n_tot + 1.))
+            * rank_sums
+                .iter()
+                .zip(&self.arr)
+                .map(|(r, b)| r.powi(2) / b.n() as f64)
+                .sum::<f64>()
+            - 3. * (n_tot + 1.);
+        let tie_correction = 1. - tie_sum / (n_tot.powi(3) - n_tot);
+
+        KruskalWallis {
+            h: if tie_correction > 0. {
+                h / tie_correction
+            } else {
+                0.
+            },
+            df: (K - 1) as f64,
+        }
+    }
+
+    /// Welch comparisons of all pairs of closures `(i, j)`, with `i < j`, in lexicographic order.
+    ///
+    /// The p-values are adjusted according to `p_adjust`. The confidence intervals have confidence level
+    /// `(1 - alpha)` for [`PAdjust::None`]; otherwise, they are Bonferroni-adjusted to have simultaneous confidence
+    /// level `(1 - alpha)`, since Holm's procedure has no corresponding confidence intervals.
+    ///
+    /// Assumes that latencies are approximately log-normal, as for [`Comp`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - The number of non-zero observations of any closure is `<= 1`.
+    /// - The `stdev_ln()` of both closures of any pair is zero.
+    /// - `alpha` not in open interval `(0, 1)`.
+    pub fn pairwise_welch(&self, alpha: f64, p_adjust: PAdjust) -> Vec<PairComp> {
+        let pairs = (0..K)
+            .flat_map(|i| (i + 1..K).map(move |j| (i, j)))
+            .collect::<Vec<_>>();
+        let ci_alpha = match p_adjust {
+            PAdjust::None => alpha,
+            PAdjust::Bonferroni | PAdjust::Holm => alpha / pairs.len().max(1) as f64,
+        };
+
+        let comps = pairs
+            .iter()
+            .map(|&(i, j)| Comp(&self.arr[i], &self.arr[j]))
+            .collect::<Vec<_>>();
+        let ps = comps
+            .iter()
+            .map(|comp| comp.welch_ln_p(0., AltHyp::Ne))
+            .collect::<Vec<_>>();
+        let ps_adj = adjust(&ps, p_adjust);
+
+        pairs
+            .iter()
+            .zip(&comps)
+            .enumerate()
+            .map(|(idx, (&(i, j), comp))| PairComp {
+                i,
+                j,
+                ratio: comp.ratio_medians_f1_f2_from_lns(),
+                ratio_ci: comp.welch_ratio_ci(ci_alpha),
+                p: ps[idx],
+                p_adj: ps_adj[idx],
+            })
+            .collect()
+    }
+
+    /// Ranking of the closures from fastest to slowest median latency, with statistically tied closures grouped
+    /// together.
+    ///
+    /// The closures are sorted by `mean(ln(latency))`. Proceeding in that order, a closure joins the current group
+    /// if the adjusted Welch p-value (see [`Self::pairwise_welch`]) for it and every closure already in the group is
+    /// at least `alpha`; otherwise, it starts a new group.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`Self::pairwise_welch`].
+    pub fn ranking(&self, alpha: f64, p_adjust: PAdjust) -> Ranking {
+        let comps = self.pairwise_welch(alpha, p_adjust);
+        let significant = |a: usize, b: usize| {
+            let (i, j) = (a.min(b), a.max(b));
+            comps
+                .iter()
+                .find(|c| c.i == i && c.j == j)
+                .is_some_and(|c| c.is_significant(alpha))
+        };
+
+        let mean_lns = self.mean_lns();
+        let mut order = (0..K).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| mean_lns[a].total_cmp(&mean_lns[b]));
+
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for k in order {
+            match groups.last_mut() {
+                Some(group) if group.iter().all(|&g| !significant(g, k)) => group.push(k),
+                _ => groups.push(vec![k]),
+            }
+        }
+        Ranking { groups }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- multi::k_way::test --nocapture
+mod test {
+    use super::*;
+    use crate::{
+        BenchCfg, FpSeconds,
+        test_support::{LO_STDEV_LN, lognormal_samp},
+    };
+    use basic_stats::{approx_eq, core::AcceptedHyp};
+
+    const ALPHA: f64 = 0.05;
+
+    /// Four closures with median ratios `1 : 1.001 : 1.1 : 1.3`.
+    fn four_way_out() -> BenchOut<4> {
+        let cfg = BenchCfg::default();
+        let samp =
+            lognormal_samp(-11., *LO_STDEV_LN, 2_000).map(|x| [x, x * 1.001, x * 1.1, x * 1.3]);
+        BenchOut::from_iter(&cfg, samp)
+    }
+
+    #[test]
+    fn test_anova_ln() {
+        let out = four_way_out();
+        let anova = out.anova_ln();
+        println!("{anova:?}");
+        assert_eq!(3., anova.df_between());
+        assert_eq!(8_000. - 4., anova.df_within());
+        assert_eq!(AcceptedHyp::Alt, anova.test(ALPHA).accepted());
+
+        // Reference computation from the natural logarithms of the observations.
+        let cfg = BenchCfg::default();
+        let groups = [[1., 2., 3.], [2., 3., 4.], [5., 6., 7.]].map(|g| g.map(|x: f64| x * 1e-6));
+        let out = BenchOut::<3>::from_iter(&cfg, (0..3).map(|t| groups.map(|g| FpSeconds(g[t]))));
+        let lns = groups.map(|g| g.map(f64::ln));
+        let means = lns.map(|g| g.iter().sum::<f64>() / 3.);
+        let grand = means.iter().sum::<f64>() / 3.;
+        let ssb = means.iter().map(|m| 3. * (m - grand).powi(2)).sum::<f64>();
+        let ssw = lns
+            .iter()
+            .zip(&means)
+            .map(|(g, m)| g.iter().map(|x| (x - m).powi(2)).sum::<f64>())
+            .sum::<f64>();
+        approx_eq!((ssb / 2.) / (ssw / 6.), out.anova_ln().f(), 1e-9);
+    }
+
+    #[test]
+    fn test_anova_ln_empty_group() {
+        // A closure without non-zero observations doesn't count towards the degrees of freedom.
+        let cfg = BenchCfg::default();
+        let groups = [[1., 2., 3.], [2., 3., 4.], [0., 0., 0.]].map(|g| g.map(|x: f64| x * 1e-6));
+        let out = BenchOut::<3>::from_iter(&cfg, (0..3).map(|t| groups.map(|g| FpSeconds(g[t]))));
+        let anova = out.anova_ln();
+        assert_eq!(1., anova.df_between());
+        assert_eq!(4., anova.df_within());
+
+        let out2 = BenchOut::<2>::from_iter(
+            &cfg,
+            (0..3).map(|t| [0, 1].map(|k| FpSeconds(groups[k][t]))),
+        );
+        assert_eq!(out2.anova_ln(), anova);
+    }
+
+    #[test]
+    fn test_kruskal_wallis() {
+        let cfg = BenchCfg::default();
+
+        // Without ties: H = 12 / (9 * 10) * (6^2 + 15^2 + 24^2) / 3 - 3 * 10 = 7.2.
+        let out = BenchOut::<3>::from_iter(
+            &cfg,
+            (1..=3).map(|t| [t, t + 3, t + 6].map(FpSeconds::from_micros)),
+        );
+        let kw = out.kruskal_wallis();
+        approx_eq!(7.2, kw.h(), 1e-9);
+        assert_eq!(2., kw.df());
+
+        // Identical samples.
+        let out = BenchOut::<3>::from_iter(&cfg, (1..=5).map(|t| [FpSeconds::from_micros(t); 3]));
+        let kw = out.kruskal_wallis();
+        approx_eq!(0., kw.h(), 1e-9);
+        assert_eq!(AcceptedHyp::Null, kw.test(ALPHA).accepted());
+
+        assert_eq!(
+            AcceptedHyp::Alt,
+            four_way_out().kruskal_wallis().test(ALPHA).accepted()
+        );
+    }
+
+    #[test]
+    fn test_adjust() {
+        let ps = [0.01, 0.04, 0.03, 0.005];
+        assert_eq!(ps.to_vec(), adjust(&ps, PAdjust::None));
+        let bonferroni = adjust(&ps, PAdjust::Bonferroni);
+        for (b, exp) in bonferroni.iter().zip([0.04, 0.16, 0.12, 0.02]) {
+            approx_eq!(exp, *b, 1e-12);
+        }
+        let holm = adjust(&ps, PAdjust::Holm);
+        for (h, exp) in holm.iter().zip([0.03, 0.06, 0.06, 0.02]) {
+            approx_eq!(exp, *h, 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_pairwise_welch_and_ranking() {
+        let out = four_way_out();
+
+        let comps = out.pairwise_welch(ALPHA, PAdjust::Holm);
+        assert_eq!(6, comps.len());
+        for c in &comps {
+            println!("{c:?}");
+            let comp = Comp(&out.arr[c.i()], &out.arr[c.j()]);
+            assert_eq!(comp.welch_ln_p(0., AltHyp::Ne), c.p());
+            assert!(c.p() <= c.p_adj());
+            assert_eq!(comp.welch_ratio_ci(ALPHA / 6.), c.ratio_ci());
+            assert!(c.ratio_ci().0 < c.ratio() && c.ratio() < c.ratio_ci().1);
+        }
+        assert_eq!((0, 1), (comps[0].i(), comps[0].j()));
+        assert!(!comps[0].is_significant(ALPHA));
+        assert!(comps.iter().skip(1).all(|c| c.is_significant(ALPHA)));
+
+        let unadjusted = out.pairwise_welch(ALPHA, PAdjust::None);
+        assert_eq!(comps[0].p(), unadjusted[0].p_adj());
+        assert_eq!(
+            Comp(&out.arr[0], &out.arr[1]).welch_ratio_ci(ALPHA),
+            unadjusted[0].ratio_ci()
+        );
+
+        let ranking = out.ranking(ALPHA, PAdjust::Holm);
+        assert_eq!(&[vec![0, 1], vec![2], vec![3]], ranking.groups());
+        assert_eq!(0, ranking.rank_of(1));
+        assert_eq!(2, ranking.rank_of(3));
+    }
+}
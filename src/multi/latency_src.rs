@@ -1,4 +1,5 @@
-use crate::{FpSeconds, latency, latency_n};
+use super::{ExecOrder, exec_order::Orderer};
+use crate::{FpSeconds, Measurement, WallTime};
 use std::iter;
 
 /// An infinite iterator that encapsulates `K` closures and yields their latencies from individual executions
@@ -13,7 +14,31 @@ use std::iter;
 /// The iterator doesn't have to be infinite, though normally it would be. If a finite iterator is used
 /// with the benchmarking functions, the benchmark will end prematurely if the iterator is exhausted
 /// before the specified benchmark run length.
+///
+/// Sources that vary the order in which the closures are executed (see [`ExecOrder`]) report it with
+/// [`Self::order`], which enables [`BenchOut::position_medians`](super::BenchOut::position_medians).
 pub trait LatencySrc<const K: usize>: Iterator<Item = ([FpSeconds; K], usize)> {
+    /// Returns the order in which the closures were executed in the last invocation of `next()`, as closure
+    /// indices, or `None` if the order is fixed.
+    fn order(&self) -> Option<[usize; K]> {
+        None
+    }
+
+    /// Returns the time spent in the last invocation of `next()` that is not included in the yielded latencies,
+    /// e.g., creating inputs with a setup closure. It counts towards the run length and the estimated iteration rate,
+    /// but not towards the latencies. Zero by default.
+    fn setup_latency(&self) -> FpSeconds {
+        FpSeconds::ZERO
+    }
+
+    /// Returns the latency of timing a no-op the same way `next()` times each closure: with the same
+    /// [`Measurement`] and, for batched sources, over a batch of no-ops, yielding the mean latency per execution as
+    /// `next()` does. Used to calibrate the per-execution timer overhead (see [`OverheadMode`](crate::OverheadMode)).
+    /// Times a single no-op with [`WallTime`] by default.
+    fn empty_latency(&self) -> FpSeconds {
+        WallTime.latency(|| ())
+    }
+
     /// Returns an iterator that, for each pair `(arr, n)` yielded by `self`, yields `arr` `n` times.
     fn dole_out(&mut self) -> impl Iterator<Item = [FpSeconds; K]> {
         self.map(|(value, count)| iter::repeat_n(value, count))
@@ -26,118 +51,587 @@ pub trait LatencySrc<const K: usize>: Iterator<Item = ([FpSeconds; K], usize)> {
     }
 }
 
-impl<const K: usize, T: LatencySrc<K>> LatencySrc<K> for &mut T {}
+impl<const K: usize, T: LatencySrc<K>> LatencySrc<K> for &mut T {
+    fn order(&self) -> Option<[usize; K]> {
+        (**self).order()
+    }
+
+    fn setup_latency(&self) -> FpSeconds {
+        (**self).setup_latency()
+    }
+
+    fn empty_latency(&self) -> FpSeconds {
+        (**self).empty_latency()
+    }
+}
 
 /// A [`LatencySrc`] that yields the latency of the invocation of a single closure on each
 /// call to `next()`.
-pub struct LatencySrc1<F: FnMut()> {
+///
+/// Latencies are measured with `M` (see [`Measurement`]), which defaults to [`WallTime`].
+pub struct LatencySrc1<F: FnMut(), M: Measurement = WallTime> {
     f: F,
+    m: M,
 }
 
 impl<F: FnMut()> LatencySrc1<F> {
-    /// Returns an instance of `Self`.
+    /// Returns an instance of `Self` that measures wall-clock time.
     pub fn new(f: F) -> Self {
-        Self { f }
+        Self::with_measurement(f, WallTime)
+    }
+}
+
+impl<F: FnMut(), M: Measurement> LatencySrc1<F, M> {
+    /// Returns an instance of `Self` that measures latencies with `m`.
+    pub fn with_measurement(f: F, m: M) -> Self {
+        Self { f, m }
     }
 }
 
-impl<F: FnMut()> Iterator for LatencySrc1<F> {
+impl<F: FnMut(), M: Measurement> Iterator for LatencySrc1<F, M> {
     type Item = ([FpSeconds; 1], usize);
 
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
-        Some(([latency(&mut self.f).into()], 1))
+        Some(([self.m.latency(&mut self.f)], 1))
     }
 }
 
-impl<F: FnMut()> LatencySrc<1> for LatencySrc1<F> {}
+impl<F: FnMut(), M: Measurement> LatencySrc<1> for LatencySrc1<F, M> {
+    fn empty_latency(&self) -> FpSeconds {
+        self.m.latency(|| ())
+    }
+}
 
 /// A [`LatencySrc`] that yields the latencies of the invocations of two closures on each
 /// call to `next()`.
-pub struct LatencySrc2<F1: FnMut(), F2: FnMut()> {
+///
+/// The closures are executed in the order determined by [`Self::with_order`], `f1` first by default.
+/// Latencies are measured with `M` (see [`Measurement`]), which defaults to [`WallTime`].
+pub struct LatencySrc2<F1: FnMut(), F2: FnMut(), M: Measurement = WallTime> {
     f1: F1,
     f2: F2,
+    m: M,
+    orderer: Option<Orderer>,
 }
 
 impl<F1: FnMut(), F2: FnMut()> LatencySrc2<F1, F2> {
-    /// Returns an instance of `Self`.
+    /// Returns an instance of `Self` that measures wall-clock time.
     pub fn new(f1: F1, f2: F2) -> Self {
-        Self { f1, f2 }
+        Self::with_measurement(f1, f2, WallTime)
+    }
+}
+
+impl<F1: FnMut(), F2: FnMut(), M: Measurement> LatencySrc2<F1, F2, M> {
+    /// Returns an instance of `Self` that measures latencies with `m`.
+    pub fn with_measurement(f1: F1, f2: F2, m: M) -> Self {
+        Self {
+            f1,
+            f2,
+            m,
+            orderer: None,
+        }
+    }
+
+    /// Sets the order in which the closures are executed on each call to `next()`.
+    /// Defaults to [`ExecOrder::Fixed`].
+    pub fn with_order(mut self, order: ExecOrder) -> Self {
+        self.orderer = Orderer::new(order, 2);
+        self
     }
 }
 
-impl<F1: FnMut(), F2: FnMut()> Iterator for LatencySrc2<F1, F2> {
+impl<F1: FnMut(), F2: FnMut(), M: Measurement> Iterator for LatencySrc2<F1, F2, M> {
     type Item = ([FpSeconds; 2], usize);
 
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
-        Some((
-            [latency(&mut self.f1).into(), latency(&mut self.f2).into()],
-            1,
-        ))
+        if self.orderer.as_mut().is_none_or(|o| o.next_order()[0] == 0) {
+            let value1 = self.m.latency(&mut self.f1);
+            let value2 = self.m.latency(&mut self.f2);
+            Some(([value1, value2], 1))
+        } else {
+            let value2 = self.m.latency(&mut self.f2);
+            let value1 = self.m.latency(&mut self.f1);
+            Some(([value1, value2], 1))
+        }
     }
 }
 
-impl<F1: FnMut(), F2: FnMut()> LatencySrc<2> for LatencySrc2<F1, F2> {}
+impl<F1: FnMut(), F2: FnMut(), M: Measurement> LatencySrc<2> for LatencySrc2<F1, F2, M> {
+    fn order(&self) -> Option<[usize; 2]> {
+        self.orderer.as_ref().map(|o| o.last_order_arr())
+    }
+
+    fn empty_latency(&self) -> FpSeconds {
+        self.m.latency(|| ())
+    }
+}
 
 /// A [`LatencySrc`] that batches invocations of a single `f` into groups of size `batch` and yields
 /// the average latency of each group `batch` times before proceding to the next group execution.
-pub struct LatencySrc1b<F: FnMut()> {
+///
+/// Latencies are measured with `M` (see [`Measurement`]), which defaults to [`WallTime`].
+pub struct LatencySrc1b<F: FnMut(), M: Measurement = WallTime> {
     /// Target closure
     f: F,
     /// Batch size
     batch: usize,
+    /// Clock
+    m: M,
 }
 
 impl<F1: FnMut()> LatencySrc1b<F1> {
-    /// Returns an instance of `Self`.
+    /// Returns an instance of `Self` that measures wall-clock time.
     pub fn new(f: F1, batch: usize) -> Self {
-        Self { f, batch }
+        Self::with_measurement(f, batch, WallTime)
+    }
+}
+
+impl<F1: FnMut(), M: Measurement> LatencySrc1b<F1, M> {
+    /// Returns an instance of `Self` that measures latencies with `m`.
+    pub fn with_measurement(f: F1, batch: usize, m: M) -> Self {
+        Self { f, batch, m }
     }
 }
 
-impl<F: FnMut()> Iterator for LatencySrc1b<F> {
+impl<F: FnMut(), M: Measurement> Iterator for LatencySrc1b<F, M> {
     type Item = ([FpSeconds; 1], usize);
 
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
         let batch = self.batch;
-        let value = latency_n(&mut self.f, batch).as_secs_f64() / batch as f64;
-        Some(([value.into()], batch))
+        let value = self.m.latency_n(&mut self.f, batch) / batch;
+        Some(([value], batch))
     }
 }
 
-impl<F1: FnMut()> LatencySrc<1> for LatencySrc1b<F1> {}
+impl<F1: FnMut(), M: Measurement> LatencySrc<1> for LatencySrc1b<F1, M> {
+    fn empty_latency(&self) -> FpSeconds {
+        self.m.latency_n(|| (), self.batch) / self.batch
+    }
+}
 
 /// A [`LatencySrc`] that batches invocations of two closures` into groups of size `batch` and yields
 /// the respective average latencies of the closures for each group `batch` times before proceding
 /// to the next group execution..
-pub struct LatencySrc2b<F1: FnMut(), F2: FnMut()> {
+///
+/// The closures are executed in the order determined by [`Self::with_order`], `f1` first by default.
+/// Latencies are measured with `M` (see [`Measurement`]), which defaults to [`WallTime`].
+pub struct LatencySrc2b<F1: FnMut(), F2: FnMut(), M: Measurement = WallTime> {
     f1: F1,
     f2: F2,
     batch: usize,
+    m: M,
+    orderer: Option<Orderer>,
 }
 
 impl<F1: FnMut(), F2: FnMut()> LatencySrc2b<F1, F2> {
-    /// Returns an instance of `Self`.
+    /// Returns an instance of `Self` that measures wall-clock time.
     pub fn new(f1: F1, f2: F2, batch: usize) -> Self {
-        Self { f1, f2, batch }
+        Self::with_measurement(f1, f2, batch, WallTime)
+    }
+}
+
+impl<F1: FnMut(), F2: FnMut(), M: Measurement> LatencySrc2b<F1, F2, M> {
+    /// Returns an instance of `Self` that measures latencies with `m`.
+    pub fn with_measurement(f1: F1, f2: F2, batch: usize, m: M) -> Self {
+        Self {
+            f1,
+            f2,
+            batch,
+            m,
+            orderer: None,
+        }
+    }
+
+    /// Sets the order in which the closures' batches are executed on each call to `next()`.
+    /// Defaults to [`ExecOrder::Fixed`].
+    pub fn with_order(mut self, order: ExecOrder) -> Self {
+        self.orderer = Orderer::new(order, 2);
+        self
     }
 }
 
-impl<F1: FnMut(), F2: FnMut()> Iterator for LatencySrc2b<F1, F2> {
+impl<F1: FnMut(), F2: FnMut(), M: Measurement> Iterator for LatencySrc2b<F1, F2, M> {
     type Item = ([FpSeconds; 2], usize);
 
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
         let batch = self.batch;
-        let value1 = latency_n(&mut self.f1, batch).as_secs_f64() / batch as f64;
-        let value2 = latency_n(&mut self.f2, batch).as_secs_f64() / batch as f64;
-        Some(([value1.into(), value2.into()], batch))
+        if self.orderer.as_mut().is_none_or(|o| o.next_order()[0] == 0) {
+            let value1 = self.m.latency_n(&mut self.f1, batch) / batch;
+            let value2 = self.m.latency_n(&mut self.f2, batch) / batch;
+            Some(([value1, value2], batch))
+        } else {
+            let value2 = self.m.latency_n(&mut self.f2, batch) / batch;
+            let value1 = self.m.latency_n(&mut self.f1, batch) / batch;
+            Some(([value1, value2], batch))
+        }
     }
 }
 
-impl<F1: FnMut(), F2: FnMut()> LatencySrc<2> for LatencySrc2b<F1, F2> {}
+impl<F1: FnMut(), F2: FnMut(), M: Measurement> LatencySrc<2> for LatencySrc2b<F1, F2, M> {
+    fn order(&self) -> Option<[usize; 2]> {
+        self.orderer.as_ref().map(|o| o.last_order_arr())
+    }
+
+    fn empty_latency(&self) -> FpSeconds {
+        self.m.latency_n(|| (), self.batch) / self.batch
+    }
+}
+
+/// A [`LatencySrc`] that, on each call to `next()`, creates an input with `setup` and yields the latency of the
+/// invocation of `routine` with that input.
+///
+/// Only `routine` is timed: neither `setup` nor the dropping of the output of `routine` are included in the
+/// latencies. The time spent in `setup` is measured separately and counts towards the run length (see
+/// [`LatencySrc::setup_latency`]). Latencies are measured with `M` (see [`Measurement`]), which defaults to
+/// [`WallTime`].
+///
+/// Setup sources are provided for one and two routines. For more routines sharing a setup, implement
+/// [`LatencySrc`] directly, reporting the setup time with [`LatencySrc::setup_latency`].
+pub struct LatencySrc1s<S, R, M: Measurement = WallTime> {
+    setup: S,
+    routine: R,
+    m: M,
+    setup_latency: FpSeconds,
+}
+
+impl<I, O, S: FnMut() -> I, R: FnMut(I) -> O> LatencySrc1s<S, R> {
+    /// Returns an instance of `Self` that measures wall-clock time.
+    pub fn new(setup: S, routine: R) -> Self {
+        Self::with_measurement(setup, routine, WallTime)
+    }
+}
+
+impl<I, O, S: FnMut() -> I, R: FnMut(I) -> O, M: Measurement> LatencySrc1s<S, R, M> {
+    /// Returns an instance of `Self` that measures latencies with `m`.
+    pub fn with_measurement(setup: S, routine: R, m: M) -> Self {
+        Self {
+            setup,
+            routine,
+            m,
+            setup_latency: FpSeconds::ZERO,
+        }
+    }
+}
+
+impl<I, O, S: FnMut() -> I, R: FnMut(I) -> O, M: Measurement> Iterator for LatencySrc1s<S, R, M> {
+    type Item = ([FpSeconds; 1], usize);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (setup_latency, input) = timed(&self.m, &mut self.setup);
+        self.setup_latency = setup_latency;
+        let (value, output) = timed(&self.m, || (self.routine)(input));
+        drop(output);
+        Some(([value], 1))
+    }
+}
+
+impl<I, O, S: FnMut() -> I, R: FnMut(I) -> O, M: Measurement> LatencySrc<1>
+    for LatencySrc1s<S, R, M>
+{
+    fn setup_latency(&self) -> FpSeconds {
+        self.setup_latency
+    }
+
+    fn empty_latency(&self) -> FpSeconds {
+        self.m.latency(|| ())
+    }
+}
+
+/// A [`LatencySrc`] that, on each call to `next()`, creates an input with `setup` and yields the latencies of the
+/// invocations of `routine1` and `routine2` with identical copies of that input.
+///
+/// Only the routines are timed: neither `setup`, the cloning of the input, nor the dropping of the outputs of the
+/// routines are included in the latencies. The time spent in `setup` and cloning counts towards the run length (see
+/// [`LatencySrc::setup_latency`]). Latencies are measured with `M` (see [`Measurement`]), which defaults to
+/// [`WallTime`]. The routines are executed in the order determined by [`Self::with_order`], `routine1` first by
+/// default.
+pub struct LatencySrc2s<S, R1, R2, M: Measurement = WallTime> {
+    setup: S,
+    routine1: R1,
+    routine2: R2,
+    m: M,
+    orderer: Option<Orderer>,
+    setup_latency: FpSeconds,
+}
+
+impl<I: Clone, O1, O2, S: FnMut() -> I, R1: FnMut(I) -> O1, R2: FnMut(I) -> O2>
+    LatencySrc2s<S, R1, R2>
+{
+    /// Returns an instance of `Self` that measures wall-clock time.
+    pub fn new(setup: S, routine1: R1, routine2: R2) -> Self {
+        Self::with_measurement(setup, routine1, routine2, WallTime)
+    }
+}
+
+impl<I: Clone, O1, O2, S: FnMut() -> I, R1: FnMut(I) -> O1, R2: FnMut(I) -> O2, M: Measurement>
+    LatencySrc2s<S, R1, R2, M>
+{
+    /// Returns an instance of `Self` that measures latencies with `m`.
+    pub fn with_measurement(setup: S, routine1: R1, routine2: R2, m: M) -> Self {
+        Self {
+            setup,
+            routine1,
+            routine2,
+            m,
+            orderer: None,
+            setup_latency: FpSeconds::ZERO,
+        }
+    }
+
+    /// Sets the order in which the routines are executed on each call to `next()`.
+    /// Defaults to [`ExecOrder::Fixed`].
+    pub fn with_order(mut self, order: ExecOrder) -> Self {
+        self.orderer = Orderer::new(order, 2);
+        self
+    }
+
+    #[inline(always)]
+    fn exec1(&mut self, input: I) -> FpSeconds {
+        let (value, output) = timed(&self.m, || (self.routine1)(input));
+        drop(output);
+        value
+    }
+
+    #[inline(always)]
+    fn exec2(&mut self, input: I) -> FpSeconds {
+        let (value, output) = timed(&self.m, || (self.routine2)(input));
+        drop(output);
+        value
+    }
+}
+
+impl<I: Clone, O1, O2, S: FnMut() -> I, R1: FnMut(I) -> O1, R2: FnMut(I) -> O2, M: Measurement>
+    Iterator for LatencySrc2s<S, R1, R2, M>
+{
+    type Item = ([FpSeconds; 2], usize);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (setup_latency, (input1, input2)) = timed(&self.m, || {
+            let input1 = (self.setup)();
+            let input2 = input1.clone();
+            (input1, input2)
+        });
+        self.setup_latency = setup_latency;
+        if self.orderer.as_mut().is_none_or(|o| o.next_order()[0] == 0) {
+            let value1 = self.exec1(input1);
+            let value2 = self.exec2(input2);
+            Some(([value1, value2], 1))
+        } else {
+            let value2 = self.exec2(input2);
+            let value1 = self.exec1(input1);
+            Some(([value1, value2], 1))
+        }
+    }
+}
+
+impl<I: Clone, O1, O2, S: FnMut() -> I, R1: FnMut(I) -> O1, R2: FnMut(I) -> O2, M: Measurement>
+    LatencySrc<2> for LatencySrc2s<S, R1, R2, M>
+{
+    fn order(&self) -> Option<[usize; 2]> {
+        self.orderer.as_ref().map(|o| o.last_order_arr())
+    }
+
+    fn setup_latency(&self) -> FpSeconds {
+        self.setup_latency
+    }
+
+    fn empty_latency(&self) -> FpSeconds {
+        self.m.latency(|| ())
+    }
+}
+
+/// Batched version of [`LatencySrc1s`]: on each call to `next()`, creates `batch` inputs with `setup`, invokes
+/// `routine` on each of them, and yields the average latency of the invocations `batch` times.
+///
+/// Only the invocations of `routine` are timed. The outputs are dropped after the timed region. The time spent in
+/// `setup` counts towards the run length (see [`LatencySrc::setup_latency`]).
+pub struct LatencySrc1sb<S, R, M: Measurement = WallTime> {
+    setup: S,
+    routine: R,
+    batch: usize,
+    m: M,
+    setup_latency: FpSeconds,
+}
+
+impl<I, O, S: FnMut() -> I, R: FnMut(I) -> O> LatencySrc1sb<S, R> {
+    /// Returns an instance of `Self` that measures wall-clock time.
+    pub fn new(setup: S, routine: R, batch: usize) -> Self {
+        Self::with_measurement(setup, routine, batch, WallTime)
+    }
+}
+
+impl<I, O, S: FnMut() -> I, R: FnMut(I) -> O, M: Measurement> LatencySrc1sb<S, R, M> {
+    /// Returns an instance of `Self` that measures latencies with `m`.
+    pub fn with_measurement(setup: S, routine: R, batch: usize, m: M) -> Self {
+        Self {
+            setup,
+            routine,
+            batch,
+            m,
+            setup_latency: FpSeconds::ZERO,
+        }
+    }
+}
+
+impl<I, O, S: FnMut() -> I, R: FnMut(I) -> O, M: Measurement> Iterator for LatencySrc1sb<S, R, M> {
+    type Item = ([FpSeconds; 1], usize);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = self.batch;
+        let (setup_latency, inputs) = timed(&self.m, || {
+            iter::repeat_with(&mut self.setup)
+                .take(batch)
+                .collect::<Vec<_>>()
+        });
+        self.setup_latency = setup_latency;
+        let (total, outputs) = timed_batch(&self.m, &mut self.routine, inputs);
+        drop(outputs);
+        Some(([total / batch], batch))
+    }
+}
+
+impl<I, O, S: FnMut() -> I, R: FnMut(I) -> O, M: Measurement> LatencySrc<1>
+    for LatencySrc1sb<S, R, M>
+{
+    fn setup_latency(&self) -> FpSeconds {
+        self.setup_latency
+    }
+
+    fn empty_latency(&self) -> FpSeconds {
+        self.m.latency_n(|| (), self.batch) / self.batch
+    }
+}
+
+/// Batched version of [`LatencySrc2s`]: on each call to `next()`, creates `batch` inputs with `setup`, invokes
+/// `routine1` and `routine2` on identical copies of each of them, and yields the respective average latencies of
+/// the invocations `batch` times.
+///
+/// Only the invocations of the routines are timed. The outputs are dropped after the timed regions. The time spent
+/// in `setup` and cloning counts towards the run length (see [`LatencySrc::setup_latency`]).
+/// The routines' batches are executed in the order determined by [`Self::with_order`], `routine1` first by default.
+pub struct LatencySrc2sb<S, R1, R2, M: Measurement = WallTime> {
+    setup: S,
+    routine1: R1,
+    routine2: R2,
+    batch: usize,
+    m: M,
+    orderer: Option<Orderer>,
+    setup_latency: FpSeconds,
+}
+
+impl<I: Clone, O1, O2, S: FnMut() -> I, R1: FnMut(I) -> O1, R2: FnMut(I) -> O2>
+    LatencySrc2sb<S, R1, R2>
+{
+    /// Returns an instance of `Self` that measures wall-clock time.
+    pub fn new(setup: S, routine1: R1, routine2: R2, batch: usize) -> Self {
+        Self::with_measurement(setup, routine1, routine2, batch, WallTime)
+    }
+}
+
+impl<I: Clone, O1, O2, S: FnMut() -> I, R1: FnMut(I) -> O1, R2: FnMut(I) -> O2, M: Measurement>
+    LatencySrc2sb<S, R1, R2, M>
+{
+    /// Returns an instance of `Self` that measures latencies with `m`.
+    pub fn with_measurement(setup: S, routine1: R1, routine2: R2, batch: usize, m: M) -> Self {
+        Self {
+            setup,
+            routine1,
+            routine2,
+            batch,
+            m,
+            orderer: None,
+            setup_latency: FpSeconds::ZERO,
+        }
+    }
+
+    /// Sets the order in which the routines' batches are executed on each call to `next()`.
+    /// Defaults to [`ExecOrder::Fixed`].
+    pub fn with_order(mut self, order: ExecOrder) -> Self {
+        self.orderer = Orderer::new(order, 2);
+        self
+    }
+}
+
+impl<I: Clone, O1, O2, S: FnMut() -> I, R1: FnMut(I) -> O1, R2: FnMut(I) -> O2, M: Measurement>
+    Iterator for LatencySrc2sb<S, R1, R2, M>
+{
+    type Item = ([FpSeconds; 2], usize);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = self.batch;
+        let (setup_latency, (inputs1, inputs2)) = timed(&self.m, || {
+            let inputs1 = iter::repeat_with(&mut self.setup)
+                .take(batch)
+                .collect::<Vec<_>>();
+            let inputs2 = inputs1.clone();
+            (inputs1, inputs2)
+        });
+        self.setup_latency = setup_latency;
+        let (total1, total2) = if self.orderer.as_mut().is_none_or(|o| o.next_order()[0] == 0) {
+            let (total1, outputs1) = timed_batch(&self.m, &mut self.routine1, inputs1);
+            drop(outputs1);
+            let (total2, outputs2) = timed_batch(&self.m, &mut self.routine2, inputs2);
+            drop(outputs2);
+            (total1, total2)
+        } else {
+            let (total2, outputs2) = timed_batch(&self.m, &mut self.routine2, inputs2);
+            drop(outputs2);
+            let (total1, outputs1) = timed_batch(&self.m, &mut self.routine1, inputs1);
+            drop(outputs1);
+            (total1, total2)
+        };
+        Some(([total1 / batch, total2 / batch], batch))
+    }
+}
+
+impl<I: Clone, O1, O2, S: FnMut() -> I, R1: FnMut(I) -> O1, R2: FnMut(I) -> O2, M: Measurement>
+    LatencySrc<2> for LatencySrc2sb<S, R1, R2, M>
+{
+    fn order(&self) -> Option<[usize; 2]> {
+        self.orderer.as_ref().map(|o| o.last_order_arr())
+    }
+
+    fn setup_latency(&self) -> FpSeconds {
+        self.setup_latency
+    }
+
+    fn empty_latency(&self) -> FpSeconds {
+        self.m.latency_n(|| (), self.batch) / self.batch
+    }
+}
+
+/// Invokes `f` once and returns its latency according to `m`, together with its output.
+/// The output is returned, rather than dropped, so that dropping it is not timed.
+#[inline(always)]
+fn timed<O>(m: &impl Measurement, f: impl FnOnce() -> O) -> (FpSeconds, O) {
+    let start = m.start();
+    let output = f();
+    (m.end(start), output)
+}
+
+/// Invokes `routine` on each of `inputs` and returns the total latency according to `m`, together with the outputs.
+/// The outputs are returned, rather than dropped, so that dropping them is not timed.
+#[inline(always)]
+fn timed_batch<I, O>(
+    m: &impl Measurement,
+    routine: &mut impl FnMut(I) -> O,
+    inputs: Vec<I>,
+) -> (FpSeconds, Vec<O>) {
+    let mut outputs = Vec::with_capacity(inputs.len());
+    let start = m.start();
+    for input in inputs {
+        outputs.push(routine(input));
+    }
+    (m.end(start), outputs)
+}
 
 #[cfg(feature = "_test_support")]
 pub mod test_support {
@@ -359,3 +853,127 @@ pub mod test_support {
         }
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- multi::latency_src::test --nocapture
+mod test {
+    use super::*;
+    use std::{thread, time::Duration};
+
+    const PAUSE: Duration = Duration::from_millis(20);
+
+    /// Output whose drop is slow, to check that dropping is not timed.
+    struct SlowDrop;
+
+    impl Drop for SlowDrop {
+        fn drop(&mut self) {
+            thread::sleep(PAUSE);
+        }
+    }
+
+    fn assert_untimed(latency: FpSeconds) {
+        assert!(latency < (PAUSE / 4).into(), "latency={latency:?}");
+    }
+
+    #[test]
+    fn test_setup_src_excludes_setup_and_drop() {
+        let mut src = LatencySrc1s::new(|| thread::sleep(PAUSE), |()| SlowDrop);
+        let ([latency], count) = src.next().unwrap();
+        assert_eq!(count, 1);
+        assert_untimed(latency);
+        assert!(src.setup_latency() >= PAUSE.into());
+
+        let mut src = LatencySrc2s::new(|| thread::sleep(PAUSE), |()| SlowDrop, |()| SlowDrop);
+        let ([latency1, latency2], count) = src.next().unwrap();
+        assert_eq!(count, 1);
+        assert_untimed(latency1);
+        assert_untimed(latency2);
+        assert!(src.setup_latency() >= PAUSE.into());
+    }
+
+    #[test]
+    fn test_setup_src_b_excludes_setup_and_drop() {
+        let mut src = LatencySrc1sb::new(|| thread::sleep(PAUSE / 2), |()| SlowDrop, 3);
+        let ([latency], count) = src.next().unwrap();
+        assert_eq!(count, 3);
+        assert_untimed(latency);
+        assert!(src.setup_latency() >= (PAUSE * 3 / 2).into());
+
+        let mut src =
+            LatencySrc2sb::new(|| thread::sleep(PAUSE / 2), |()| SlowDrop, |()| SlowDrop, 3);
+        let ([latency1, latency2], count) = src.next().unwrap();
+        assert_eq!(count, 3);
+        assert_untimed(latency1);
+        assert_untimed(latency2);
+        assert!(src.setup_latency() >= (PAUSE * 3 / 2).into());
+    }
+
+    #[test]
+    fn test_setup_src_shares_inputs() {
+        let mut next_input = 0_u32;
+        let mut inputs1 = Vec::new();
+        let mut inputs2 = Vec::new();
+        let mut src = LatencySrc2s::new(
+            || {
+                next_input += 1;
+                vec![next_input; 4]
+            },
+            |v: Vec<u32>| inputs1.push(v),
+            |v: Vec<u32>| inputs2.push(v),
+        );
+        src.by_ref().take(5).for_each(drop);
+        drop(src);
+
+        assert_eq!(inputs1.len(), 5);
+        assert_eq!(inputs1, inputs2);
+        assert_eq!(inputs1[4], vec![5; 4]);
+    }
+
+    #[test]
+    fn test_setup_src_b_shares_inputs() {
+        let mut setup_count = 0_u32;
+        let mut inputs1 = Vec::new();
+        let mut inputs2 = Vec::new();
+        let mut src = LatencySrc2sb::new(
+            || {
+                setup_count += 1;
+                setup_count
+            },
+            |x| inputs1.push(x),
+            |x| inputs2.push(x),
+            4,
+        );
+        src.by_ref().take(2).for_each(drop);
+        drop(src);
+
+        assert_eq!(setup_count, 8);
+        assert_eq!(inputs1, (1..=8).collect::<Vec<_>>());
+        assert_eq!(inputs1, inputs2);
+    }
+
+    /// Clock that reads the same elapsed time for any measurement.
+    struct FixedClock(FpSeconds);
+
+    impl Measurement for FixedClock {
+        type Start = ();
+
+        fn start(&self) {}
+
+        fn end(&self, _start: ()) -> FpSeconds {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_empty_latency_per_execution() {
+        let tick = FpSeconds::from_micros(1);
+        let src = LatencySrc1::with_measurement(|| (), FixedClock(tick));
+        assert_eq!(tick, src.empty_latency());
+
+        // Batched sources yield the mean over the batch, as `next()` does.
+        let mut src = LatencySrc1b::with_measurement(|| (), 100, FixedClock(tick));
+        assert_eq!(tick / 100, src.empty_latency());
+        assert_eq!(([tick / 100], 100), src.next().unwrap());
+    }
+}
@@ -1,12 +1,27 @@
 //! Benchmark multiple closures together, executing each of them in every benchmarking iteration.
 //!
 //! The benchmarking functions in this module produce a single [`BenchOut<K>`] that holds one
-//! [`crate::BenchOut`] per closure.
+//! [`crate::BenchOut`] per closure. When the number of closures is only known at runtime, [`NamedLatencySrc`] and
+//! the `bench_run_named*` functions produce a [`NamedBenchOut`] instead.
+//!
+//! The order in which the closures are executed within each iteration can be varied to avoid order-dependent bias
+//! (see [`ExecOrder`]).
 
 mod bench_out;
 mod bench_run;
+mod exec_order;
+mod k_way;
 mod latency_src;
+mod named;
+mod paired_diff;
+mod positions;
 
 pub use bench_out::*;
 pub use bench_run::*;
+pub use exec_order::ExecOrder;
+pub use k_way::*;
 pub use latency_src::*;
+pub use named::*;
+pub(crate) use paired_diff::*;
+pub use positions::PositionMedians;
+pub(crate) use positions::PositionStats;
@@ -0,0 +1,500 @@
+//! Benchmarking of a number of closures known only at runtime, identified by name.
+
+use super::{
+    ExecOrder, PositionMedians, PositionStats,
+    bench_run::{Collector, CollectorSrc, Ordered, run_x},
+    exec_order::Orderer,
+};
+use crate::{
+    BenchCfg, Comp, FpSeconds, LatencyUnit, Measurement, RunInfo, RunLength, SummaryStats,
+    WallTime,
+    status::{DefaultStatus, NoStatus, Status},
+};
+use std::{collections::HashSet, io::stderr, ops::Index};
+
+/// Named closure benchmarked by [`NamedLatencySrc`].
+pub type NamedTarget<'a> = (String, Box<dyn FnMut() + 'a>);
+
+/// An infinite iterator that encapsulates a runtime-sized list of named closures and yields their latencies, in the
+/// order of the list, on each call to `next()`. The closures are executed in the order determined by
+/// [`Self::with_order`], the order of the list by default. It is the counterpart of [`LatencySrc`](super::LatencySrc) for
+/// a number of closures not known at compile time, e.g., candidates built from a configuration or a parameter
+/// sweep.
+///
+/// Each call to `next()` yields a pair `(latencies, 1)`, as for [`LatencySrc2`](super::LatencySrc2).
+///
+/// Latencies are measured with `M` (see [`Measurement`]), which defaults to [`WallTime`].
+pub struct NamedLatencySrc<'a, M: Measurement = WallTime> {
+    targets: Vec<NamedTarget<'a>>,
+    m: M,
+    orderer: Option<Orderer>,
+}
+
+impl<'a> NamedLatencySrc<'a> {
+    /// Returns an instance of `Self` that measures wall-clock time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `targets` is empty or its names are not unique.
+    pub fn new(targets: Vec<NamedTarget<'a>>) -> Self {
+        Self::with_measurement(targets, WallTime)
+    }
+}
+
+impl<'a, M: Measurement> NamedLatencySrc<'a, M> {
+    /// Returns an instance of `Self` that measures latencies with `m`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `targets` is empty or its names are not unique.
+    pub fn with_measurement(targets: Vec<NamedTarget<'a>>, m: M) -> Self {
+        assert!(!targets.is_empty(), "`targets` must not be empty");
+        let mut names = HashSet::new();
+        assert!(
+            targets.iter().all(|(name, _)| names.insert(name)),
+            "target names must be unique"
+        );
+        Self {
+            targets,
+            m,
+            orderer: None,
+        }
+    }
+
+    /// Sets the order in which the closures are executed on each call to `next()`.
+    /// Defaults to [`ExecOrder::Fixed`].
+    pub fn with_order(mut self, order: ExecOrder) -> Self {
+        self.orderer = Orderer::new(order, self.targets.len());
+        self
+    }
+
+    /// Returns the order in which the closures were executed in the last invocation of `next()`, as closure
+    /// indices, or `None` if the order is fixed.
+    pub fn order(&self) -> Option<&[usize]> {
+        self.orderer.as_ref().map(Orderer::last_order)
+    }
+
+    /// Names of the closures, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.targets.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Number of closures.
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Always `false`, as `self` has at least one closure.
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+}
+
+impl<M: Measurement> Iterator for NamedLatencySrc<'_, M> {
+    type Item = (Vec<FpSeconds>, usize);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut latencies = vec![FpSeconds::ZERO; self.targets.len()];
+        match &mut self.orderer {
+            Some(orderer) => {
+                for &k in orderer.next_order() {
+                    latencies[k] = self.m.latency(&mut self.targets[k].1);
+                }
+            }
+            None => {
+                for (latency, (_, f)) in latencies.iter_mut().zip(&mut self.targets) {
+                    *latency = self.m.latency(f);
+                }
+            }
+        }
+        Some((latencies, 1))
+    }
+}
+
+/// Adapts a [`NamedLatencySrc`] to yield the execution order of the closures together with their latencies.
+struct OrderedNamedSrc<'a, M: Measurement>(NamedLatencySrc<'a, M>);
+
+impl<M: Measurement> Iterator for OrderedNamedSrc<'_, M> {
+    type Item = (Ordered<Vec<FpSeconds>, Vec<usize>>, usize);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (latencies, count) = self.0.next()?;
+        let order = self.0.order().map(<[usize]>::to_vec);
+        Some((
+            Ordered {
+                latencies,
+                order,
+                setup: FpSeconds::ZERO,
+            },
+            count,
+        ))
+    }
+}
+
+impl<M: Measurement> CollectorSrc<Ordered<Vec<FpSeconds>, Vec<usize>>> for OrderedNamedSrc<'_, M> {
+    fn empty_latency(&self) -> FpSeconds {
+        self.0.m.latency(|| ())
+    }
+}
+
+/// Contains the data resulting from benchmarking the closures of a [`NamedLatencySrc`]; the runtime-sized
+/// counterpart of [`BenchOut<K>`](super::BenchOut).
+///
+/// Holds one [`crate::BenchOut`] per closure, accessible by name or by position.
+///
+/// With feature **"serde"**, this struct can be serialized and deserialized (see [`crate::BenchOut`]).
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NamedBenchOut {
+    names: Vec<String>,
+    outs: Vec<crate::BenchOut>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    positions: PositionStats,
+}
+
+impl NamedBenchOut {
+    /// Instantiates an empty `Self` for closures with the given `names`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `names` is empty or not unique.
+    pub fn new(cfg: &BenchCfg, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let names = names.into_iter().map(Into::into).collect::<Vec<String>>();
+        assert!(!names.is_empty(), "`names` must not be empty");
+        let mut unique = HashSet::new();
+        assert!(
+            names.iter().all(|name| unique.insert(name)),
+            "names must be unique"
+        );
+        let outs = names.iter().map(|_| crate::BenchOut::new(cfg)).collect();
+        Self {
+            names,
+            outs,
+            positions: PositionStats::new(cfg),
+        }
+    }
+
+    /// Names of the closures, in order.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Number of closures.
+    pub fn len(&self) -> usize {
+        self.outs.len()
+    }
+
+    /// Always `false`, as `self` has at least one closure.
+    pub fn is_empty(&self) -> bool {
+        self.outs.is_empty()
+    }
+
+    /// Position of the closure named `name`, if any.
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    /// Output of the closure named `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&crate::BenchOut> {
+        self.position(name).map(|i| &self.outs[i])
+    }
+
+    /// Iterator over the names and outputs of the closures, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &crate::BenchOut)> {
+        self.names.iter().map(String::as_str).zip(&self.outs)
+    }
+
+    /// Returns a [`Comp`] comparing the closures named `name1` and `name2`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either name is not the name of a closure.
+    pub fn comp(&self, name1: &str, name2: &str) -> Comp<'_> {
+        Comp(&self[name1], &self[name2])
+    }
+
+    /// Latency unit used in data collection.
+    pub fn recording_unit(&self) -> LatencyUnit {
+        self.outs[0].recording_unit()
+    }
+
+    /// Information about the execution of the benchmark run that produced `self`.
+    pub fn run_info(&self) -> &RunInfo {
+        self.outs[0].run_info()
+    }
+
+    /// Number of observations (sample size) for each closure.
+    pub fn n(&self) -> u64 {
+        self.outs[0].n()
+    }
+
+    /// Summary descriptive statistics for each closure, in order.
+    pub fn summaries(&self) -> Vec<SummaryStats> {
+        self.outs.iter().map(|b| b.summary()).collect()
+    }
+
+    /// Median latencies of each closure, in order.
+    pub fn medians(&self) -> Vec<FpSeconds> {
+        self.outs.iter().map(|b| b.median()).collect()
+    }
+
+    /// Median latencies of each closure, in order, when it was executed first and when it was executed last in an
+    /// iteration.
+    ///
+    /// Returns `None` if the closures were executed in a fixed order (see [`ExecOrder`]).
+    pub fn position_medians(&self) -> Option<Vec<PositionMedians>> {
+        self.positions.medians(self.recording_unit())
+    }
+}
+
+impl Index<&str> for NamedBenchOut {
+    type Output = crate::BenchOut;
+
+    /// # Panics
+    ///
+    /// Panics if `name` is not the name of a closure.
+    fn index(&self, name: &str) -> &Self::Output {
+        self.get(name)
+            .unwrap_or_else(|| panic!("no closure named {name:?}"))
+    }
+}
+
+impl Index<usize> for NamedBenchOut {
+    type Output = crate::BenchOut;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.outs[i]
+    }
+}
+
+impl Collector for NamedBenchOut {
+    type Latencies = Ordered<Vec<FpSeconds>, Vec<usize>>;
+
+    fn outs(&self) -> &[crate::BenchOut] {
+        &self.outs
+    }
+
+    fn outs_mut(&mut self) -> &mut [crate::BenchOut] {
+        &mut self.outs
+    }
+
+    fn positions_mut(&mut self) -> &mut PositionStats {
+        &mut self.positions
+    }
+
+    fn capture(&mut self, batch_latencies: (Self::Latencies, usize)) {
+        let (
+            Ordered {
+                latencies, order, ..
+            },
+            count,
+        ) = batch_latencies;
+        assert_eq!(
+            self.outs.len(),
+            latencies.len(),
+            "number of latencies must match number of closures"
+        );
+        let mut accepted = true;
+        for (b, &latency) in self.outs.iter_mut().zip(&latencies) {
+            accepted &= b.capture_data((latency, count));
+        }
+        // Latencies by position are only captured if all closures accepted the observation.
+        if accepted && let Some(order) = order {
+            let unit = self.recording_unit();
+            self.positions.capture(unit, &latencies, &order, count);
+        }
+    }
+
+    fn clear(&mut self) {
+        for b in &mut self.outs {
+            b.reset();
+        }
+        self.positions.reset();
+    }
+
+    fn setup_latency(latencies: &Self::Latencies) -> FpSeconds {
+        latencies.setup
+    }
+}
+
+/// Repeatedly invokes `src.next()`, collects the resulting latency data in a [`NamedBenchOut`] object, and
+/// *optionally* reports progress status during benchmark execution.
+///
+/// Has the same warm-up and execution semantics as [`bench_run_x`](super::bench_run_x).
+///
+/// Arguments:
+/// - `cfg` - bench configuration used to run the benchmark.
+/// - `src` - named closures to benchmark.
+/// - `run_length` - target run length (iteration count and/or duration) for data collection.
+/// - `s` - status handler for reporting warm-up and execution progress.
+pub fn bench_run_named_x<'a, M: Measurement, S: Status<'a>>(
+    cfg: &BenchCfg,
+    src: NamedLatencySrc<'_, M>,
+    run_length: RunLength,
+    s: S,
+) -> NamedBenchOut {
+    let state = NamedBenchOut::new(cfg, src.names());
+    run_x(cfg, state, OrderedNamedSrc(src), run_length, s, None, None)
+}
+
+/// Benchmarks the closures of `src` with the default bench configuration.
+/// This function calls [`bench_run_named_arg_cfg`] with the default bench configuration.
+///
+/// Arguments:
+/// - `src` - named closures to benchmark.
+/// - `run_length` - target run length (iteration count and/or duration) for data collection.
+pub fn bench_run_named<M: Measurement>(
+    src: NamedLatencySrc<'_, M>,
+    run_length: RunLength,
+) -> NamedBenchOut {
+    let cfg = BenchCfg::default();
+    bench_run_named_arg_cfg(&cfg, src, run_length)
+}
+
+/// Benchmarks the closures of `src`.
+/// This function calls [`bench_run_named_x`] with a no-op progress status handler.
+///
+/// Arguments:
+/// - `cfg` - bench configuration used to run the benchmark.
+/// - `src` - named closures to benchmark.
+/// - `run_length` - target run length (iteration count and/or duration) for data collection.
+pub fn bench_run_named_arg_cfg<M: Measurement>(
+    cfg: &BenchCfg,
+    src: NamedLatencySrc<'_, M>,
+    run_length: RunLength,
+) -> NamedBenchOut {
+    bench_run_named_x(cfg, src, run_length, NoStatus)
+}
+
+/// Benchmarks the closures of `src` with the default bench configuration, reporting status to `stderr`.
+/// This function calls [`bench_run_named_with_status_arg_cfg`] with the default bench configuration.
+///
+/// Arguments:
+/// - `src` - named closures to benchmark.
+/// - `run_length` - target run length (iteration count and/or duration) for data collection.
+pub fn bench_run_named_with_status<M: Measurement>(
+    src: NamedLatencySrc<'_, M>,
+    run_length: RunLength,
+) -> NamedBenchOut {
+    let cfg = BenchCfg::default();
+    bench_run_named_with_status_arg_cfg(&cfg, src, run_length)
+}
+
+/// Benchmarks the closures of `src`, reporting status to `stderr`.
+/// This function calls [`bench_run_named_x`] with a pre-defined status handler that outputs benchmark status to
+/// `stderr`.
+///
+/// Arguments:
+/// - `cfg` - bench configuration used to run the benchmark.
+/// - `src` - named closures to benchmark.
+/// - `run_length` - target run length (iteration count and/or duration) for data collection.
+pub fn bench_run_named_with_status_arg_cfg<M: Measurement>(
+    cfg: &BenchCfg,
+    src: NamedLatencySrc<'_, M>,
+    run_length: RunLength,
+) -> NamedBenchOut {
+    let mut w = stderr();
+    let s = DefaultStatus::new(
+        &mut w,
+        "Warming up".to_owned(),
+        "Executing bench_run".to_owned(),
+    );
+
+    bench_run_named_x(cfg, src, run_length, s)
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- multi::named::test --nocapture
+mod test {
+    use super::*;
+    use crate::OverheadMode;
+    use std::{hint::black_box, thread, time::Duration};
+
+    fn quick_cfg() -> BenchCfg {
+        BenchCfg::default()
+            .with_warmup_millis(0)
+            .with_status_millis(1)
+            .with_recording_unit(LatencyUnit::NANO)
+    }
+
+    fn sleepers(micros: &[u64]) -> Vec<NamedTarget<'static>> {
+        micros
+            .iter()
+            .map(|&us| {
+                let f: Box<dyn FnMut()> =
+                    Box::new(move || thread::sleep(Duration::from_micros(us)));
+                (format!("sleep_{us}us"), f)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bench_run_named() {
+        let src = NamedLatencySrc::new(sleepers(&[100, 1_000, 3_000]));
+        let out = bench_run_named_arg_cfg(&quick_cfg(), src, RunLength::Count(20));
+
+        assert_eq!(3, out.len());
+        assert_eq!(["sleep_100us", "sleep_1000us", "sleep_3000us"], out.names());
+        assert_eq!(20, out.n());
+        assert!(out.run_info().execs_per_sec().is_some());
+        assert!(out["sleep_100us"].median() < out["sleep_1000us"].median());
+        assert!(out["sleep_1000us"].median() < out["sleep_3000us"].median());
+        assert!(out[1].median() >= FpSeconds::from_micros(1_000));
+        assert!(out.get("sleep_2000us").is_none());
+        assert!(
+            out.comp("sleep_3000us", "sleep_1000us")
+                .ratio_medians_f1_f2()
+                > 1.
+        );
+        assert_eq!(out.medians()[2], out["sleep_3000us"].median());
+    }
+
+    #[test]
+    fn test_bench_run_named_borrowing_and_overhead() {
+        let mut calls = [0_u64; 2];
+        let [c0, c1] = &mut calls;
+        let targets: Vec<NamedTarget<'_>> = vec![
+            ("a".to_owned(), Box::new(|| *c0 += black_box(1))),
+            ("b".to_owned(), Box::new(|| *c1 += black_box(1))),
+        ];
+        let cfg = quick_cfg().with_overhead_mode(OverheadMode::Report);
+        let out =
+            bench_run_named_arg_cfg(&cfg, NamedLatencySrc::new(targets), RunLength::Count(50));
+
+        assert_eq!(50, out.n());
+        assert!(out.run_info().overhead().is_some());
+        assert!(out["b"].run_info().overhead().is_some());
+        // Execs-per-second probing and warm-up also invoke the closures.
+        assert_eq!(calls[0], calls[1]);
+        assert!(calls[0] >= 50);
+    }
+
+    #[test]
+    fn test_bench_run_named_exec_order() {
+        let src = NamedLatencySrc::new(sleepers(&[100, 200, 300]));
+        let out = bench_run_named_arg_cfg(&quick_cfg(), src, RunLength::Count(10));
+        assert!(out.position_medians().is_none());
+
+        let src = NamedLatencySrc::new(sleepers(&[100, 200, 300])).with_order(ExecOrder::Random(1));
+        let out = bench_run_named_arg_cfg(&quick_cfg(), src, RunLength::Count(30));
+        let positions = out.position_medians().expect("order is not fixed");
+        assert_eq!(3, positions.len());
+        // Each iteration has one first and one last closure.
+        assert_eq!(30, positions.iter().map(|p| p.n_first).sum::<u64>());
+        assert_eq!(30, positions.iter().map(|p| p.n_last).sum::<u64>());
+        assert!(out["sleep_100us"].median() < out["sleep_300us"].median());
+    }
+
+    #[test]
+    fn test_named_latency_src_panics_on_duplicate_names() {
+        let result = std::panic::catch_unwind(|| {
+            let mut targets = sleepers(&[1]);
+            targets.extend(sleepers(&[1]));
+            NamedLatencySrc::new(targets);
+        });
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! Paired differences of the natural logarithms of latencies collected in the same benchmarking iteration.
+
+use crate::{FpSeconds, Timing, new_timing};
+use basic_stats::core::SampleMoments;
+
+/// Scale factor applied to `|ln(latency(f1)) - ln(latency(f2))|` before recording it in a histogram.
+///
+/// Absolute log-differences below `0.5 / LN_DIFF_SCALE` are treated as zero.
+const LN_DIFF_SCALE: f64 = 1_000_000.;
+
+/// Initial highest trackable value of the paired-difference histograms (auto-resizable).
+const LN_DIFF_HIST_HIGH: u64 = 1_000_000;
+
+/// Accumulates the paired differences `ln(latency(f1)) - ln(latency(f2))` for the two closures of a
+/// [`DuoOut`](crate::duo::DuoOut), one difference per benchmarking iteration (or batch).
+///
+/// Keeps the running sums required for paired Student t statistics and, optionally, histograms of the absolute
+/// differences split by sign, required for the Wilcoxon signed-rank test. As these track differences of logarithms
+/// rather than latencies, they are always auto-resizable, regardless of
+/// [`BenchCfg::hist_bounds`](crate::BenchCfg::hist_bounds) and
+/// [`BenchCfg::hist_overflow`](crate::BenchCfg::hist_overflow).
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct PairedDiff {
+    pub(crate) n: u64,
+    pub(crate) sum: f64,
+    pub(crate) sum2: f64,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_support::timing_v2_opt", default)
+    )]
+    pub(crate) hist_pos: Option<Timing>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_support::timing_v2_opt", default)
+    )]
+    pub(crate) hist_neg: Option<Timing>,
+}
+
+impl PairedDiff {
+    /// Creates a new empty instance, with sign-split histograms if `with_hist` is `true`.
+    pub(crate) fn new(with_hist: bool, sigfig: u8) -> Self {
+        let new_hist = || with_hist.then(|| new_timing(LN_DIFF_HIST_HIGH, sigfig));
+        Self {
+            n: 0,
+            sum: 0.,
+            sum2: 0.,
+            hist_pos: new_hist(),
+            hist_neg: new_hist(),
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.n = 0;
+        self.sum = 0.;
+        self.sum2 = 0.;
+        for hist in [&mut self.hist_pos, &mut self.hist_neg]
+            .into_iter()
+            .flatten()
+        {
+            hist.reset();
+        }
+    }
+
+    /// Updates `self` with the mean latencies of `f1` and `f2` for one iteration or batch.
+    ///
+    /// Iterations where either latency is zero are ignored, consistent with the `*_ln_*` statistics of
+    /// [`BenchOut`](crate::BenchOut).
+    #[inline(always)]
+    pub(crate) fn capture(&mut self, latency1: FpSeconds, latency2: FpSeconds) {
+        if latency1 <= FpSeconds::ZERO || latency2 <= FpSeconds::ZERO {
+            return;
+        }
+
+        // The batch size cancels out in the difference of logarithms.
+        let d = latency1.as_f64().ln() - latency2.as_f64().ln();
+        self.n += 1;
+        self.sum += d;
+        self.sum2 += d.powi(2);
+
+        let scaled = (d.abs() * LN_DIFF_SCALE).round() as u64;
+        let hist = if d > 0. {
+            &mut self.hist_pos
+        } else {
+            &mut self.hist_neg
+        };
+        if scaled > 0
+            && let Some(hist) = hist
+        {
+            hist.record(scaled)
+                .expect("can't happen: histogram is auto-resizable");
+        }
+    }
+
+    /// Adds the paired differences of `other` to `self`. The histograms are kept only if both have them.
+    pub(crate) fn merge(&mut self, other: &PairedDiff) {
+        self.n += other.n;
+        self.sum += other.sum;
+        self.sum2 += other.sum2;
+        for (hist, other_hist) in [
+            (&mut self.hist_pos, &other.hist_pos),
+            (&mut self.hist_neg, &other.hist_neg),
+        ] {
+            match (hist.as_mut(), other_hist) {
+                (Some(hist), Some(other_hist)) => hist
+                    .add(other_hist)
+                    .expect("can't happen: histogram is auto-resizable"),
+                _ => *hist = None,
+            }
+        }
+    }
+
+    pub(crate) fn moments(&self) -> SampleMoments {
+        SampleMoments::new(self.n, self.sum, self.sum2)
+    }
+
+    /// Computes the Wilcoxon signed-rank statistics from the sign-split histograms.
+    ///
+    /// Zero differences are dropped; tied absolute differences get their average rank.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the histograms are not available.
+    pub(crate) fn signed_rank(&self) -> SignedRank {
+        let (Some(hist_pos), Some(hist_neg)) = (&self.hist_pos, &self.hist_neg) else {
+            panic!(
+                "paired-difference histograms not available, see `BenchCfg::with_paired_diff_hist`"
+            );
+        };
+
+        let mut iter_pos = hist_pos
+            .iter_recorded()
+            .map(|x| (x.value_iterated_to(), x.count_at_value()))
+            .peekable();
+        let mut iter_neg = hist_neg
+            .iter_recorded()
+            .map(|x| (x.value_iterated_to(), x.count_at_value()))
+            .peekable();
+
+        let mut n = 0_u64;
+        let mut w_plus = 0.;
+        let mut ties_adj = 0.;
+
+        loop {
+            let value = match (iter_pos.peek(), iter_neg.peek()) {
+                (None, None) => break,
+                (Some(&(v, _)), None) | (None, Some(&(v, _))) => v,
+                (Some(&(vp, _)), Some(&(vn, _))) => vp.min(vn),
+            };
+            let count_pos = iter_pos.next_if(|&(v, _)| v == value).map_or(0, |(_, c)| c);
+            let count_neg = iter_neg.next_if(|&(v, _)| v == value).map_or(0, |(_, c)| c);
+
+            let t = (count_pos + count_neg) as f64;
+            let avg_rank = n as f64 + (t + 1.) / 2.;
+            w_plus += count_pos as f64 * avg_rank;
+            ties_adj += t.powi(3) - t;
+            n += count_pos + count_neg;
+        }
+
+        let nf = n as f64;
+        let mean = nf * (nf + 1.) / 4.;
+        let var = nf * (nf + 1.) * (2. * nf + 1.) / 24. - ties_adj / 48.;
+
+        SignedRank { w_plus, mean, var }
+    }
+}
+
+/// Wilcoxon signed-rank statistics.
+pub(crate) struct SignedRank {
+    /// Sum of the ranks of the positive differences.
+    pub(crate) w_plus: f64,
+    /// Mean of `w_plus` under the null hypothesis.
+    pub(crate) mean: f64,
+    /// Variance of `w_plus` under the null hypothesis, adjusted for ties.
+    pub(crate) var: f64,
+}
+
+impl SignedRank {
+    /// Normal approximation *z* value, or `None` if the variance is zero (no non-zero differences).
+    pub(crate) fn z(&self) -> Option<f64> {
+        if self.var > 0. {
+            Some((self.w_plus - self.mean) / self.var.sqrt())
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Latencies of each closure split by its position (first or last) in the execution order of each iteration.
+
+use crate::{
+    BenchCfg, FpSeconds, HistOverflow, LatencyUnit, Timing, new_timing,
+    summary_stats::new_timing_with_bounds,
+};
+
+/// Initial highest trackable value of the position histograms (auto-resizable) when their bounds are unknown, e.g.,
+/// after deserialization.
+const POSITION_HIST_HIGH: u64 = 20 * 1000 * 1000;
+
+/// Median latencies of a closure when it was executed first and when it was executed last in an iteration.
+///
+/// A ratio [`Self::ratio_last_first`] far from `1` indicates an order-dependent effect, e.g., a cache warmed by the
+/// preceding closure. See [`ExecOrder`](super::ExecOrder).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionMedians {
+    /// Number of observations with the closure executed first.
+    pub n_first: u64,
+    /// Median latency with the closure executed first, if `n_first > 0`.
+    pub median_first: Option<FpSeconds>,
+    /// Number of observations with the closure executed last.
+    pub n_last: u64,
+    /// Median latency with the closure executed last, if `n_last > 0`.
+    pub median_last: Option<FpSeconds>,
+}
+
+impl PositionMedians {
+    /// Ratio `median_last / median_first`, if both medians exist.
+    pub fn ratio_last_first(&self) -> Option<f64> {
+        Some(self.median_last?.as_f64() / self.median_first?.as_f64())
+    }
+}
+
+/// Histograms of a closure's latencies when it was executed first and when it was executed last.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct PositionHists {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::timing_v2"))]
+    first: Timing,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::timing_v2"))]
+    last: Timing,
+}
+
+impl PositionHists {
+    fn medians(&self, unit: LatencyUnit) -> PositionMedians {
+        let median = |hist: &Timing| {
+            (!hist.is_empty()).then(|| unit.fpsecs_from_value(hist.value_at_quantile(0.5)))
+        };
+        PositionMedians {
+            n_first: self.first.len(),
+            median_first: median(&self.first),
+            n_last: self.last.len(),
+            median_last: median(&self.last),
+        }
+    }
+}
+
+/// Accumulates the latencies of each closure by position in the execution order, for latency sources that report
+/// a non-fixed order. The histograms are only allocated once such a source is encountered.
+///
+/// The histograms have the same bounds as those of the closures (see [`BenchCfg::hist_bounds`]). They are
+/// auto-resizable, but with [`HistOverflow::Clamp`] or [`HistOverflow::Reject`], latencies above the highest
+/// trackable value are clamped, so they don't grow. As rejected observations are not captured, only clamping
+/// applies in practice.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct PositionStats {
+    sigfig: u8,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    range: Option<(u64, u64)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    clamp: bool,
+    hists: Vec<PositionHists>,
+}
+
+impl PositionStats {
+    pub(crate) fn new(cfg: &BenchCfg) -> Self {
+        Self {
+            sigfig: cfg.sigfig(),
+            range: Some(cfg.hist_range(None)),
+            clamp: cfg.hist_overflow() != HistOverflow::Resize,
+            hists: Vec::new(),
+        }
+    }
+
+    /// Sizes the histograms for a benchmark whose estimated number of iterations per second is `execs_per_sec`,
+    /// consistent with [`BenchOut::size_hist`](crate::BenchOut::size_hist). Discards any latencies captured so far.
+    pub(crate) fn size_hists(&mut self, cfg: &BenchCfg, execs_per_sec: f64) {
+        self.range = Some(cfg.hist_range(Some(execs_per_sec)));
+        self.hists.clear();
+    }
+
+    /// Creates an empty histogram within the bounds of `self`.
+    fn new_hist(&self) -> Timing {
+        match self.range {
+            Some((low, high)) => new_timing_with_bounds(low, high, self.sigfig, true),
+            None => new_timing(POSITION_HIST_HIGH, self.sigfig),
+        }
+    }
+
+    /// Allocates the histograms for `k` closures, unless already allocated.
+    fn alloc_hists(&mut self, k: usize) {
+        if self.hists.is_empty() {
+            self.hists = (0..k)
+                .map(|_| PositionHists {
+                    first: self.new_hist(),
+                    last: self.new_hist(),
+                })
+                .collect();
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        for h in &mut self.hists {
+            h.first.reset();
+            h.last.reset();
+        }
+    }
+
+    /// Adds the latencies by position of `other` to `self`, converting them from `other_unit` to `unit` if needed.
+    pub(crate) fn merge(
+        &mut self,
+        unit: LatencyUnit,
+        other: &PositionStats,
+        other_unit: LatencyUnit,
+    ) {
+        if other.hists.is_empty() {
+            return;
+        }
+        if self.hists.is_empty() {
+            self.sigfig = other.sigfig;
+            self.alloc_hists(other.hists.len());
+        }
+        assert_eq!(
+            self.hists.len(),
+            other.hists.len(),
+            "number of closures must be equal"
+        );
+
+        let add = |hist: &mut Timing, other_hist: &Timing| {
+            if unit == other_unit {
+                hist.add(other_hist)
+                    .expect("can't happen: histogram is auto-resizable");
+            } else {
+                for x in other_hist.iter_recorded() {
+                    let value =
+                        unit.value_from_fpsecs(other_unit.fpsecs_from_value(x.value_iterated_to()));
+                    hist.record_n(value, x.count_at_value())
+                        .expect("can't happen: histogram is auto-resizable");
+                }
+            }
+        };
+        for (h, other_h) in self.hists.iter_mut().zip(&other.hists) {
+            add(&mut h.first, &other_h.first);
+            add(&mut h.last, &other_h.last);
+        }
+    }
+
+    /// Updates `self` with the mean latencies of the closures for one iteration or batch, executed in `order`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `latencies` and `order` have different lengths.
+    #[inline(always)]
+    pub(crate) fn capture(
+        &mut self,
+        unit: LatencyUnit,
+        latencies: &[FpSeconds],
+        order: &[usize],
+        batch: usize,
+    ) {
+        assert_eq!(
+            latencies.len(),
+            order.len(),
+            "number of latencies must match length of order"
+        );
+        let (Some(&first), Some(&last)) = (order.first(), order.last()) else {
+            return;
+        };
+        self.alloc_hists(latencies.len());
+
+        let clamp = self.clamp;
+        let record = |hist: &mut Timing, latency: FpSeconds| {
+            let mut value = unit.value_from_fpsecs(latency);
+            if clamp {
+                value = value.min(hist.high());
+            }
+            hist.record_n(value, batch as u64)
+                .expect("can't happen: histogram is auto-resizable");
+        };
+        record(&mut self.hists[first].first, latencies[first]);
+        record(&mut self.hists[last].last, latencies[last]);
+    }
+
+    /// Medians by position of each closure, or `None` if no execution order was captured.
+    pub(crate) fn medians(&self, unit: LatencyUnit) -> Option<Vec<PositionMedians>> {
+        if self.hists.is_empty() {
+            return None;
+        }
+        Some(self.hists.iter().map(|h| h.medians(unit)).collect())
+    }
+}
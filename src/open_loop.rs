@@ -0,0 +1,387 @@
+//! Implements open-loop benchmarking: the target is invoked at a given arrival rate, rather than as soon as the
+//! previous invocation ends.
+//!
+//! Closed-loop benchmarks hide queueing delays: when an invocation is slow, the following ones are simply issued
+//! later, so the latencies they would have experienced under a steady load are never observed (coordinated
+//! omission). Open-loop benchmarks measure latency under load, e.g., for in-process services.
+
+use crate::{BenchCfg, BenchOut, CancelToken, FpSeconds, OpenLoopInfo, RunLength, rng::SplitMix64};
+use std::{
+    hint, thread,
+    time::{Duration, Instant},
+};
+
+/// Remaining wait below which the scheduler busy-waits rather than sleeps until the intended start time.
+const SPIN_THRESHOLD: Duration = Duration::from_micros(200);
+
+/// Distribution of the arrival times of an [`OpenLoop`] benchmark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arrivals {
+    /// Arrivals are evenly spaced.
+    Fixed,
+    /// Arrivals follow a Poisson process, i.e., inter-arrival times are exponentially distributed. They are drawn
+    /// from a pseudo-random number generator seeded with the argument, for reproducibility.
+    Poisson(u64),
+}
+
+/// Determines how latencies are measured in an [`OpenLoop`] benchmark, and thus whether coordinated omission is
+/// corrected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OmissionCorrection {
+    /// Latencies are measured from the intended start time of each invocation, so they include the time spent
+    /// waiting for previous invocations to complete.
+    IntendedStart,
+    /// Latencies are measured from the actual start time of each invocation, and each latency is recorded together
+    /// with the latencies of the invocations that would have been issued during its execution, as with
+    /// HdrHistogram's `record_correct` with the mean inter-arrival interval as the expected interval. The synthetic
+    /// latencies count towards all the statistics of the resulting [`BenchOut`], but are not added to its raw
+    /// samples.
+    RecordCorrect,
+    /// Latencies are measured from the actual start time of each invocation, without correction.
+    None,
+}
+
+/// Configuration of an open-loop benchmark: arrival rate, arrival distribution, and coordinated-omission correction.
+/// See [`bench_run_open_loop`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OpenLoop {
+    rate: f64,
+    arrivals: Arrivals,
+    correction: OmissionCorrection,
+}
+
+impl OpenLoop {
+    /// Instantiates `Self` with arrival rate `rate`, in invocations per second, [`Arrivals::Fixed`], and
+    /// [`OmissionCorrection::IntendedStart`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is not finite and positive.
+    pub fn new(rate: f64) -> Self {
+        assert!(
+            rate.is_finite() && rate > 0.,
+            "`rate` must be finite and positive"
+        );
+        Self {
+            rate,
+            arrivals: Arrivals::Fixed,
+            correction: OmissionCorrection::IntendedStart,
+        }
+    }
+
+    /// Arrival rate, in invocations per second.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Distribution of the arrival times.
+    pub fn arrivals(&self) -> Arrivals {
+        self.arrivals
+    }
+
+    /// Coordinated-omission correction.
+    pub fn correction(&self) -> OmissionCorrection {
+        self.correction
+    }
+
+    /// Sets the distribution of the arrival times.
+    pub fn with_arrivals(mut self, arrivals: Arrivals) -> Self {
+        self.arrivals = arrivals;
+        self
+    }
+
+    /// Sets the coordinated-omission correction.
+    pub fn with_correction(mut self, correction: OmissionCorrection) -> Self {
+        self.correction = correction;
+        self
+    }
+
+    /// Mean inter-arrival interval.
+    fn interval(&self) -> Duration {
+        Duration::from_secs_f64(1. / self.rate)
+    }
+}
+
+/// Intended start times of the invocations, as offsets from the start of the run.
+struct Schedule {
+    arrivals: Arrivals,
+    interval: f64,
+    rng: SplitMix64,
+    offset: f64,
+}
+
+impl Schedule {
+    fn new(load: &OpenLoop) -> Self {
+        let seed = match load.arrivals {
+            Arrivals::Poisson(seed) => seed,
+            Arrivals::Fixed => 0,
+        };
+        Self {
+            arrivals: load.arrivals,
+            interval: 1. / load.rate,
+            rng: SplitMix64::new(seed),
+            offset: 0.,
+        }
+    }
+
+    fn next_offset(&mut self) -> Duration {
+        let offset = self.offset;
+        self.offset += match self.arrivals {
+            Arrivals::Fixed => self.interval,
+            Arrivals::Poisson(_) => -(1. - self.rng.next_f64()).ln() * self.interval,
+        };
+        Duration::from_secs_f64(offset)
+    }
+}
+
+/// Waits until `instant`, sleeping while the remaining wait is long enough and busy-waiting for the rest.
+fn wait_until(instant: Instant) {
+    loop {
+        let now = Instant::now();
+        if now >= instant {
+            return;
+        }
+        let remaining = instant - now;
+        if remaining > SPIN_THRESHOLD {
+            thread::sleep(remaining - SPIN_THRESHOLD);
+        } else {
+            hint::spin_loop();
+        }
+    }
+}
+
+/// Invokes `f` according to `load` until `run_length` is reached or `cancel_token` is cancelled, and captures the
+/// latencies in `out`.
+fn run_phase(
+    out: &mut BenchOut,
+    load: &OpenLoop,
+    f: &mut impl FnMut(),
+    run_length: RunLength,
+    cancel_token: Option<&CancelToken>,
+) -> OpenLoopInfo {
+    let (exec_count, run_time) = run_length.exec_count_and_duration();
+    let interval = load.interval();
+    let mut schedule = Schedule::new(load);
+    let mut invocations = 0_u64;
+    let mut late_invocations = 0_u64;
+    let start = Instant::now();
+
+    while invocations < exec_count as u64 {
+        let offset = schedule.next_offset();
+        if offset >= run_time {
+            break;
+        }
+        let intended = start + offset;
+        wait_until(intended);
+        if cancel_token.is_some_and(|t| t.is_cancelled()) {
+            out.run_info.aborted = true;
+            break;
+        }
+
+        let actual = Instant::now();
+        f();
+        let end = Instant::now();
+
+        if actual - intended >= interval {
+            late_invocations += 1;
+        }
+        match load.correction {
+            OmissionCorrection::IntendedStart => {
+                out.capture_data(((end - intended).into(), 1));
+            }
+            OmissionCorrection::RecordCorrect => {
+                out.capture_corrected((end - actual).into(), interval.into())
+            }
+            OmissionCorrection::None => {
+                out.capture_data(((end - actual).into(), 1));
+            }
+        }
+        invocations += 1;
+    }
+
+    let elapsed = FpSeconds::from(start.elapsed()).as_f64();
+    OpenLoopInfo {
+        target_rate: load.rate,
+        achieved_rate: if elapsed > 0. {
+            invocations as f64 / elapsed
+        } else {
+            0.
+        },
+        invocations,
+        late_invocations,
+    }
+}
+
+/// Invokes closure `f` at the arrival rate of `load`, from a single scheduler thread, and collects the resulting
+/// latency data in a [`BenchOut`] object. The achieved rate and the number of late invocations are available
+/// from [`RunInfo::open_loop`](crate::RunInfo::open_loop).
+///
+/// Invocations are never skipped: when `f` takes longer than the inter-arrival interval, later invocations start
+/// late, and with [`OmissionCorrection::IntendedStart`] their latencies include the delay.
+///
+/// Prior to data collection, the benchmark is "warmed-up" by invoking `f` at the same arrival rate for
+/// [`BenchCfg::warmup_millis`] milliseconds, regardless of [`BenchCfg::warmup_mode`]. Latencies are measured with the
+/// wall clock, and the precision target of a [`RunLength::Precision`] is not assessed, only its caps apply, so a
+/// [`RunLength::Precision`] without caps runs until the [`BenchCfg::cancel_token`] is cancelled. The run stops early
+/// if the [`BenchCfg::cancel_token`] is cancelled.
+///
+/// Arguments:
+/// - `cfg` - bench configuration used to run the benchmark.
+/// - `load` - arrival rate, arrival distribution, and coordinated-omission correction.
+/// - `f` - benchmark target.
+/// - `run_length` - target run length (invocation count and/or duration) for data collection.
+pub fn bench_run_open_loop_arg_cfg(
+    cfg: &BenchCfg,
+    load: &OpenLoop,
+    mut f: impl FnMut(),
+    run_length: RunLength,
+) -> BenchOut {
+    let mut out = BenchOut::new(cfg);
+    // The arrival rate stands for the estimated iteration rate of closed-loop runs.
+    out.size_hist(cfg, load.rate);
+
+    let warmup_run_length = RunLength::Time(Duration::from_millis(cfg.warmup_millis()));
+    run_phase(
+        &mut out,
+        load,
+        &mut f,
+        warmup_run_length,
+        cfg.cancel_token(),
+    );
+    out.reset();
+
+    let info = run_phase(&mut out, load, &mut f, run_length, cfg.cancel_token());
+    out.run_info.open_loop = Some(info);
+    out
+}
+
+/// Invokes closure `f` at the arrival rate of `load` and collects the resulting latency data in a [`BenchOut`]
+/// object. Runs with the default [`BenchCfg`].
+/// This function calls [`bench_run_open_loop_arg_cfg`] with the default bench configuration.
+///
+/// Arguments:
+/// - `load` - arrival rate, arrival distribution, and coordinated-omission correction.
+/// - `f` - benchmark target.
+/// - `run_length` - target run length (invocation count and/or duration) for data collection.
+pub fn bench_run_open_loop(load: &OpenLoop, f: impl FnMut(), run_length: RunLength) -> BenchOut {
+    let cfg = BenchCfg::default();
+    bench_run_open_loop_arg_cfg(&cfg, load, f, run_length)
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- open_loop::test --nocapture
+mod test {
+    use super::*;
+    use crate::HistOverflow;
+
+    fn quick_cfg() -> BenchCfg {
+        BenchCfg::default().with_warmup_millis(0)
+    }
+
+    fn sleeper(micros: u64) -> impl FnMut() {
+        move || thread::sleep(Duration::from_micros(micros))
+    }
+
+    #[test]
+    fn test_schedule() {
+        let mut fixed = Schedule::new(&OpenLoop::new(1_000.));
+        let offsets = (0..3).map(|_| fixed.next_offset()).collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                Duration::ZERO,
+                Duration::from_millis(1),
+                Duration::from_millis(2)
+            ],
+            offsets
+        );
+
+        let load = OpenLoop::new(1_000.).with_arrivals(Arrivals::Poisson(5));
+        let mut poisson = Schedule::new(&load);
+        let last = (0..10_000).map(|_| poisson.next_offset()).last().unwrap();
+        let mean_interval = last.as_secs_f64() / 9_999.;
+        assert!((mean_interval / 1e-3 - 1.).abs() < 0.05, "{mean_interval}");
+    }
+
+    #[test]
+    fn test_capture_corrected() {
+        let mut out = BenchOut::new(&quick_cfg());
+        let interval = FpSeconds::from_millis(1);
+        out.capture_corrected(FpSeconds::from_micros(3_500), interval);
+        out.capture_corrected(FpSeconds::from_micros(500), interval);
+        let values = out.iter().map(|x| x.as_f64()).collect::<Vec<_>>();
+        let expected = [500e-6, 1_500e-6, 2_500e-6, 3_500e-6];
+        assert_eq!(expected.len(), values.len());
+        for (value, exp) in values.iter().zip(expected) {
+            assert!((value / exp - 1.).abs() < 1e-3, "{values:?}");
+        }
+        // Synthetic observations count towards all statistics.
+        assert_eq!(4, out.n_nz());
+        assert!((out.mean().as_f64() / 2e-3 - 1.).abs() < 1e-3);
+        let mean_ln = expected.iter().map(|x| x.ln()).sum::<f64>() / 4.;
+        assert!((out.mean_ln() - mean_ln).abs() < 1e-9);
+
+        let mut out = BenchOut::new(&quick_cfg());
+        out.capture_corrected(FpSeconds::from_millis(1_000), FpSeconds::from_micros(1));
+        assert_eq!(1 + crate::bench_out::MAX_CORRECTIONS, out.n());
+        assert_eq!(out.n(), out.n_nz());
+    }
+
+    #[test]
+    fn test_open_loop_under_capacity() {
+        let load = OpenLoop::new(1_000.);
+        let out =
+            bench_run_open_loop_arg_cfg(&quick_cfg(), &load, sleeper(100), RunLength::Count(200));
+
+        let info = out.run_info().open_loop().expect("open-loop info");
+        println!("{info:?}, median={:?}", out.median());
+        assert_eq!(200, out.n());
+        assert_eq!(200, info.invocations());
+        // The scheduler never runs ahead of the arrival rate, but sleeps may overshoot on a loaded machine, so the
+        // lower bound and the count of late invocations leave a wide margin.
+        assert!(info.achieved_rate() > 500. && info.achieved_rate() <= 1_010.);
+        assert!(info.late_invocations() < 100);
+    }
+
+    #[test]
+    fn test_open_loop_hist_sized_from_rate() {
+        // Without bounds, the histogram is sized from the arrival rate rather than the fallback.
+        let cfg = quick_cfg().with_hist_overflow(HistOverflow::Reject);
+        let load = OpenLoop::new(20.);
+        let out = bench_run_open_loop_arg_cfg(&cfg, &load, sleeper(25_000), RunLength::Count(3));
+        assert_eq!(3, out.n());
+        assert_eq!(0, out.run_info().out_of_range());
+    }
+
+    #[test]
+    fn test_open_loop_over_capacity() {
+        // Each invocation takes at least 2 intervals, so delays accumulate. The bounds below follow from that lower
+        // bound alone and hold however much the sleeps overshoot.
+        let run = |correction| {
+            let load = OpenLoop::new(1_000.).with_correction(correction);
+            bench_run_open_loop_arg_cfg(&quick_cfg(), &load, sleeper(2_000), RunLength::Count(50))
+        };
+
+        let intended = run(OmissionCorrection::IntendedStart);
+        let info = intended.run_info().open_loop().unwrap();
+        println!("{info:?}");
+        // Every invocation but the first starts at least one interval late.
+        assert!(info.late_invocations() >= 49);
+        assert!(info.achieved_rate() <= 500.);
+
+        let uncorrected = run(OmissionCorrection::None);
+        let corrected = run(OmissionCorrection::RecordCorrect);
+        println!(
+            "medians: intended={:?}, uncorrected={:?}, corrected={:?}",
+            intended.median(),
+            uncorrected.median(),
+            corrected.median()
+        );
+        assert_eq!(50, uncorrected.n());
+        assert!(corrected.n() > 50);
+        // With `d` the duration of an invocation, the `k`-th latency from the intended start is about
+        // `k * (d - interval) + d`, which is at least 5 times `d` at the median, for `d >= 2 * interval`.
+        assert!(intended.median() > uncorrected.median() * 5.);
+        assert!(uncorrected.median() >= FpSeconds::from_millis(2));
+    }
+}
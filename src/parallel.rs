@@ -0,0 +1,322 @@
+//! Benchmark closures on multiple threads concurrently, e.g., to study lock or allocator contention.
+//!
+//! Each thread runs its own benchmark of a latency source, as with
+//! [`multi::bench_run_arg_cfg`](crate::multi::bench_run_arg_cfg). The threads synchronize so that their warm-up
+//! phases start together and their execution phases start together.
+//! The resulting [`ParallelOut`] holds one [`BenchOut`] per thread, as well as their merged aggregate and the wall
+//! time of the execution phase.
+//! [`thread_sweep_arg_cfg`] runs a closure for a range of thread counts and produces a scaling curve.
+
+use crate::{
+    BenchCfg, BenchOut, FpSeconds, RunLength,
+    multi::{self, LatencySrc, LatencySrc1},
+    status::NoStatus,
+};
+use std::{
+    sync::Barrier,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Contains the data resulting from benchmarking latency sources on multiple threads concurrently.
+///
+/// With feature **"serde"**, this struct can be serialized and deserialized (see [`BenchOut`]).
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParallelOut {
+    threads: Vec<BenchOut>,
+    merged: BenchOut,
+    wall_time: Duration,
+}
+
+impl ParallelOut {
+    /// Instantiates `Self` from the outputs of the individual threads and the wall time of the execution phase.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` is empty.
+    fn new(cfg: &BenchCfg, threads: Vec<BenchOut>, wall_time: Duration) -> Self {
+        assert!(!threads.is_empty(), "`threads` must not be empty");
+        let mut merged = BenchOut::new(cfg);
+        merged.samples = None;
+        merged.run_info = threads[0].run_info.clone();
+        merged.absorb(&threads[0]);
+        for out in &threads[1..] {
+            merged.absorb(out);
+            merged.run_info.merge(&out.run_info);
+        }
+        Self {
+            threads,
+            merged,
+            wall_time,
+        }
+    }
+
+    /// Number of threads.
+    pub fn thread_count(&self) -> usize {
+        self.threads.len()
+    }
+
+    /// Outputs of the individual threads, in the order of the latency sources.
+    pub fn threads(&self) -> &[BenchOut] {
+        &self.threads
+    }
+
+    /// Latency observations of all threads combined.
+    ///
+    /// Raw samples are not retained, as the threads' iterations have no common order. The run information is
+    /// that of the threads merged as by [`BenchOut::merge`], e.g., counts of out-of-range observations are added.
+    pub fn merged(&self) -> &BenchOut {
+        &self.merged
+    }
+
+    /// Wall time of the execution phase, from its common start until the last thread finished.
+    pub fn wall_time(&self) -> Duration {
+        self.wall_time
+    }
+
+    /// Aggregate throughput, in executions per second: the number of executions recorded by all threads divided by
+    /// [`Self::wall_time`].
+    ///
+    /// As it is based on wall time, the throughput includes the benchmarking overhead between executions and any
+    /// time during which threads were idle, e.g., after the faster threads finished.
+    pub fn throughput(&self) -> f64 {
+        self.merged.n() as f64 / self.wall_time.as_secs_f64()
+    }
+
+    /// Point of a scaling curve for the thread count of `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no observations.
+    pub fn scaling_point(&self) -> ScalingPoint {
+        let summary = self.merged.summary();
+        ScalingPoint {
+            threads: self.thread_count(),
+            median: summary.median,
+            p99: summary.p99,
+            throughput: self.throughput(),
+        }
+    }
+}
+
+/// Latency and throughput statistics for a given number of threads. See [`thread_sweep_arg_cfg`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScalingPoint {
+    /// Number of threads.
+    pub threads: usize,
+    /// Median latency over all threads.
+    pub median: FpSeconds,
+    /// 99th percentile latency over all threads.
+    pub p99: FpSeconds,
+    /// Aggregate throughput, in executions per second (see [`ParallelOut::throughput`]).
+    pub throughput: f64,
+}
+
+/// Benchmarks each of `srcs` on its own thread, all threads running concurrently.
+///
+/// The warm-up and execution phases start together on all threads (see [`BenchCfg::warmup_millis`]).
+/// If a latency source panics, its thread stops collecting data while the other threads complete their runs, and the
+/// panic is then propagated.
+///
+/// Arguments:
+/// - `cfg` - bench configuration used to run the benchmark.
+/// - `srcs` - latency sources, one per thread.
+/// - `run_length` - target run length (iteration count and/or duration) for data collection. Applies to
+///   each thread.
+///
+/// # Panics
+///
+/// Panics if `srcs` is empty or if a thread panics.
+pub fn bench_run_threads_src_arg_cfg<Src: LatencySrc<1> + Send>(
+    cfg: &BenchCfg,
+    srcs: impl IntoIterator<Item = Src>,
+    run_length: RunLength,
+) -> ParallelOut {
+    let srcs = srcs.into_iter().collect::<Vec<_>>();
+    assert!(!srcs.is_empty(), "`srcs` must not be empty");
+    // The calling thread also waits on the barrier, to time the execution phase.
+    let barrier = Barrier::new(srcs.len() + 1);
+
+    let (threads, wall_time) = thread::scope(|s| {
+        let handles = srcs
+            .into_iter()
+            .map(|src| {
+                let barrier = &barrier;
+                s.spawn(move || multi::bench_run_synced(cfg, src, run_length, NoStatus, barrier))
+            })
+            .collect::<Vec<_>>();
+        // Warm-up, then execution.
+        barrier.wait();
+        barrier.wait();
+        let start = Instant::now();
+        let threads = handles
+            .into_iter()
+            .map(|h| h.join().expect("benchmark thread panicked").flatten())
+            .collect::<Vec<_>>();
+        (threads, start.elapsed())
+    });
+
+    ParallelOut::new(cfg, threads, wall_time)
+}
+
+/// Benchmarks `threads` copies of `f` concurrently, each on its own thread.
+/// This function calls [`bench_run_threads_src_arg_cfg`] with a [`LatencySrc1`] for each copy of `f`.
+///
+/// Arguments:
+/// - `cfg` - bench configuration used to run the benchmark.
+/// - `f` - benchmark target, cloned for each thread.
+/// - `threads` - number of threads.
+/// - `run_length` - target run length (iteration count and/or duration) for data collection. Applies to
+///   each thread.
+///
+/// # Panics
+///
+/// Panics if `threads` is zero or if a thread panics.
+pub fn bench_run_threads_arg_cfg(
+    cfg: &BenchCfg,
+    f: impl FnMut() + Clone + Send,
+    threads: usize,
+    run_length: RunLength,
+) -> ParallelOut {
+    assert!(threads > 0, "`threads` must be positive");
+    let srcs = (0..threads).map(|_| LatencySrc1::new(f.clone()));
+    bench_run_threads_src_arg_cfg(cfg, srcs, run_length)
+}
+
+/// Benchmarks `threads` copies of `f` concurrently with the default [`BenchCfg`].
+/// This function calls [`bench_run_threads_arg_cfg`] with the default bench configuration.
+///
+/// Arguments:
+/// - `f` - benchmark target, cloned for each thread.
+/// - `threads` - number of threads.
+/// - `run_length` - target run length (iteration count and/or duration) for data collection. Applies to
+///   each thread.
+///
+/// # Panics
+///
+/// Panics if `threads` is zero or if a thread panics.
+pub fn bench_run_threads(
+    f: impl FnMut() + Clone + Send,
+    threads: usize,
+    run_length: RunLength,
+) -> ParallelOut {
+    let cfg = BenchCfg::default();
+    bench_run_threads_arg_cfg(&cfg, f, threads, run_length)
+}
+
+/// Runs [`bench_run_threads_arg_cfg`] for each of the `thread_counts` and returns the resulting scaling curve.
+///
+/// Arguments:
+/// - `cfg` - bench configuration used to run the benchmarks.
+/// - `f` - benchmark target, cloned for each thread.
+/// - `thread_counts` - numbers of threads, e.g., `[1, 2, 4, 8]`.
+/// - `run_length` - target run length (iteration count and/or duration) for data collection. Applies to
+///   each thread of each benchmark.
+///
+/// # Panics
+///
+/// Panics if any of the `thread_counts` is zero, if a benchmark has no observations, or if a thread panics.
+pub fn thread_sweep_arg_cfg(
+    cfg: &BenchCfg,
+    f: impl FnMut() + Clone + Send,
+    thread_counts: impl IntoIterator<Item = usize>,
+    run_length: RunLength,
+) -> Vec<ScalingPoint> {
+    thread_counts
+        .into_iter()
+        .map(|threads| {
+            bench_run_threads_arg_cfg(cfg, f.clone(), threads, run_length).scaling_point()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- parallel::test --nocapture
+mod test {
+    use super::*;
+    use crate::LatencyUnit;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    fn quick_cfg() -> BenchCfg {
+        BenchCfg::default()
+            .with_warmup_millis(0)
+            .with_status_millis(1)
+            .with_recording_unit(LatencyUnit::NANO)
+    }
+
+    #[test]
+    fn test_bench_run_threads() {
+        let f = || thread::sleep(Duration::from_micros(200));
+        let out = bench_run_threads_arg_cfg(&quick_cfg(), f, 3, RunLength::Count(30));
+
+        assert_eq!(3, out.thread_count());
+        for b in out.threads() {
+            assert_eq!(30, b.n());
+        }
+        assert_eq!(90, out.merged().n());
+        assert!(out.merged().samples().is_none());
+        let sum_means = out.threads().iter().map(|b| b.mean().as_f64()).sum::<f64>();
+        assert!((out.merged().mean().as_f64() - sum_means / 3.).abs() < 1e-9);
+        let info = out.merged().run_info();
+        let threads_info = out.threads().iter().map(|b| b.run_info());
+        assert_eq!(
+            threads_info.clone().map(|i| i.out_of_range()).sum::<u64>(),
+            info.out_of_range()
+        );
+        assert_eq!(
+            threads_info.map(|i| i.overhead_floored()).sum::<u64>(),
+            info.overhead_floored()
+        );
+        // Each thread sleeps for at least 30 * 200 microseconds after the common start, but the calling thread may
+        // start timing late, so the bounds leave a wide margin.
+        assert!(out.wall_time() >= Duration::from_micros(200 * 30 / 2));
+        // At most 3 executions every 200 microseconds.
+        assert!(out.throughput() > 0.);
+        assert!(out.throughput() <= 2. * 3. / 200e-6);
+    }
+
+    #[test]
+    fn test_bench_run_threads_src_panic() {
+        // The panic of one thread's source must not block the other threads on the barrier.
+        let srcs = (0..3).map(|i| {
+            LatencySrc1::new(move || {
+                assert!(i != 1, "source panic");
+                thread::sleep(Duration::from_micros(10));
+            })
+        });
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            bench_run_threads_src_arg_cfg(&quick_cfg(), srcs, RunLength::Count(30))
+        }));
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_thread_sweep_contention() {
+        // A lock held for the whole execution serializes the threads.
+        let lock = Arc::new(Mutex::new(()));
+        let f = move || {
+            let _guard = lock.lock().unwrap();
+            thread::sleep(Duration::from_micros(300));
+        };
+
+        let points = thread_sweep_arg_cfg(&quick_cfg(), f, [1, 4], RunLength::Count(20));
+        println!("{points:?}");
+
+        assert_eq!(
+            vec![1, 4],
+            points.iter().map(|p| p.threads).collect::<Vec<_>>()
+        );
+        // The lock is not fair, so a thread may reacquire it repeatedly and the median may not reflect the wait.
+        // The slowest executions, however, wait for other threads to release the lock at least once.
+        assert!(points[1].p99 > points[0].median * 1.5);
+        assert!(points[1].p99 >= points[1].median);
+        // Serialized executions don't scale: 4 independent threads would reach about 4 times the throughput.
+        assert!(points[1].throughput < points[0].throughput * 2.5);
+    }
+}
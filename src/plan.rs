@@ -0,0 +1,307 @@
+//! Sample-size and power planning from pilot benchmark runs.
+//!
+//! The required sample sizes are computed for two-sided Student or Welch tests on the natural logarithms of
+//! latencies, using the log-scale standard deviations of the pilot runs as estimates of the population ones. Since
+//! those estimates are themselves noisy, it is prudent to add a margin to the planned run length, or to use a
+//! [`RunLength::Precision`] run instead.
+
+use crate::{
+    BenchOut, Comp, RunLength,
+    duo::DuoOut,
+    multi,
+    stats_support::{t_quantile, z_quantile},
+};
+use std::time::Duration;
+
+/// Number of refinements of the normal-approximation sample size with Student t quantiles.
+const T_REFINEMENTS: usize = 10;
+
+/// Run length planned from pilot runs by the `plan_*` methods of [`BenchOut`], [`Comp`], [`DuoOut`], and
+/// [`multi::BenchOut`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunPlan {
+    count: usize,
+    est_time: Option<Duration>,
+}
+
+impl RunPlan {
+    /// Required number of iterations for each run.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// [`RunLength::Count`] with the required number of iterations.
+    pub fn run_length(&self) -> RunLength {
+        RunLength::Count(self.count)
+    }
+
+    /// Estimated time to execute the planned iterations, excluding warm-up, based on
+    /// [`RunInfo::execs_per_sec`](crate::RunInfo::execs_per_sec) of the pilot runs; `None` if that estimate is not
+    /// available for any of the pilot runs.
+    pub fn est_time(&self) -> Option<Duration> {
+        self.est_time
+    }
+}
+
+/// Smallest sample size `n` such that a two-sided test with significance level `alpha` detects a log-scale
+/// difference `ln_delta` with probability `power`, where the variance of the estimated difference is `var / n` and
+/// `df(n)` is the number of degrees of freedom of the test statistic.
+fn required_count(
+    ln_delta: f64,
+    var: f64,
+    alpha: f64,
+    power: f64,
+    df: impl Fn(f64) -> f64,
+) -> usize {
+    assert!(
+        ln_delta.is_finite() && ln_delta != 0.,
+        "`ratio` must be positive, finite, and different from 1"
+    );
+    assert!(
+        0. < alpha && alpha < 1.,
+        "`alpha` not in open interval `(0, 1)`"
+    );
+    assert!(
+        0. < power && power < 1.,
+        "`power` not in open interval `(0, 1)`"
+    );
+    assert!(var > 0., "log-scale variance of pilot runs is zero");
+
+    let count_for =
+        |q_alpha: f64, q_power: f64| (q_alpha + q_power).powi(2) * var / ln_delta.powi(2);
+    // Start with a large-sample (normal) approximation and refine it with Student t quantiles.
+    let mut n = count_for(z_quantile(1. - alpha / 2.), z_quantile(power)).max(2.);
+    for _ in 0..T_REFINEMENTS {
+        let df = df(n.ceil()).max(1.);
+        n = count_for(t_quantile(1. - alpha / 2., df), t_quantile(power, df)).max(2.);
+    }
+    n.ceil() as usize
+}
+
+/// Estimated time for `count` iterations of each of the runs with the given `execs_per_sec` estimates.
+fn est_time(
+    count: usize,
+    execs_per_sec: impl IntoIterator<Item = Option<f64>>,
+) -> Option<Duration> {
+    execs_per_sec
+        .into_iter()
+        .map(|eps| eps.map(|eps| count as f64 / eps))
+        .sum::<Option<f64>>()
+        .map(Duration::from_secs_f64)
+}
+
+impl BenchOut {
+    /// Plans a run long enough for Student's one-sample test on `ln(latency)` (see [`BenchOut::student_ln_test`])
+    /// to detect, with significance level `alpha` and probability `power`, a median latency that differs from a
+    /// hypothesized one by the factor `ratio` (e.g., `1.02` for 2%), using `self` as the pilot run.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - Number of non-zero observations of `self` is zero or `self.stdev_ln() == 0`.
+    /// - `ratio` is not positive and finite or `ratio == 1`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    /// - `power` not in open interval `(0, 1)`.
+    pub fn plan_ratio(&self, ratio: f64, alpha: f64, power: f64) -> RunPlan {
+        let var = self.stdev_ln().powi(2);
+        let count = required_count(ratio.ln(), var, alpha, power, |n| n - 1.);
+        RunPlan {
+            count,
+            est_time: est_time(count, [self.run_info.execs_per_sec]),
+        }
+    }
+}
+
+impl Comp<'_> {
+    /// Plans runs of `f1` and `f2`, each with the same number of iterations, long enough for Welch's test on
+    /// `ln(latency)` (see [`Comp::welch_ln_test`]) to detect, with significance level `alpha` and probability
+    /// `power`, a ratio of median latencies equal to `ratio` (e.g., `1.02` for 2%), using `self.out_f1()` and
+    /// `self.out_f2()` as the pilot runs.
+    ///
+    /// The estimated time is for both runs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - Number of non-zero observations of either pilot run is zero.
+    /// - `self.out_f1().stdev_ln() == 0` and `self.out_f2().stdev_ln() == 0`.
+    /// - `ratio` is not positive and finite or `ratio == 1`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    /// - `power` not in open interval `(0, 1)`.
+    pub fn plan_ratio(&self, ratio: f64, alpha: f64, power: f64) -> RunPlan {
+        let (var1, var2) = (self.0.stdev_ln().powi(2), self.1.stdev_ln().powi(2));
+        // Welch-Satterthwaite degrees of freedom for equal sample sizes.
+        let df = |n: f64| (n - 1.) * (var1 + var2).powi(2) / (var1.powi(2) + var2.powi(2));
+        let count = required_count(ratio.ln(), var1 + var2, alpha, power, df);
+        RunPlan {
+            count,
+            est_time: est_time(
+                count,
+                [self.0.run_info.execs_per_sec, self.1.run_info.execs_per_sec],
+            ),
+        }
+    }
+}
+
+impl DuoOut {
+    /// Plans a run of `f1` and `f2` long enough for the paired test on `ln(latency)` (see
+    /// [`DuoOut::paired_ln_test`]) to detect, with significance level `alpha` and probability `power`, a ratio of
+    /// median latencies equal to `ratio`, using the paired differences of `self` as the pilot.
+    ///
+    /// When the noise is shared by both closures, this requires far fewer iterations than
+    /// [`multi::BenchOut::plan_ratio`], which plans for Welch's test.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.paired_n() == 0` or `self.paired_stdev_diff_ln() == 0`.
+    /// - `ratio` is not positive and finite or `ratio == 1`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    /// - `power` not in open interval `(0, 1)`.
+    pub fn plan_paired_ratio(&self, ratio: f64, alpha: f64, power: f64) -> RunPlan {
+        let var = self.paired_stdev_diff_ln().powi(2);
+        let count = required_count(ratio.ln(), var, alpha, power, |n| n - 1.);
+        RunPlan {
+            count,
+            est_time: est_time(count, [self.run_info().execs_per_sec]),
+        }
+    }
+}
+
+impl<const K: usize> multi::BenchOut<K> {
+    /// Plans a run of the `K` closures long enough for Welch's test on `ln(latency)` to detect the given `ratio` of
+    /// median latencies between every pair of closures, using `self` as the pilot run. The required count is the
+    /// maximum of [`Comp::plan_ratio`] over all pairs. For [`DuoOut`], this is the plan for [`DuoOut::welch_ln_test`].
+    ///
+    /// The estimated time is for a single run executing all closures in each iteration.
+    ///
+    /// No adjustment is made for multiple comparisons; to control the family-wise error rate, pass `alpha` divided
+    /// by the number of pairs `K * (K - 1) / 2` (Bonferroni).
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `K < 2`.
+    /// - Number of non-zero observations of any closure is zero.
+    /// - Both `stdev_ln()` values of some pair of closures are zero.
+    /// - `ratio` is not positive and finite or `ratio == 1`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    /// - `power` not in open interval `(0, 1)`.
+    pub fn plan_ratio(&self, ratio: f64, alpha: f64, power: f64) -> RunPlan {
+        assert!(K >= 2, "`K` must be at least 2");
+        let count = (0..K)
+            .flat_map(|i| (i + 1..K).map(move |j| (i, j)))
+            .map(|(i, j)| {
+                Comp(&self.arr[i], &self.arr[j])
+                    .plan_ratio(ratio, alpha, power)
+                    .count
+            })
+            .max()
+            .expect("can't happen: `K >= 2`");
+        RunPlan {
+            count,
+            est_time: est_time(count, [self.run_info().execs_per_sec]),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- plan::test --nocapture
+mod test {
+    use super::*;
+    use crate::{
+        BenchCfg, FpSeconds,
+        test_support::{HI_STDEV_LN, lognormal_samp},
+    };
+    use basic_stats::core::{AcceptedHyp, AltHyp};
+
+    const ALPHA: f64 = 0.05;
+    const POWER: f64 = 0.8;
+
+    #[test]
+    fn test_required_count() {
+        // Textbook value: a one-standard-deviation difference needs 8 observations for a one-sample t-test with
+        // alpha 0.05 and power 0.8 (normal approximation: 7.85).
+        let n = required_count(1., 1., ALPHA, POWER, |n| n - 1.);
+        assert!((8..=11).contains(&n), "{n}");
+
+        // Large samples are close to the normal approximation `(1.959964 + 0.841621)^2 / delta^2`.
+        let n = required_count(0.02, 1., ALPHA, POWER, |n| n - 1.);
+        let normal_n = (1.959964_f64 + 0.841621).powi(2) / 0.02_f64.powi(2);
+        assert!((n as f64 - normal_n).abs() / normal_n < 0.01, "{n}");
+
+        // Smaller differences and higher power require more observations.
+        assert!(required_count(0.01, 1., ALPHA, POWER, |n| n - 1.) > n);
+        assert!(required_count(0.02, 1., ALPHA, 0.9, |n| n - 1.) > n);
+    }
+
+    #[test]
+    fn test_plan_ratio() {
+        let cfg = BenchCfg::default();
+        let mu = -9.;
+        let sigma = *HI_STDEV_LN;
+        let pilot1 = BenchOut::from_iter(&cfg, lognormal_samp(mu + 1.02_f64.ln(), sigma, 500));
+        let pilot2 = BenchOut::from_iter(&cfg, lognormal_samp(mu, sigma, 500));
+        let comp = Comp::new(&pilot1, &pilot2);
+
+        let plan = comp.plan_ratio(1.02, ALPHA, POWER);
+        println!("{plan:?}");
+        // Pilot outputs not produced by a benchmark run have no `execs_per_sec` estimate.
+        assert_eq!(None, plan.est_time());
+        assert_eq!(RunLength::Count(plan.count()), plan.run_length());
+
+        // A run of the planned length detects the difference.
+        let count = plan.count();
+        let out1 = BenchOut::from_iter(&cfg, lognormal_samp(mu + 1.02_f64.ln(), sigma, count));
+        let out2 = BenchOut::from_iter(&cfg, lognormal_samp(mu, sigma, count));
+        assert_eq!(
+            AcceptedHyp::Alt,
+            Comp::new(&out1, &out2)
+                .welch_ln_test(0., AltHyp::Ne, ALPHA)
+                .accepted()
+        );
+
+        // Two-sample comparisons need about twice as many iterations as one-sample ones with the same variance.
+        let one_samp = pilot2.plan_ratio(1.02, ALPHA, POWER).count() as f64;
+        let two_samp = count as f64;
+        assert!(
+            (1.9..2.1).contains(&(two_samp / one_samp)),
+            "{one_samp}, {two_samp}"
+        );
+    }
+
+    #[test]
+    fn test_plan_multi() {
+        let cfg = BenchCfg::default();
+        let sigma = *HI_STDEV_LN;
+        let wide = lognormal_samp(-9., sigma * 2., 500).collect::<Vec<_>>();
+        let samp = lognormal_samp(-9., sigma, 500)
+            .zip(wide.into_iter().rev())
+            .map(|(x, y)| [x, y, x * 1.5]);
+        let mut out = multi::BenchOut::<3>::from_iter(&cfg, samp);
+        for b in &mut out.arr {
+            b.run_info.execs_per_sec = Some(1000.);
+        }
+
+        let plan = out.plan_ratio(1.02, ALPHA, POWER);
+        let comp_counts = [(0, 1), (0, 2), (1, 2)].map(|(i, j)| {
+            Comp(&out.arr[i], &out.arr[j])
+                .plan_ratio(1.02, ALPHA, POWER)
+                .count()
+        });
+        assert_eq!(*comp_counts.iter().max().unwrap(), plan.count());
+        let est_time = plan.est_time().unwrap().as_secs_f64();
+        assert!((est_time - plan.count() as f64 / 1000.).abs() < 1e-6);
+
+        let duo = DuoOut::from_iter(
+            &cfg,
+            lognormal_samp(-9., sigma, 500).map(|x| [x * 1.02, x + FpSeconds::from_micros(1)]),
+        );
+        // With shared noise, the paired plan needs far fewer iterations.
+        assert!(
+            duo.plan_paired_ratio(1.02, ALPHA, POWER).count() * 10
+                < duo.plan_ratio(1.02, ALPHA, POWER).count()
+        );
+    }
+}
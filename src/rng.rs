@@ -0,0 +1,63 @@
+//! Small seeded pseudo-random number generator for reproducible sampling.
+
+/// SplitMix64 pseudo-random number generator.
+///
+/// Not cryptographically secure. Used where reproducibility from a seed matters more than statistical
+/// sophistication, e.g., reservoir sampling.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `[0, 1)` with 53 bits of precision.
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /// Returns a value in `0..n`, with negligible bias for `n` much smaller than `2^64`.
+    pub(crate) fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- rng::test --nocapture
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_mix_64() {
+        // Reference values for seed 0.
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(0xE220_A839_7B1D_CDAF, rng.next_u64());
+        assert_eq!(0x6E78_9E6A_A1B9_65F4, rng.next_u64());
+
+        let mut rng = SplitMix64::new(42);
+        let mut counts = [0_u32; 4];
+        for _ in 0..4_000 {
+            counts[rng.below(4) as usize] += 1;
+        }
+        assert!(
+            counts.iter().all(|&c| (900..1_100).contains(&c)),
+            "{counts:?}"
+        );
+
+        let mean = (0..4_000).map(|_| rng.next_f64()).sum::<f64>() / 4_000.;
+        assert!((0.48..0.52).contains(&mean), "{mean}");
+    }
+}
@@ -0,0 +1,433 @@
+//! Outlier classification and robust statistics for the natural logarithms of latencies.
+//!
+//! All computations use the latencies recorded in the [`BenchOut`] histogram, so they are subject to the histogram's
+//! resolution (see [`BenchCfg::sigfig`](crate::BenchCfg::sigfig)). Zero latencies are excluded, consistent with the
+//! `*_ln_*` statistics of [`BenchOut`].
+
+use crate::{
+    BenchOut, FpSeconds,
+    stats_support::{t_quantile, t_to_p},
+};
+use basic_stats::core::{AltHyp, Ci};
+
+/// Multiple of the interquartile range beyond the quartiles for mild outliers with [`BenchOut::tukey_outliers`].
+pub const TUKEY_MILD: f64 = 1.5;
+/// Multiple of the interquartile range beyond the quartiles for severe outliers with [`BenchOut::tukey_outliers`].
+pub const TUKEY_SEVERE: f64 = 3.;
+/// Multiple of the scaled median absolute deviation beyond the median for mild outliers with
+/// [`BenchOut::mad_outliers`].
+pub const MAD_MILD: f64 = 3.;
+/// Multiple of the scaled median absolute deviation beyond the median for severe outliers with
+/// [`BenchOut::mad_outliers`].
+pub const MAD_SEVERE: f64 = 5.;
+
+/// Scale factor that makes the median absolute deviation a consistent estimator of the standard deviation for
+/// normal data.
+const MAD_SCALE: f64 = 1.482_602_218_505_602;
+
+/// Classification of the latencies of a [`BenchOut`] as low or high, mild or severe outliers, based on fences
+/// on the natural logarithms of the latencies.
+///
+/// A latency below the low severe fence is a low severe outlier, and a latency below the low mild fence (but not
+/// below the low severe fence) is a low mild outlier; similarly for high outliers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outliers {
+    fences: [FpSeconds; 4],
+    counts: [u64; 4],
+}
+
+impl Outliers {
+    /// Low severe, low mild, high mild, and high severe fences, in that order.
+    pub fn fences(&self) -> [FpSeconds; 4] {
+        self.fences
+    }
+
+    /// Number of low severe outliers.
+    pub fn low_severe(&self) -> u64 {
+        self.counts[0]
+    }
+
+    /// Number of low mild outliers.
+    pub fn low_mild(&self) -> u64 {
+        self.counts[1]
+    }
+
+    /// Number of high mild outliers.
+    pub fn high_mild(&self) -> u64 {
+        self.counts[2]
+    }
+
+    /// Number of high severe outliers.
+    pub fn high_severe(&self) -> u64 {
+        self.counts[3]
+    }
+
+    /// Number of low outliers, mild or severe.
+    pub fn low(&self) -> u64 {
+        self.low_severe() + self.low_mild()
+    }
+
+    /// Number of high outliers, mild or severe.
+    pub fn high(&self) -> u64 {
+        self.high_mild() + self.high_severe()
+    }
+
+    /// Total number of outliers.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+/// Method used to reduce the influence of extreme latencies on the statistics computed by [`BenchOut::robust_ln`].
+///
+/// The argument of each variant is the proportion `g` of observations affected in each tail, which must be in the
+/// interval `[0, 0.5)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RobustMethod {
+    /// The lowest and highest `floor(g * n)` observations are discarded when computing the mean.
+    Trim(f64),
+    /// The lowest and highest `floor(g * n)` observations are replaced by the nearest remaining observation when
+    /// computing the mean.
+    Winsorize(f64),
+}
+
+/// Robust counterparts of the `*_ln_*` statistics of [`BenchOut`], computed by [`BenchOut::robust_ln`].
+///
+/// The standard deviation is always the winsorized standard deviation, and the standard error of the mean is
+/// `stdev_ln / ((1 - 2g) * sqrt(n))`, with `h - 1` degrees of freedom, where `h` is the number of observations that
+/// are neither trimmed nor winsorized (Tukey-McLaughlin).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RobustLn {
+    method: RobustMethod,
+    n: u64,
+    h: u64,
+    mean_ln: f64,
+    stdev_ln: f64,
+}
+
+impl RobustLn {
+    /// Method used to compute `self`.
+    pub fn method(&self) -> RobustMethod {
+        self.method
+    }
+
+    /// Number of non-zero observations.
+    pub fn n(&self) -> u64 {
+        self.n
+    }
+
+    /// Number of non-zero observations that are neither trimmed nor winsorized.
+    pub fn h(&self) -> u64 {
+        self.h
+    }
+
+    /// Trimmed or winsorized mean of the natural logarithms of the latencies.
+    pub fn mean_ln(&self) -> f64 {
+        self.mean_ln
+    }
+
+    /// Winsorized standard deviation of the natural logarithms of the latencies.
+    pub fn stdev_ln(&self) -> f64 {
+        self.stdev_ln
+    }
+
+    fn std_err(&self) -> f64 {
+        let g = match self.method {
+            RobustMethod::Trim(g) | RobustMethod::Winsorize(g) => g,
+        };
+        self.stdev_ln / ((1. - 2. * g) * (self.n as f64).sqrt())
+    }
+
+    /// Robust counterpart of [`BenchOut::student_ln_t`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self.stdev_ln() == 0`.
+    pub fn student_ln_t(&self, ln_mu0: f64) -> f64 {
+        assert!(self.stdev_ln > 0., "`self.stdev_ln() == 0`");
+        (self.mean_ln - ln_mu0) / self.std_err()
+    }
+
+    /// Robust counterpart of [`BenchOut::student_ln_df`].
+    pub fn student_ln_df(&self) -> f64 {
+        self.h as f64 - 1.
+    }
+
+    /// Robust counterpart of [`BenchOut::student_ln_p`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.h() <= 1`.
+    /// - `self.stdev_ln() == 0`.
+    pub fn student_ln_p(&self, ln_mu0: f64, alt_hyp: AltHyp) -> f64 {
+        t_to_p(self.student_ln_t(ln_mu0), self.student_ln_df(), alt_hyp)
+    }
+
+    /// Robust counterpart of [`BenchOut::student_ln_ci`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.h() <= 1`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    pub fn student_ln_ci(&self, alpha: f64) -> Ci {
+        assert!(
+            0. < alpha && alpha < 1.,
+            "`alpha` not in open interval `(0, 1)`"
+        );
+        let half_width = t_quantile(1. - alpha / 2., self.student_ln_df()) * self.std_err();
+        Ci(self.mean_ln - half_width, self.mean_ln + half_width)
+    }
+
+    /// Robust counterpart of [`BenchOut::student_median_ci`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.h() <= 1`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    pub fn student_median_ci(&self, alpha: f64) -> (FpSeconds, FpSeconds) {
+        let Ci(low, high) = self.student_ln_ci(alpha);
+        (low.exp().into(), high.exp().into())
+    }
+}
+
+impl BenchOut {
+    /// Natural logarithms of the non-zero latencies recorded in the histogram, in increasing order, with their counts.
+    fn ln_counts(&self) -> Vec<(f64, u64)> {
+        self.iter_with_counts()
+            .filter(|&(value, _)| value > FpSeconds::ZERO)
+            .map(|(value, count)| (value.as_f64().ln(), count as u64))
+            .collect()
+    }
+
+    /// Classifies latencies as outliers using Tukey's fences on their natural logarithms: the mild (severe) fences
+    /// are [`TUKEY_MILD`] ([`TUKEY_SEVERE`]) times the interquartile range below the first quartile and above the
+    /// third quartile.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of non-zero observations is zero.
+    pub fn tukey_outliers(&self) -> Outliers {
+        let lns = self.ln_counts();
+        let q1 = quantile(&lns, 0.25);
+        let q3 = quantile(&lns, 0.75);
+        let iqr = q3 - q1;
+        let fences = [
+            q1 - TUKEY_SEVERE * iqr,
+            q1 - TUKEY_MILD * iqr,
+            q3 + TUKEY_MILD * iqr,
+            q3 + TUKEY_SEVERE * iqr,
+        ];
+        classify(&lns, fences)
+    }
+
+    /// Classifies latencies as outliers using the median absolute deviation (MAD) of their natural logarithms:
+    /// the mild (severe) fences are [`MAD_MILD`] ([`MAD_SEVERE`]) times the scaled MAD below and above the median.
+    /// The MAD is scaled to be a consistent estimator of the standard deviation under the log-normal assumption.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of non-zero observations is zero.
+    pub fn mad_outliers(&self) -> Outliers {
+        let lns = self.ln_counts();
+        let median = quantile(&lns, 0.5);
+        let mut devs = lns
+            .iter()
+            .map(|&(x, count)| ((x - median).abs(), count))
+            .collect::<Vec<_>>();
+        devs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mad = MAD_SCALE * quantile(&devs, 0.5);
+        let fences = [
+            median - MAD_SEVERE * mad,
+            median - MAD_MILD * mad,
+            median + MAD_MILD * mad,
+            median + MAD_SEVERE * mad,
+        ];
+        classify(&lns, fences)
+    }
+
+    /// Trimmed or winsorized statistics for the natural logarithms of the latencies, which are less sensitive to
+    /// extreme latencies (e.g., stalls) than the corresponding `*_ln_*` statistics of `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - The proportion in `method` is not in the interval `[0, 0.5)`.
+    /// - The number of non-zero observations is zero.
+    pub fn robust_ln(&self, method: RobustMethod) -> RobustLn {
+        let g = match method {
+            RobustMethod::Trim(g) | RobustMethod::Winsorize(g) => g,
+        };
+        assert!(
+            (0. ..0.5).contains(&g),
+            "proportion must be in the interval `[0, 0.5)`"
+        );
+
+        let lns = self.ln_counts();
+        let n = lns.iter().map(|&(_, count)| count).sum::<u64>();
+        assert!(n > 0, "number of non-zero observations is zero");
+        let k = (g * n as f64).floor() as u64;
+        let h = n - 2 * k;
+        let low = value_at(&lns, k);
+        let high = value_at(&lns, n - 1 - k);
+
+        // Sums over the winsorized sample and over the observations that are not trimmed.
+        let (mut sum_w, mut sum2_w, mut sum_kept) = (0., 0., 0.);
+        let mut pos = 0;
+        for &(x, count) in &lns {
+            let below = count.min(k.saturating_sub(pos));
+            let above = count.min((pos + count).saturating_sub(n - k));
+            let kept = count - below - above;
+            for (value, c) in [(low, below), (x, kept), (high, above)] {
+                sum_w += value * c as f64;
+                sum2_w += value.powi(2) * c as f64;
+            }
+            sum_kept += x * kept as f64;
+            pos += count;
+        }
+
+        let nf = n as f64;
+        let mean_w = sum_w / nf;
+        let stdev_ln = if n > 1 {
+            ((sum2_w - nf * mean_w.powi(2)) / (nf - 1.)).max(0.).sqrt()
+        } else {
+            0.
+        };
+        let mean_ln = match method {
+            RobustMethod::Trim(_) => sum_kept / h as f64,
+            RobustMethod::Winsorize(_) => mean_w,
+        };
+
+        RobustLn {
+            method,
+            n,
+            h,
+            mean_ln,
+            stdev_ln,
+        }
+    }
+}
+
+/// Value at zero-based position `pos` of the sample represented by `values`, sorted in increasing order.
+fn value_at(values: &[(f64, u64)], pos: u64) -> f64 {
+    let mut acc = 0;
+    for &(x, count) in values {
+        acc += count;
+        if acc > pos {
+            return x;
+        }
+    }
+    panic!("number of non-zero observations is zero")
+}
+
+/// Quantile `q` of the sample represented by `values`, sorted in increasing order, with linear interpolation
+/// between adjacent order statistics.
+fn quantile(values: &[(f64, u64)], q: f64) -> f64 {
+    let n = values.iter().map(|&(_, count)| count).sum::<u64>();
+    assert!(n > 0, "number of non-zero observations is zero");
+    let pos = q * (n - 1) as f64;
+    let lo = value_at(values, pos.floor() as u64);
+    let hi = value_at(values, pos.ceil() as u64);
+    lo + (hi - lo) * pos.fract()
+}
+
+fn classify(lns: &[(f64, u64)], fences: [f64; 4]) -> Outliers {
+    let mut counts = [0; 4];
+    for &(x, count) in lns {
+        let class = if x < fences[0] {
+            Some(0)
+        } else if x < fences[1] {
+            Some(1)
+        } else if x > fences[3] {
+            Some(3)
+        } else if x > fences[2] {
+            Some(2)
+        } else {
+            None
+        };
+        if let Some(class) = class {
+            counts[class] += count;
+        }
+    }
+    Outliers {
+        fences: fences.map(|f| f.exp().into()),
+        counts,
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- robust::test --nocapture
+mod test {
+    use super::*;
+    use crate::{
+        BenchCfg,
+        test_support::{LO_STDEV_LN, lognormal_samp},
+    };
+    use basic_stats::{approx_eq, rel_approx_eq};
+
+    const MU: f64 = -11.5; // ~10µs
+
+    fn out_with_stalls(n_stalls: usize) -> BenchOut {
+        let cfg = BenchCfg::default();
+        let stalls = std::iter::repeat_n(FpSeconds::from_millis(5), n_stalls);
+        BenchOut::from_iter(&cfg, lognormal_samp(MU, *LO_STDEV_LN, 10_000).chain(stalls))
+    }
+
+    #[test]
+    fn test_outliers() {
+        let clean = out_with_stalls(0);
+        let stalled = out_with_stalls(20);
+
+        for outliers in [stalled.tukey_outliers(), stalled.mad_outliers()] {
+            println!("{outliers:?}");
+            assert!(outliers.high_severe() >= 20);
+            let fences = outliers.fences();
+            assert!(fences.windows(2).all(|w| w[0] < w[1]));
+            assert_eq!(outliers.low() + outliers.high(), outliers.total());
+        }
+
+        // For a log-normal sample, about 0.7% of the observations are beyond Tukey's mild fences.
+        let outliers = clean.tukey_outliers();
+        assert!(outliers.total() < 200, "{outliers:?}");
+        assert_eq!(0, outliers.high_severe());
+    }
+
+    #[test]
+    fn test_robust_ln() {
+        let clean = out_with_stalls(0);
+        let stalled = out_with_stalls(100);
+
+        // With no trimming, the statistics match the raw ones, up to histogram resolution.
+        let untrimmed = stalled.robust_ln(RobustMethod::Trim(0.));
+        approx_eq!(stalled.mean_ln(), untrimmed.mean_ln(), 1e-2);
+        rel_approx_eq!(stalled.stdev_ln(), untrimmed.stdev_ln(), 1e-2);
+        assert_eq!(stalled.n_nz(), untrimmed.h());
+
+        for method in [RobustMethod::Trim(0.05), RobustMethod::Winsorize(0.05)] {
+            let robust = stalled.robust_ln(method);
+            println!("{robust:?}");
+            assert_eq!(stalled.n_nz(), robust.n());
+            assert!(
+                (robust.mean_ln() - clean.mean_ln()).abs()
+                    < (stalled.mean_ln() - clean.mean_ln()).abs() / 10.
+            );
+            assert!(robust.stdev_ln() < stalled.stdev_ln());
+
+            let ci = robust.student_ln_ci(0.05);
+            assert!(ci.0 < robust.mean_ln() && robust.mean_ln() < ci.1);
+            let (low, high) = robust.student_median_ci(0.05);
+            approx_eq!(ci.0.exp(), low.as_f64(), 1e-15);
+            approx_eq!(ci.1.exp(), high.as_f64(), 1e-15);
+            approx_eq!(0.05, robust.student_ln_p(ci.1, AltHyp::Ne), 1e-9);
+        }
+
+        let trimmed = stalled.robust_ln(RobustMethod::Trim(0.05));
+        assert_eq!(trimmed.n() - 2 * (trimmed.n() / 20), trimmed.h());
+
+        let result = std::panic::catch_unwind(|| stalled.robust_ln(RobustMethod::Trim(0.5)));
+        assert!(result.is_err());
+    }
+}
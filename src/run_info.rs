@@ -0,0 +1,236 @@
+//! Information about the execution of a benchmark run, as opposed to the latency data it collected.
+
+use crate::FpSeconds;
+use std::time::Duration;
+
+/// Information about the execution of a benchmark run.
+///
+/// Available from [`BenchOut::run_info`](crate::BenchOut::run_info) and
+/// [`multi::BenchOut::run_info`](crate::multi::BenchOut::run_info).
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RunInfo {
+    pub(crate) precision: Option<PrecisionOutcome>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) overhead: Option<OverheadEstimate>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) batch: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) execs_per_sec: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) open_loop: Option<OpenLoopInfo>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) aborted: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) warmup: Option<WarmupTrace>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) out_of_range: u64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) overhead_floored: u64,
+}
+
+impl RunInfo {
+    /// Outcome of a [`RunLength::Precision`](crate::RunLength::Precision) run; `None` for other run lengths.
+    pub fn precision(&self) -> Option<PrecisionOutcome> {
+        self.precision
+    }
+
+    /// Timer overhead estimate, per execution of a closure, like the captured latencies; `None` if the run used
+    /// [`OverheadMode::Ignore`](crate::OverheadMode::Ignore).
+    pub fn overhead(&self) -> Option<OverheadEstimate> {
+        self.overhead
+    }
+
+    /// Batch size chosen by auto-batching (see [`BenchCfg::auto_batch`](crate::BenchCfg::auto_batch)); `None` if
+    /// the run was not auto-batched.
+    pub fn batch(&self) -> Option<usize> {
+        self.batch
+    }
+
+    /// Estimated number of iterations per second, from the probing done before the warm-up; `None` if `self` was
+    /// not produced by a benchmark run or the estimate was not finite.
+    ///
+    /// For runs of multiple closures, an iteration executes each closure once.
+    pub fn execs_per_sec(&self) -> Option<f64> {
+        self.execs_per_sec
+    }
+
+    /// Arrival rates and late invocations of an open-loop run (see [`bench_run_open_loop`](crate::bench_run_open_loop));
+    /// `None` for other runs.
+    pub fn open_loop(&self) -> Option<OpenLoopInfo> {
+        self.open_loop
+    }
+
+    /// `true` if the run was stopped early through a [`CancelToken`](crate::CancelToken), in which case the latency
+    /// data covers only part of the requested run length and may be empty.
+    pub fn aborted(&self) -> bool {
+        self.aborted
+    }
+
+    /// Trace of an adaptive warm-up (see [`WarmupMode::Adaptive`](crate::WarmupMode::Adaptive)); `None` if the run
+    /// used a fixed warm-up.
+    pub fn warmup(&self) -> Option<&WarmupTrace> {
+        self.warmup.as_ref()
+    }
+
+    /// Number of observations above the highest trackable value of the histogram that were clamped or rejected
+    /// (see [`HistOverflow`](crate::HistOverflow)); always `0` with
+    /// [`HistOverflow::Resize`](crate::HistOverflow::Resize).
+    pub fn out_of_range(&self) -> u64 {
+        self.out_of_range
+    }
+
+    /// Number of observations that were at most the timer overhead and were floored at zero when it was subtracted
+    /// (see [`OverheadMode::Subtract`](crate::OverheadMode::Subtract)). Such observations don't contribute to the
+    /// statistics of the logarithms of the latencies, so a large count relative to [`BenchOut::n`](crate::BenchOut::n)
+    /// means the results are dominated by the overhead.
+    pub fn overhead_floored(&self) -> u64 {
+        self.overhead_floored
+    }
+
+    /// Keeps the information on which `self` and `other` agree and clears the rest; a merge is aborted if either
+    /// input is, and its counts of out-of-range and overhead-floored observations are the sums of those of the
+    /// inputs.
+    /// See [`BenchOut::merge`](crate::BenchOut::merge).
+    pub(crate) fn merge(&mut self, other: &RunInfo) {
+        fn keep_if_equal<T: PartialEq>(field: &mut Option<T>, other: &Option<T>) {
+            if field != other {
+                *field = None;
+            }
+        }
+        keep_if_equal(&mut self.precision, &other.precision);
+        keep_if_equal(&mut self.overhead, &other.overhead);
+        keep_if_equal(&mut self.batch, &other.batch);
+        keep_if_equal(&mut self.execs_per_sec, &other.execs_per_sec);
+        keep_if_equal(&mut self.open_loop, &other.open_loop);
+        keep_if_equal(&mut self.warmup, &other.warmup);
+        self.aborted |= other.aborted;
+        self.out_of_range += other.out_of_range;
+        self.overhead_floored += other.overhead_floored;
+    }
+}
+
+/// Outcome of a [`RunLength::Precision`](crate::RunLength::Precision) run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrecisionOutcome {
+    pub(crate) converged: bool,
+    pub(crate) rel_half_width: Option<f64>,
+}
+
+impl PrecisionOutcome {
+    /// `true` if the target precision was reached; `false` if the run ended because a cap was reached or the latency
+    /// source was exhausted.
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+
+    /// Relative half-width of the confidence interval at the end of the run
+    /// (see [`PrecisionTarget`](crate::PrecisionTarget)).
+    ///
+    /// Is `None` if there were not enough non-zero observations to compute the confidence interval.
+    pub fn rel_half_width(&self) -> Option<f64> {
+        self.rel_half_width
+    }
+}
+
+/// Timer overhead estimated before a benchmark run (see [`OverheadMode`](crate::OverheadMode)).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OverheadEstimate {
+    pub(crate) median: FpSeconds,
+    pub(crate) subtracted: bool,
+}
+
+impl OverheadEstimate {
+    /// Median latency of an empty closure measured through the benchmarking path, per execution. For batched
+    /// latency sources, it is the median of the mean latency over a batch of empty closures.
+    pub fn median(&self) -> FpSeconds {
+        self.median
+    }
+
+    /// `true` if the overhead was subtracted from the captured latencies.
+    pub fn subtracted(&self) -> bool {
+        self.subtracted
+    }
+}
+
+/// Outcome of an open-loop run (see [`bench_run_open_loop`](crate::bench_run_open_loop)).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OpenLoopInfo {
+    pub(crate) target_rate: f64,
+    pub(crate) achieved_rate: f64,
+    pub(crate) invocations: u64,
+    pub(crate) late_invocations: u64,
+}
+
+impl OpenLoopInfo {
+    /// Target arrival rate, in invocations per second.
+    pub fn target_rate(&self) -> f64 {
+        self.target_rate
+    }
+
+    /// Achieved rate: number of invocations divided by the duration of the run, in invocations per second.
+    pub fn achieved_rate(&self) -> f64 {
+        self.achieved_rate
+    }
+
+    /// Number of invocations of the target. With
+    /// [`OmissionCorrection::RecordCorrect`](crate::OmissionCorrection::RecordCorrect), the number of observations
+    /// may be higher.
+    pub fn invocations(&self) -> u64 {
+        self.invocations
+    }
+
+    /// Number of invocations that started at least one mean inter-arrival interval after their intended start time
+    /// because the target was still executing. Each late invocation is counted once, however late it started.
+    pub fn late_invocations(&self) -> u64 {
+        self.late_invocations
+    }
+}
+
+/// Trace of an adaptive warm-up (see [`AdaptiveWarmup`](crate::AdaptiveWarmup)).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WarmupTrace {
+    pub(crate) window: usize,
+    pub(crate) medians: Vec<FpSeconds>,
+    pub(crate) cutoff: Option<usize>,
+    pub(crate) iterations: usize,
+    pub(crate) elapsed: Duration,
+}
+
+impl WarmupTrace {
+    /// Number of iterations per window.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Median aggregate latency of each complete warm-up window, in order.
+    pub fn medians(&self) -> &[FpSeconds] {
+        &self.medians
+    }
+
+    /// Number of warm-up iterations after which steady state was detected; `None` if the warm-up ended without
+    /// detecting it, i.e., after the maximum duration, when the latency source was exhausted, or when the run was
+    /// cancelled.
+    pub fn cutoff(&self) -> Option<usize> {
+        self.cutoff
+    }
+
+    /// `true` if steady state was detected.
+    pub fn steady(&self) -> bool {
+        self.cutoff.is_some()
+    }
+
+    /// Total number of warm-up iterations.
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Duration of the warm-up.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
@@ -0,0 +1,301 @@
+//! Aggregation of repeated runs of the same benchmark, e.g., from several process invocations.
+
+use crate::{BenchOut, FpSeconds};
+use basic_stats::{
+    core::{Ci, SampleMoments, sample_mean, sample_stdev},
+    normal::student_1samp_ci,
+};
+
+/// Statistics of one run in a [`RunSet`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RunStats {
+    n: u64,
+    median: FpSeconds,
+    mean_ln: f64,
+    stdev_ln: f64,
+    n_nz: u64,
+}
+
+impl RunStats {
+    fn new(out: &BenchOut) -> Self {
+        Self {
+            n: out.n(),
+            median: out.median(),
+            mean_ln: out.mean_ln(),
+            stdev_ln: out.stdev_ln(),
+            n_nz: out.n_nz,
+        }
+    }
+
+    /// Number of observations of the run.
+    pub fn n(&self) -> u64 {
+        self.n
+    }
+
+    /// Median latency of the run.
+    pub fn median(&self) -> FpSeconds {
+        self.median
+    }
+
+    /// Mean of the natural logarithms of the run's latencies (see [`BenchOut::mean_ln`]).
+    pub fn mean_ln(&self) -> f64 {
+        self.mean_ln
+    }
+
+    /// Standard deviation of the natural logarithms of the run's latencies (see [`BenchOut::stdev_ln`]).
+    pub fn stdev_ln(&self) -> f64 {
+        self.stdev_ln
+    }
+}
+
+/// Results of repeated runs of the same benchmark.
+///
+/// Keeps the statistics of each run as well as the merge of all runs (see [`BenchOut::merge`]), so that the
+/// run-to-run variance of `mean(ln(latency))` can be reported separately from the variance within runs. When runs
+/// differ systematically (e.g., due to memory layout or CPU frequency), the confidence intervals of a single
+/// [`BenchOut`] are too narrow, whereas [`Self::median_ci`] accounts for both sources of variance.
+///
+/// With feature **"serde"**, this struct can be serialized and deserialized (see [`BenchOut`]).
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RunSet {
+    runs: Vec<RunStats>,
+    merged: Option<BenchOut>,
+}
+
+impl RunSet {
+    /// Instantiates an empty `Self`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the results of a run to `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out` has fewer than 2 non-zero observations.
+    pub fn push(&mut self, out: BenchOut) {
+        assert!(
+            out.n_nz >= 2,
+            "number of non-zero observations must be at least 2"
+        );
+        self.runs.push(RunStats::new(&out));
+        match &mut self.merged {
+            Some(merged) => merged.merge(&out),
+            None => self.merged = Some(out),
+        }
+    }
+
+    /// Number of runs.
+    pub fn len(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// `true` if there are no runs.
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// Statistics of each run, in the order they were added.
+    pub fn runs(&self) -> &[RunStats] {
+        &self.runs
+    }
+
+    /// Median latency of each run, in the order they were added.
+    pub fn run_medians(&self) -> Vec<FpSeconds> {
+        self.runs.iter().map(|r| r.median).collect()
+    }
+
+    /// Merge of all runs, or `None` if there are no runs.
+    pub fn merged(&self) -> Option<&BenchOut> {
+        self.merged.as_ref()
+    }
+
+    /// Number of runs, sum, and sum of squares of the runs' `mean(ln(latency))` values.
+    fn run_mean_ln_sums(&self) -> (u64, f64, f64) {
+        let (sum, sum2) = self.runs.iter().fold((0., 0.), |(sum, sum2), r| {
+            (sum + r.mean_ln, sum2 + r.mean_ln.powi(2))
+        });
+        (self.runs.len() as u64, sum, sum2)
+    }
+
+    /// Mean over runs of each run's `mean(ln(latency))`, with each run weighted equally.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no runs.
+    pub fn mean_ln(&self) -> f64 {
+        let (n, sum, _) = self.run_mean_ln_sums();
+        sample_mean(n, sum).expect("number of runs is zero")
+    }
+
+    /// Pooled variance of the natural logarithms of latencies within runs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no runs.
+    pub fn within_var_ln(&self) -> f64 {
+        assert!(!self.is_empty(), "number of runs is zero");
+        let (ss, df) = self.runs.iter().fold((0., 0.), |(ss, df), r| {
+            let df_r = r.n_nz as f64 - 1.;
+            (ss + df_r * r.stdev_ln.powi(2), df + df_r)
+        });
+        ss / df
+    }
+
+    /// Sample variance of the runs' `mean(ln(latency))` values. It includes the contribution of the variance within
+    /// runs, which decreases with the number of observations per run (see [`Self::between_var_component_ln`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are fewer than 2 runs.
+    pub fn between_var_ln(&self) -> f64 {
+        assert!(self.len() >= 2, "number of runs must be at least 2");
+        let (n, sum, sum2) = self.run_mean_ln_sums();
+        sample_stdev(n, sum, sum2)
+            .expect("can't happen: number of runs is at least 2")
+            .powi(2)
+    }
+
+    /// Estimate of the run-to-run variance component of `mean(ln(latency))`, i.e., the variance that remains
+    /// however many observations each run has: [`Self::between_var_ln`] minus the expected contribution of the
+    /// variance within runs, floored at zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are fewer than 2 runs.
+    pub fn between_var_component_ln(&self) -> f64 {
+        let mean_inv_n =
+            self.runs.iter().map(|r| 1. / r.n_nz as f64).sum::<f64>() / self.len() as f64;
+        (self.between_var_ln() - self.within_var_ln() * mean_inv_n).max(0.)
+    }
+
+    /// Confidence interval for `mean(ln(latency))` with confidence level `(1 - alpha)`, accounting for both the
+    /// variance within runs and the variance between runs.
+    ///
+    /// Computed with Student's t distribution from the runs' `mean(ln(latency))` values, with `len() - 1` degrees of
+    /// freedom.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.len() < 2`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    pub fn mean_ln_ci(&self, alpha: f64) -> Ci {
+        assert!(self.len() >= 2, "number of runs must be at least 2");
+        let (n, sum, sum2) = self.run_mean_ln_sums();
+        student_1samp_ci(&SampleMoments::new(n, sum, sum2), alpha)
+            .expect("`alpha` not in open interval `(0, 1)`")
+    }
+
+    /// Confidence interval for the median latency with confidence level `(1 - alpha)`, accounting for both the
+    /// variance within runs and the variance between runs. It is the exponential of [`Self::mean_ln_ci`].
+    ///
+    /// Assumes that latencies are approximately log-normal.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the following conditions is true:
+    /// - `self.len() < 2`.
+    /// - `alpha` not in open interval `(0, 1)`.
+    pub fn median_ci(&self, alpha: f64) -> (FpSeconds, FpSeconds) {
+        let Ci(log_low, log_high) = self.mean_ln_ci(alpha);
+        (log_low.exp().into(), log_high.exp().into())
+    }
+}
+
+impl FromIterator<BenchOut> for RunSet {
+    fn from_iter<T: IntoIterator<Item = BenchOut>>(iter: T) -> Self {
+        let mut set = Self::new();
+        for out in iter {
+            set.push(out);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- run_set::test --nocapture
+mod test {
+    use super::*;
+    use crate::{
+        BenchCfg, LatencyUnit, SampleRetention,
+        test_support::{LO_STDEV_LN, lognormal_samp},
+    };
+
+    const ALPHA: f64 = 0.05;
+
+    fn run(cfg: &BenchCfg, median: f64, n: usize) -> BenchOut {
+        BenchOut::from_iter(cfg, lognormal_samp(median.ln(), *LO_STDEV_LN, n))
+    }
+
+    #[test]
+    fn test_merge() {
+        let cfg = BenchCfg::default().with_sample_retention(SampleRetention::Bounded(1_000));
+        let out1 = run(&cfg, 1e-6, 600);
+        let out2 = run(
+            &cfg.clone().with_recording_unit(LatencyUnit::PICO),
+            2e-6,
+            400,
+        );
+
+        let mut merged = run(&cfg, 1e-6, 600);
+        merged.merge(&out2);
+
+        assert_eq!(1_000, merged.n());
+        assert_eq!(LatencyUnit::NANO, merged.recording_unit());
+        let exp_mean = (out1.mean() * 600. + out2.mean() * 400.).as_f64() / 1_000.;
+        assert!((merged.mean().as_f64() - exp_mean).abs() < 1e-12);
+        let exp_mean_ln = (out1.mean_ln() * 600. + out2.mean_ln() * 400.) / 1_000.;
+        assert!((merged.mean_ln() - exp_mean_ln).abs() < 1e-9);
+        assert!(out1.median() < merged.median() && merged.median() < out2.median());
+
+        let samples = merged.samples().expect("samples retained by both");
+        assert_eq!(1_000, samples.len());
+        assert_eq!(1_000, samples.seen());
+
+        // Raw samples are discarded if not retained by both.
+        let mut merged = run(&cfg, 1e-6, 10);
+        merged.merge(&run(&BenchCfg::default(), 1e-6, 10));
+        assert!(merged.samples().is_none());
+
+        // A merge is aborted if any input is.
+        let mut aborted = run(&cfg, 1e-6, 10);
+        aborted.run_info.aborted = true;
+        merged.merge(&aborted);
+        assert!(merged.run_info().aborted());
+    }
+
+    #[test]
+    fn test_run_set() {
+        let cfg = BenchCfg::default();
+        let medians = [1.0e-6, 1.1e-6, 0.9e-6, 1.05e-6, 0.95e-6];
+        let set = medians
+            .iter()
+            .map(|&median| run(&cfg, median, 2_000))
+            .collect::<RunSet>();
+
+        assert_eq!(5, set.len());
+        assert_eq!(10_000, set.merged().unwrap().n());
+        for (median, exp) in set.run_medians().iter().zip(medians) {
+            assert!((median.as_f64() / exp - 1.).abs() < 0.02, "{median:?}");
+        }
+
+        // Run-to-run noise dominates the within-run contribution to the variance of the run means.
+        let within = set.within_var_ln();
+        let between = set.between_var_ln();
+        println!("within={within}, between={between}");
+        assert!((within.sqrt() / *LO_STDEV_LN - 1.).abs() < 0.05);
+        assert!(between > 10. * within / 2_000.);
+        assert!(set.between_var_component_ln() > 0.);
+
+        // The run-aware CI is wider than the one from the merged output alone.
+        let (low, high) = set.median_ci(ALPHA);
+        let (low_merged, high_merged) = set.merged().unwrap().student_median_ci(ALPHA);
+        println!("run set ci=({low:?}, {high:?}), merged ci=({low_merged:?}, {high_merged:?})");
+        assert!(low < low_merged && high_merged < high);
+        assert!(low.as_f64() < 1e-6 && 1e-6 < high.as_f64());
+    }
+}
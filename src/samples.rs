@@ -0,0 +1,430 @@
+//! Retention of raw per-iteration latencies and time-series analysis of a benchmark run.
+
+use crate::{FpSeconds, rng::SplitMix64, stats_support::kolmogorov_sf};
+
+/// Determines whether and how the raw per-iteration latencies of a benchmark run are retained
+/// (see [`BenchCfg::with_sample_retention`](crate::BenchCfg::with_sample_retention)).
+///
+/// For batched latency sources, one latency (the batch mean) is retained per batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum SampleRetention {
+    /// Raw latencies are not retained.
+    Off,
+    /// The first `capacity` latencies are retained; later ones are discarded.
+    Bounded(usize),
+    /// A uniform random sample of `capacity` latencies is retained over the whole run (reservoir sampling),
+    /// using a pseudo-random number generator seeded with the second argument, for reproducibility.
+    Reservoir(usize, u64),
+}
+
+/// Raw per-iteration latencies retained from a benchmark run, together with the position of each latency in the
+/// run (see [`SampleRetention`]).
+///
+/// Available from [`BenchOut::samples`](crate::BenchOut::samples). The time-series statistics are computed on the
+/// natural logarithms of the non-zero retained latencies, in run order, consistent with the `*_ln_*` statistics of
+/// [`BenchOut`](crate::BenchOut). With [`SampleRetention::Reservoir`], consecutive retained latencies are generally
+/// not consecutive in the run, so [`Self::lag1_autocorr_ln`] understates short-range autocorrelation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Samples {
+    capacity: usize,
+    seed: Option<u64>,
+    seen: u64,
+    entries: Vec<(u64, FpSeconds)>,
+    rng: SplitMix64,
+}
+
+impl Samples {
+    /// Creates a new empty instance, or `None` if `retention` is [`SampleRetention::Off`].
+    pub(crate) fn new(retention: SampleRetention) -> Option<Self> {
+        let (capacity, seed) = match retention {
+            SampleRetention::Off => return None,
+            SampleRetention::Bounded(capacity) => (capacity, None),
+            SampleRetention::Reservoir(capacity, seed) => (capacity, Some(seed)),
+        };
+        Some(Self {
+            capacity,
+            seed,
+            seen: 0,
+            entries: Vec::new(),
+            rng: SplitMix64::new(seed.unwrap_or_default()),
+        })
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.seen = 0;
+        self.entries.clear();
+        self.rng = SplitMix64::new(self.seed.unwrap_or_default());
+    }
+
+    /// Offers the latency of the next iteration for retention.
+    #[inline(always)]
+    pub(crate) fn capture(&mut self, latency: FpSeconds) {
+        let index = self.seen;
+        self.seen += 1;
+
+        if self.entries.len() < self.capacity {
+            self.entries.push((index, latency));
+        } else if self.seed.is_some() {
+            let slot = self.rng.below(self.seen) as usize;
+            if slot < self.capacity {
+                self.entries[slot] = (index, latency);
+            }
+        }
+    }
+
+    /// Offers the latencies offered to `other` for retention, as if they had been offered to `self` after its own.
+    ///
+    /// The retention policy of `self` applies. With [`SampleRetention::Reservoir`], the result is a uniform random
+    /// sample of the combined latencies, drawn from the samples of `self` and `other`; it may hold fewer than
+    /// `capacity` latencies if `other` retained fewer than needed.
+    pub(crate) fn merge(&mut self, other: &Samples) {
+        let offset = self.seen;
+        let mut others = other
+            .entries
+            .iter()
+            .map(|&(index, latency)| (index + offset, latency))
+            .collect::<Vec<_>>();
+
+        if self.seed.is_none() {
+            others.sort_by_key(|&(index, _)| index);
+            let room = self.capacity.saturating_sub(self.entries.len());
+            self.entries.extend(others.into_iter().take(room));
+        } else {
+            // Draws without replacement from the combined population, choosing the side of each draw in proportion to
+            // its remaining population and taking a not-yet-taken latency from that side's uniform sample.
+            let mut own = std::mem::take(&mut self.entries);
+            self.shuffle(&mut own);
+            self.shuffle(&mut others);
+            let (mut pop_own, mut pop_others) = (self.seen, other.seen);
+            while self.entries.len() < self.capacity && pop_own + pop_others > 0 {
+                let (side, pop) = if self.rng.below(pop_own + pop_others) < pop_own {
+                    (&mut own, &mut pop_own)
+                } else {
+                    (&mut others, &mut pop_others)
+                };
+                *pop -= 1;
+                let Some(entry) = side.pop() else {
+                    break;
+                };
+                self.entries.push(entry);
+            }
+        }
+
+        self.seen += other.seen;
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.rng.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// Number of iterations whose latencies were offered for retention.
+    pub fn seen(&self) -> u64 {
+        self.seen
+    }
+
+    /// Number of retained latencies.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// `true` if no latencies were retained.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the retained latencies as `(position, latency)` pairs in run order, where `position` is the zero-based
+    /// position of the iteration in the run.
+    ///
+    /// Each call copies and sorts the retained latencies. The sort takes linear time with
+    /// [`SampleRetention::Bounded`], as they are then retained in run order, and `O(n log n)` for `n` retained
+    /// latencies otherwise, so callers that iterate repeatedly should collect the result once.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, FpSeconds)> {
+        let mut entries = self.entries.clone();
+        entries.sort_by_key(|&(index, _)| index);
+        entries.into_iter()
+    }
+
+    /// Natural logarithms of the non-zero retained latencies, with their positions, in run order.
+    fn lns(&self) -> Vec<(u64, f64)> {
+        self.iter()
+            .filter(|&(_, latency)| latency > FpSeconds::ZERO)
+            .map(|(index, latency)| (index, latency.as_f64().ln()))
+            .collect()
+    }
+
+    /// Lag-1 sample autocorrelation of the natural logarithms of the retained latencies.
+    ///
+    /// Returns `None` if there are fewer than 3 non-zero retained latencies or if they are all equal.
+    pub fn lag1_autocorr_ln(&self) -> Option<f64> {
+        lag1_autocorr(&self.lns().into_iter().map(|(_, x)| x).collect::<Vec<_>>())
+    }
+
+    /// Ratio of the effective sample size to the actual sample size, under a first-order autoregressive model with
+    /// the autocorrelation [`Self::lag1_autocorr_ln`]: `(1 - r) / (1 + r)`.
+    ///
+    /// Negative autocorrelation is treated as zero, so the ratio is in `(0, 1]`. Returns `1` if the autocorrelation
+    /// is not available.
+    pub fn ess_ratio(&self) -> f64 {
+        ess_ratio(self.lag1_autocorr_ln())
+    }
+
+    /// Detects a shift in the level of the natural logarithms of the retained latencies, using the CUSUM statistic.
+    ///
+    /// The position of the shift is where the absolute cumulative sum of deviations from the overall mean is largest.
+    /// Its significance is assessed against the asymptotic distribution of the maximum of a Brownian bridge, with
+    /// the variance inflated for lag-1 autocorrelation. A gradual trend also results in a significant statistic,
+    /// with a position near the middle of the run.
+    ///
+    /// Returns `None` if there are fewer than 3 non-zero retained latencies or if they are all equal.
+    pub fn changepoint(&self) -> Option<Changepoint> {
+        let lns = self.lns();
+        let xs = lns.iter().map(|&(_, x)| x).collect::<Vec<_>>();
+        let n = xs.len();
+        let rho = lag1_autocorr(&xs)?;
+
+        let nf = n as f64;
+        let mean = xs.iter().sum::<f64>() / nf;
+        let var = xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (nf - 1.);
+        let long_run_var = var / ess_ratio(Some(rho));
+
+        let mut cusum = 0.;
+        let mut max_abs = 0.;
+        let mut split = 1;
+        for (i, x) in xs.iter().enumerate().take(n - 1) {
+            cusum += x - mean;
+            if cusum.abs() > max_abs {
+                max_abs = cusum.abs();
+                split = i + 1;
+            }
+        }
+
+        let statistic = max_abs / (long_run_var * nf).sqrt();
+        let mean_before = xs[..split].iter().sum::<f64>() / split as f64;
+        let mean_after = xs[split..].iter().sum::<f64>() / (n - split) as f64;
+
+        Some(Changepoint {
+            position: lns[split].0,
+            ln_shift: mean_after - mean_before,
+            statistic,
+            p: kolmogorov_sf(statistic),
+        })
+    }
+}
+
+/// Lag-1 sample autocorrelation of `xs`, or `None` if `xs` has fewer than 3 elements or zero variance.
+fn lag1_autocorr(xs: &[f64]) -> Option<f64> {
+    if xs.len() < 3 {
+        return None;
+    }
+    let mean = xs.iter().sum::<f64>() / xs.len() as f64;
+    let denom = xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>();
+    if denom == 0. {
+        return None;
+    }
+    let numer = xs
+        .windows(2)
+        .map(|w| (w[0] - mean) * (w[1] - mean))
+        .sum::<f64>();
+    Some(numer / denom)
+}
+
+fn ess_ratio(rho: Option<f64>) -> f64 {
+    match rho {
+        Some(rho) => {
+            let rho = rho.clamp(0., 1.);
+            ((1. - rho) / (1. + rho)).max(f64::EPSILON)
+        }
+        None => 1.,
+    }
+}
+
+/// Result of [`Samples::changepoint`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Changepoint {
+    position: u64,
+    ln_shift: f64,
+    statistic: f64,
+    p: f64,
+}
+
+impl Changepoint {
+    /// Zero-based position in the run of the first iteration after the estimated shift.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Difference between the means of the natural logarithms of the latencies after and before the shift.
+    pub fn ln_shift(&self) -> f64 {
+        self.ln_shift
+    }
+
+    /// Ratio of the median latency after the shift to the median latency before the shift, under the
+    /// log-normal assumption.
+    pub fn ratio(&self) -> f64 {
+        self.ln_shift.exp()
+    }
+
+    /// Normalized CUSUM statistic.
+    pub fn statistic(&self) -> f64 {
+        self.statistic
+    }
+
+    /// p-value of the test of the hypothesis that the level of the latencies did not shift during the run.
+    pub fn p(&self) -> f64 {
+        self.p
+    }
+
+    /// `true` if the hypothesis that the level of the latencies did not shift is rejected at significance
+    /// level `alpha`.
+    pub fn is_significant(&self, alpha: f64) -> bool {
+        self.p < alpha
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- samples::test --nocapture
+mod test {
+    use super::*;
+    use basic_stats::approx_eq;
+
+    /// Deterministic pseudo-random standard normal sample (Box-Muller).
+    fn std_normal_samp(seed: u64, n: usize) -> Vec<f64> {
+        let mut rng = SplitMix64::new(seed);
+        let mut uniform = || (rng.next_u64() >> 11) as f64 / (1_u64 << 53) as f64;
+        (0..n)
+            .map(|_| {
+                let u1 = 1. - uniform();
+                let u2 = uniform();
+                (-2. * u1.ln()).sqrt() * (2. * std::f64::consts::PI * u2).cos()
+            })
+            .collect()
+    }
+
+    fn samples_from_lns(lns: impl Iterator<Item = f64>) -> Samples {
+        let mut samples = Samples::new(SampleRetention::Bounded(usize::MAX)).unwrap();
+        for ln in lns {
+            samples.capture(FpSeconds(ln.exp()));
+        }
+        samples
+    }
+
+    #[test]
+    fn test_bounded_retention() {
+        let mut samples = Samples::new(SampleRetention::Bounded(3)).unwrap();
+        for i in 1..=5 {
+            samples.capture(FpSeconds(i as f64));
+        }
+        assert_eq!(5, samples.seen());
+        assert_eq!(
+            vec![(0, FpSeconds(1.)), (1, FpSeconds(2.)), (2, FpSeconds(3.))],
+            samples.iter().collect::<Vec<_>>()
+        );
+
+        samples.reset();
+        assert!(samples.is_empty());
+        assert_eq!(0, samples.seen());
+        assert_eq!(None, Samples::new(SampleRetention::Off));
+    }
+
+    #[test]
+    fn test_reservoir_retention() {
+        let run = |seed| {
+            let mut samples = Samples::new(SampleRetention::Reservoir(100, seed)).unwrap();
+            for i in 0..10_000 {
+                samples.capture(FpSeconds(i as f64));
+            }
+            samples
+        };
+
+        let samples = run(7);
+        assert_eq!(100, samples.len());
+        let positions = samples.iter().map(|(i, _)| i).collect::<Vec<_>>();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        assert!(samples.iter().all(|(i, x)| x == FpSeconds(i as f64)));
+        // Uniform over the run, so not concentrated at the start.
+        assert!(positions.iter().filter(|&&i| i >= 5_000).count() > 25);
+
+        assert_eq!(samples, run(7));
+        assert_ne!(samples, run(8));
+    }
+
+    #[test]
+    fn test_merge() {
+        let filled = |retention, range: std::ops::Range<u64>| {
+            let mut samples = Samples::new(retention).unwrap();
+            for i in range {
+                samples.capture(FpSeconds(i as f64));
+            }
+            samples
+        };
+
+        let mut bounded = filled(SampleRetention::Bounded(5), 0..3);
+        bounded.merge(&filled(SampleRetention::Bounded(5), 3..10));
+        assert_eq!(10, bounded.seen());
+        assert!(bounded.iter().all(|(i, x)| x == FpSeconds(i as f64)));
+        assert_eq!(
+            vec![0, 1, 2, 3, 4],
+            bounded.iter().map(|(i, _)| i).collect::<Vec<_>>()
+        );
+
+        // Merging 3,000 then 1,000 latencies: about a quarter of the reservoir comes from the second run.
+        let mut reservoir = filled(SampleRetention::Reservoir(400, 1), 0..3_000);
+        reservoir.merge(&filled(SampleRetention::Reservoir(400, 2), 3_000..4_000));
+        assert_eq!(4_000, reservoir.seen());
+        assert_eq!(400, reservoir.len());
+        assert!(reservoir.iter().all(|(i, x)| x == FpSeconds(i as f64)));
+        let from_second = reservoir.iter().filter(|&(i, _)| i >= 3_000).count();
+        assert!((70..130).contains(&from_second), "{from_second}");
+    }
+
+    #[test]
+    fn test_lag1_autocorr_and_ess() {
+        let noise = std_normal_samp(1, 20_000);
+        let iid = samples_from_lns(noise.iter().cloned());
+        approx_eq!(0., iid.lag1_autocorr_ln().unwrap(), 0.03);
+        approx_eq!(1., iid.ess_ratio(), 0.06);
+
+        const RHO: f64 = 0.6;
+        let mut prev = 0.;
+        let ar1 = samples_from_lns(noise.iter().map(|e| {
+            prev = RHO * prev + e;
+            prev
+        }));
+        approx_eq!(RHO, ar1.lag1_autocorr_ln().unwrap(), 0.03);
+        approx_eq!((1. - RHO) / (1. + RHO), ar1.ess_ratio(), 0.03);
+    }
+
+    #[test]
+    fn test_changepoint() {
+        let noise = std_normal_samp(2, 2_000);
+
+        let stable = samples_from_lns(noise.iter().map(|e| 0.1 * e));
+        let cp = stable.changepoint().unwrap();
+        println!("stable: {cp:?}");
+        assert!(!cp.is_significant(0.01));
+
+        let shifted = samples_from_lns(
+            noise
+                .iter()
+                .enumerate()
+                .map(|(i, e)| 0.1 * e + if i < 1_200 { 0. } else { 0.05 }),
+        );
+        let cp = shifted.changepoint().unwrap();
+        println!("shifted: {cp:?}");
+        assert!(cp.is_significant(0.01));
+        assert!((1_100..1_300).contains(&cp.position()), "{cp:?}");
+        approx_eq!(0.05, cp.ln_shift(), 0.015);
+
+        assert!(
+            samples_from_lns([1., 1., 1.].into_iter())
+                .changepoint()
+                .is_none()
+        );
+    }
+}
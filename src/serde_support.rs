@@ -0,0 +1,310 @@
+//! Serde helpers for types that can't derive `Serialize`/`Deserialize` directly.
+//!
+//! Gated by feature **"serde"**.
+
+use crate::{BenchOut, HistOverflow, LatencyUnit, RunInfo, Samples, Timing};
+
+/// (De)serializes a [`Timing`](crate::Timing) histogram using HdrHistogram's compact V2 (deflate) encoding.
+///
+/// The encoding is lossless: all recorded values and counts, as well as the histogram's lowest discernible value,
+/// highest trackable value, and significant figures, are preserved. The deserialized histogram is auto-resizable,
+/// consistent with [`new_timing`](crate::new_timing); see [`BenchOutRepr`] for the histogram of a [`BenchOut`].
+pub(crate) mod timing_v2 {
+    use crate::Timing;
+    use hdrhistogram::serialization::{
+        Deserializer as HistDeserializer, Serializer as _, V2DeflateSerializer,
+    };
+    use serde::{Deserialize, Deserializer, Serializer, de, ser};
+
+    pub(crate) fn serialize<S: Serializer>(
+        hist: &Timing,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut buf = Vec::<u8>::new();
+        V2DeflateSerializer::new()
+            .serialize(hist, &mut buf)
+            .map_err(|e| {
+                ser::Error::custom(format!("histogram V2 deflate serialization failed: {e}"))
+            })?;
+        serializer.serialize_bytes(&buf)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Timing, D::Error> {
+        let buf = Vec::<u8>::deserialize(deserializer)?;
+        let mut hist: Timing = HistDeserializer::new()
+            .deserialize(&mut buf.as_slice())
+            .map_err(|e| de::Error::custom(format!("histogram V2 deserialization failed: {e}")))?;
+        hist.auto(true);
+        Ok(hist)
+    }
+}
+
+/// Deserialized form of [`BenchOut`], from which the histogram is made auto-resizable only with
+/// [`HistOverflow::Resize`], as in [`BenchOut::new`].
+#[derive(serde::Deserialize)]
+pub(crate) struct BenchOutRepr {
+    recording_unit: LatencyUnit,
+    #[serde(with = "timing_v2")]
+    hist: Timing,
+    #[serde(default)]
+    hist_overflow: HistOverflow,
+    sum: f64,
+    sum2: f64,
+    n_nz: u64,
+    sum_ln: f64,
+    sum2_ln: f64,
+    #[serde(default)]
+    run_info: RunInfo,
+    #[serde(default)]
+    samples: Option<Samples>,
+}
+
+impl From<BenchOutRepr> for BenchOut {
+    fn from(repr: BenchOutRepr) -> Self {
+        let mut hist = repr.hist;
+        hist.auto(repr.hist_overflow == HistOverflow::Resize);
+        Self {
+            recording_unit: repr.recording_unit,
+            hist,
+            hist_overflow: repr.hist_overflow,
+            sum: repr.sum,
+            sum2: repr.sum2,
+            n_nz: repr.n_nz,
+            sum_ln: repr.sum_ln,
+            sum2_ln: repr.sum2_ln,
+            run_info: repr.run_info,
+            samples: repr.samples,
+        }
+    }
+}
+
+/// (De)serializes an optional [`Timing`](crate::Timing) histogram using [`timing_v2`].
+pub(crate) mod timing_v2_opt {
+    use crate::Timing;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    struct Wrap<'a>(&'a Timing);
+
+    impl Serialize for Wrap<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            super::timing_v2::serialize(self.0, serializer)
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct WrapOwned(#[serde(with = "super::timing_v2")] Timing);
+
+    pub(crate) fn serialize<S: Serializer>(
+        hist: &Option<Timing>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match hist {
+            Some(hist) => serializer.serialize_some(&Wrap(hist)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Timing>, D::Error> {
+        let wrapped = Option::<WrapOwned>::deserialize(deserializer)?;
+        Ok(wrapped.map(|w| w.0))
+    }
+}
+
+/// (De)serializes a `[T; K]` array, for arbitrary `K`, as a sequence of length `K`.
+///
+/// Serde's built-in array support doesn't cover const-generic lengths.
+pub(crate) mod array {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de, ser::SerializeSeq};
+
+    pub(crate) fn serialize<S, T, const K: usize>(
+        arr: &[T; K],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        let mut seq = serializer.serialize_seq(Some(K))?;
+        for item in arr {
+            seq.serialize_element(item)?;
+        }
+        seq.end()
+    }
+
+    pub(crate) fn deserialize<'de, D, T, const K: usize>(
+        deserializer: D,
+    ) -> Result<[T; K], D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        let vec = Vec::<T>::deserialize(deserializer)?;
+        let len = vec.len();
+        vec.try_into().map_err(|_| {
+            de::Error::invalid_length(len, &format!("an array of length {K}").as_str())
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- serde_support::test --nocapture
+mod test {
+    use crate::{
+        BenchCfg, BenchOut, Comp, FpSeconds, HistOverflow, LatencyUnit, SummaryStats, multi,
+        test_support::{HI_STDEV_LN, LO_STDEV_LN, lognormal_out, lognormal_samp},
+    };
+    use basic_stats::core::AltHyp;
+
+    const ALPHA: f64 = 0.05;
+
+    fn roundtrip<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+        let json = serde_json::to_string(value).expect("serialization failed");
+        serde_json::from_str(&json).expect("deserialization failed")
+    }
+
+    #[test]
+    fn test_bench_out_roundtrip() {
+        let cfg = BenchCfg::default().with_recording_unit(LatencyUnit::PICO);
+        let mu = FpSeconds::from_micros(10).ln();
+        let out = lognormal_out(&cfg, mu, *HI_STDEV_LN, 5_000);
+        let out_rt = roundtrip(&out);
+
+        assert_eq!(out.recording_unit(), out_rt.recording_unit());
+        assert_eq!(out.hist, out_rt.hist);
+        assert_eq!(out.hist.sigfig(), out_rt.hist.sigfig());
+        assert!(out_rt.hist.is_auto_resize());
+        assert_eq!(out.summary(), out_rt.summary());
+        assert_eq!(
+            out.iter_with_counts().collect::<Vec<_>>(),
+            out_rt.iter_with_counts().collect::<Vec<_>>()
+        );
+
+        let ln_mu0 = out.mean_ln() + 0.01;
+        assert_eq!(out.n_nz(), out_rt.n_nz());
+        assert_eq!(out.mean_ln(), out_rt.mean_ln());
+        assert_eq!(out.stdev_ln(), out_rt.stdev_ln());
+        assert_eq!(out.student_ln_t(ln_mu0), out_rt.student_ln_t(ln_mu0));
+        assert_eq!(out.student_ln_df(), out_rt.student_ln_df());
+        assert_eq!(
+            out.student_ln_p(ln_mu0, AltHyp::Ne),
+            out_rt.student_ln_p(ln_mu0, AltHyp::Ne)
+        );
+        assert_eq!(out.student_ln_ci(ALPHA), out_rt.student_ln_ci(ALPHA));
+        assert_eq!(
+            out.student_median_ci(ALPHA),
+            out_rt.student_median_ci(ALPHA)
+        );
+    }
+
+    #[test]
+    fn test_bench_out_roundtrip_hist_overflow() {
+        let cfg = BenchCfg::default()
+            .with_hist_bounds(FpSeconds::from_micros(1), FpSeconds::from_millis(10))
+            .with_hist_overflow(HistOverflow::Reject);
+        let mut out_rt = roundtrip(&BenchOut::from_iter(
+            &cfg,
+            lognormal_samp(FpSeconds::from_micros(10).ln(), *LO_STDEV_LN, 100),
+        ));
+
+        assert_eq!(HistOverflow::Reject, out_rt.hist_overflow());
+        assert!(!out_rt.hist.is_auto_resize());
+        assert!(!out_rt.capture_data((FpSeconds::from_millis(1_000), 1)));
+        assert_eq!(100, out_rt.n());
+    }
+
+    #[test]
+    fn test_comp_stats_identical_after_roundtrip() {
+        let cfg = BenchCfg::default();
+        let out1 = lognormal_out(&cfg, 8., *LO_STDEV_LN, 2_000);
+        let out2 = lognormal_out(&cfg, 8.01, *HI_STDEV_LN, 3_000);
+        let out1_rt = roundtrip(&out1);
+        let out2_rt = roundtrip(&out2);
+
+        let comp = Comp::new(&out1, &out2);
+        let comp_rt = Comp::new(&out1_rt, &out2_rt);
+
+        assert_eq!(comp.welch_ln_t(0.), comp_rt.welch_ln_t(0.));
+        assert_eq!(comp.welch_ln_df(), comp_rt.welch_ln_df());
+        assert_eq!(
+            comp.welch_ln_p(0., AltHyp::Lt),
+            comp_rt.welch_ln_p(0., AltHyp::Lt)
+        );
+        assert_eq!(comp.welch_ln_ci(ALPHA), comp_rt.welch_ln_ci(ALPHA));
+        assert_eq!(comp.welch_ratio_ci(ALPHA), comp_rt.welch_ratio_ci(ALPHA));
+    }
+
+    #[test]
+    fn test_multi_bench_out_roundtrip() {
+        let cfg = BenchCfg::default();
+        let out = multi::BenchOut::<3>::from_iter(
+            &cfg,
+            lognormal_samp(-9., *LO_STDEV_LN, 1_000).map(|x| [x, x * 2., x * 3.]),
+        );
+        let out_rt = roundtrip(&out);
+
+        for k in 0..out.arity() {
+            assert_eq!(out[k].hist, out_rt[k].hist);
+            assert_eq!(out[k].summary(), out_rt[k].summary());
+        }
+        assert_eq!(out.mean_lns(), out_rt.mean_lns());
+        assert_eq!(out.stdev_lns(), out_rt.stdev_lns());
+
+        let json = serde_json::to_string(&out).unwrap();
+        let wrong_arity = serde_json::from_str::<multi::BenchOut<2>>(&json);
+        assert!(wrong_arity.is_err(), "expected error on arity mismatch");
+    }
+
+    #[test]
+    fn test_duo_out_paired_roundtrip() {
+        let cfg = BenchCfg::default();
+        let out = multi::BenchOut::<2>::from_iter(
+            &cfg,
+            lognormal_samp(-9., *LO_STDEV_LN, 1_000)
+                .zip(lognormal_samp(-9., *HI_STDEV_LN, 1_000))
+                .map(|(x, y)| [x, y]),
+        );
+        let out_rt = roundtrip(&out);
+
+        assert_eq!(out.paired_n(), out_rt.paired_n());
+        assert_eq!(out.paired_mean_diff_ln(), out_rt.paired_mean_diff_ln());
+        assert_eq!(out.paired_stdev_diff_ln(), out_rt.paired_stdev_diff_ln());
+        assert_eq!(
+            out.wilcoxon_signed_rank_w(),
+            out_rt.wilcoxon_signed_rank_w()
+        );
+    }
+
+    #[test]
+    fn test_small_types_roundtrip() {
+        let cfg = BenchCfg::default();
+        let out: BenchOut = lognormal_out(&cfg, -9., *LO_STDEV_LN, 100);
+        let summary: SummaryStats = out.summary();
+
+        assert_eq!(summary, roundtrip(&summary));
+        assert_eq!(FpSeconds(1.25e-7), roundtrip(&FpSeconds(1.25e-7)));
+        assert_eq!(LatencyUnit::MICRO, roundtrip(&LatencyUnit::MICRO));
+    }
+
+    #[test]
+    fn test_latency_unit_validated() {
+        assert_eq!("9", serde_json::to_string(&LatencyUnit::NANO).unwrap());
+        for unit in [
+            LatencyUnit::SEC,
+            LatencyUnit::MILLI,
+            LatencyUnit::MICRO,
+            LatencyUnit::NANO,
+            LatencyUnit::PICO,
+        ] {
+            assert_eq!(unit, roundtrip(&unit));
+        }
+        for json in ["1", "7", "13", "255"] {
+            let result = serde_json::from_str::<LatencyUnit>(json);
+            assert!(result.is_err(), "expected error for exponent {json}");
+        }
+    }
+}
@@ -0,0 +1,493 @@
+//! Statistical helpers not provided by `basic_stats`.
+//!
+//! `basic_stats` provides tests and confidence intervals computed from sample moments, with the degrees of freedom
+//! implied by the sample size, but not the distributions behind them. This crate also needs Student t p-values and
+//! quantiles for other degrees of freedom (effective sample sizes, trimmed samples, and sample-size planning), the F
+//! and chi-squared distributions of the k-way tests, the binomial distribution of the bootstrap, and the Kolmogorov
+//! distribution of the change-point test. Rather than make `statrs` a regular dependency for these, this module
+//! implements the few special functions they rest on. The tests check them against `statrs`, a dev-dependency,
+//! including in the tails and for extreme degrees of freedom.
+
+use basic_stats::core::AltHyp;
+use std::f64::consts::PI;
+
+/// Maximum number of iterations of the bisection below, and minimum number of iterations allowed for the series and
+/// continued fractions (see [`max_iter`]).
+const MAX_ITER: usize = 500;
+/// Relative convergence threshold of the series and continued fractions below.
+const EPS: f64 = 1e-15;
+/// Smallest magnitude allowed for the intermediate terms of the modified Lentz algorithm.
+const FPMIN: f64 = 1e-300;
+
+/// Maximum number of iterations of the series and continued fractions below for a shape parameter `a`.
+///
+/// The number of terms they need to converge grows with the square root of `a`, e.g., about `8 * sqrt(a)` for the
+/// series of the incomplete gamma function near its mean.
+fn max_iter(a: f64) -> usize {
+    MAX_ITER.max((16. * a.sqrt()) as usize)
+}
+
+/// Natural logarithm of the gamma function, for `x > 0`, using the Lanczos approximation (`g = 7`, `n = 9`).
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    const G: f64 = 7.;
+    const COEFS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // Reflection formula.
+        return (PI / (PI * x).sin()).ln() - ln_gamma(1. - x);
+    }
+    let x = x - 1.;
+    let a = COEFS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFS[0], |acc, (i, c)| acc + c / (x + (i + 1) as f64));
+    let t = x + G + 0.5;
+    0.5 * (2. * PI).ln() + (x + 0.5) * t.ln() - t + a.ln()
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`, for `a > 0` and `b > 0`.
+pub(crate) fn beta_reg(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0. {
+        return 0.;
+    }
+    if x >= 1. {
+        return 1.;
+    }
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1. - x).ln();
+    // The continued fraction converges fastest on this side of the mean of the distribution.
+    if x < (a + 1.) / (a + b + 2.) {
+        ln_front.exp() * beta_cf(a, b, x) / a
+    } else {
+        1. - ln_front.exp() * beta_cf(b, a, 1. - x) / b
+    }
+}
+
+/// Continued fraction of the incomplete beta function, evaluated with the modified Lentz algorithm.
+fn beta_cf(a: f64, b: f64, x: f64) -> f64 {
+    fn nz(v: f64) -> f64 {
+        if v.abs() < FPMIN { FPMIN } else { v }
+    }
+
+    let (qab, qap, qam) = (a + b, a + 1., a - 1.);
+    let mut c = 1.;
+    let mut d = 1. / nz(1. - qab * x / qap);
+    let mut h = d;
+    for m in 1..=max_iter(a.max(b)) {
+        let m = m as f64;
+        let m2 = 2. * m;
+
+        let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+        d = 1. / nz(1. + aa * d);
+        c = nz(1. + aa / c);
+        h *= d * c;
+
+        let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+        d = 1. / nz(1. + aa * d);
+        c = nz(1. + aa / c);
+        let del = d * c;
+        h *= del;
+
+        if (del - 1.).abs() < EPS {
+            break;
+        }
+    }
+    h
+}
+
+/// Regularized upper incomplete gamma function `Q(a, x)`, for `a > 0`.
+pub(crate) fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0. {
+        return 1.;
+    }
+    let ln_front = -x + a * x.ln() - ln_gamma(a);
+    if x < a + 1. {
+        // Series for the lower incomplete gamma function.
+        let mut ap = a;
+        let mut del = 1. / a;
+        let mut sum = del;
+        for _ in 0..max_iter(a) {
+            ap += 1.;
+            del *= x / ap;
+            sum += del;
+            if del.abs() < sum.abs() * EPS {
+                break;
+            }
+        }
+        1. - sum * ln_front.exp()
+    } else {
+        // Continued fraction for the upper incomplete gamma function, with the modified Lentz algorithm.
+        let mut b = x + 1. - a;
+        let mut c = 1. / FPMIN;
+        let mut d = 1. / b;
+        let mut h = d;
+        for i in 1..=max_iter(a) {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.;
+            d = an * d + b;
+            if d.abs() < FPMIN {
+                d = FPMIN;
+            }
+            c = b + an / c;
+            if c.abs() < FPMIN {
+                c = FPMIN;
+            }
+            d = 1. / d;
+            let del = d * c;
+            h *= del;
+            if (del - 1.).abs() < EPS {
+                break;
+            }
+        }
+        ln_front.exp() * h
+    }
+}
+
+/// Cumulative distribution function of the standard normal distribution.
+fn normal_cdf(z: f64) -> f64 {
+    // `erfc(x) == Q(1/2, x^2)` for `x >= 0`.
+    let tail = 0.5 * gamma_q(0.5, z * z / 2.);
+    if z < 0. { tail } else { 1. - tail }
+}
+
+/// Cumulative distribution function of the Student t distribution with `df` degrees of freedom.
+fn students_t_cdf(t: f64, df: f64) -> f64 {
+    let tail = 0.5 * beta_reg(df / 2., 0.5, df / (df + t * t));
+    if t < 0. { tail } else { 1. - tail }
+}
+
+/// p-value for a standard normal test statistic `z` under alternative hypothesis `alt_hyp`.
+pub(crate) fn z_to_p(z: f64, alt_hyp: AltHyp) -> f64 {
+    match alt_hyp {
+        AltHyp::Lt => normal_cdf(z),
+        AltHyp::Gt => normal_cdf(-z),
+        AltHyp::Ne => 2. * normal_cdf(-z.abs()),
+    }
+}
+
+/// p-value for a Student t test statistic `t` with `df` degrees of freedom under alternative hypothesis `alt_hyp`.
+///
+/// # Panics
+///
+/// Panics if `df` is not positive.
+pub(crate) fn t_to_p(t: f64, df: f64, alt_hyp: AltHyp) -> f64 {
+    assert!(df > 0., "degrees of freedom must be positive");
+    match alt_hyp {
+        AltHyp::Lt => students_t_cdf(t, df),
+        AltHyp::Gt => students_t_cdf(-t, df),
+        AltHyp::Ne => 2. * students_t_cdf(-t.abs(), df),
+    }
+}
+
+/// Quantile `q` of the standard normal distribution.
+///
+/// Uses Acklam's rational approximation, refined with one step of Halley's method. Returns `NaN` if `q` is not in
+/// `[0, 1]`.
+pub(crate) fn z_quantile(q: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const Q_LOW: f64 = 0.02425;
+
+    fn poly(coefs: &[f64], x: f64) -> f64 {
+        coefs.iter().fold(0., |acc, c| acc * x + c)
+    }
+
+    if !(0. ..=1.).contains(&q) {
+        return f64::NAN;
+    }
+    if q == 0. {
+        return f64::NEG_INFINITY;
+    }
+    if q == 1. {
+        return f64::INFINITY;
+    }
+
+    let tail = |q: f64| {
+        let r = (-2. * q.ln()).sqrt();
+        poly(&C, r) / (poly(&D, r) * r + 1.)
+    };
+    let x = if q < Q_LOW {
+        tail(q)
+    } else if q <= 1. - Q_LOW {
+        let r = q - 0.5;
+        let r2 = r * r;
+        poly(&A, r2) * r / (poly(&B, r2) * r2 + 1.)
+    } else {
+        -tail(1. - q)
+    };
+
+    let e = normal_cdf(x) - q;
+    let u = e * (2. * PI).sqrt() * (x * x / 2.).exp();
+    x - u / (1. + x * u / 2.)
+}
+
+/// Quantile `q` of the Student t distribution with `df` degrees of freedom, found by bisection. Returns `NaN` if `q`
+/// is not in `[0, 1]`.
+///
+/// # Panics
+///
+/// Panics if `df` is not positive.
+pub(crate) fn t_quantile(q: f64, df: f64) -> f64 {
+    assert!(df > 0., "degrees of freedom must be positive");
+    if !(0. ..=1.).contains(&q) {
+        return f64::NAN;
+    }
+    if q == 0. {
+        return f64::NEG_INFINITY;
+    }
+    if q == 1. {
+        return f64::INFINITY;
+    }
+
+    // The t distribution has heavier tails than the normal, so its quantile is further from zero. The bracket's inner
+    // end is zero rather than the normal quantile, as rounding errors of the CDF at the latter, for large `df`, could
+    // put it on the wrong side of the t quantile.
+    let z = z_quantile(q);
+    let (mut lo, mut hi) = if z < 0. {
+        (2. * z - 1., 0.)
+    } else {
+        (0., 2. * z + 1.)
+    };
+    while students_t_cdf(lo, df) > q {
+        lo *= 2.;
+    }
+    while students_t_cdf(hi, df) < q {
+        hi *= 2.;
+    }
+    for _ in 0..MAX_ITER {
+        let mid = (lo + hi) / 2.;
+        if hi - lo <= EPS * mid.abs().max(1.) {
+            break;
+        }
+        if students_t_cdf(mid, df) < q {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.
+}
+
+/// Upper-tail p-value for an F test statistic `f` with `df1` and `df2` degrees of freedom.
+///
+/// # Panics
+///
+/// Panics if `df1` or `df2` is not positive.
+pub(crate) fn f_to_p(f: f64, df1: f64, df2: f64) -> f64 {
+    assert!(df1 > 0. && df2 > 0., "degrees of freedom must be positive");
+    if f <= 0. {
+        return 1.;
+    }
+    beta_reg(df2 / 2., df1 / 2., df2 / (df2 + df1 * f))
+}
+
+/// Upper-tail p-value for a chi-squared test statistic `x` with `df` degrees of freedom.
+///
+/// # Panics
+///
+/// Panics if `df` is not positive.
+pub(crate) fn chi2_to_p(x: f64, df: f64) -> f64 {
+    assert!(df > 0., "degrees of freedom must be positive");
+    gamma_q(df / 2., x / 2.)
+}
+
+/// Survival function of the Kolmogorov distribution, i.e., the asymptotic distribution of the supremum of the
+/// absolute value of a Brownian bridge.
+pub(crate) fn kolmogorov_sf(d: f64) -> f64 {
+    // The series converges slowly for small `d`, where the survival function is indistinguishable from 1.
+    if d < 0.2 {
+        return 1.;
+    }
+    let sum = (1..=100)
+        .map(|j| {
+            let sign = if j % 2 == 1 { 1. } else { -1. };
+            sign * (-2. * (j as f64 * d).powi(2)).exp()
+        })
+        .sum::<f64>();
+    (2. * sum).clamp(0., 1.)
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+mod test {
+    use super::*;
+    use basic_stats::{approx_eq, rel_approx_eq};
+    use statrs::distribution::{
+        Beta, ChiSquared, ContinuousCDF, FisherSnedecor, Normal, StudentsT,
+    };
+
+    #[test]
+    fn test_special_functions() {
+        const EPSILON: f64 = 1e-12;
+        approx_eq!(24_f64.ln(), ln_gamma(5.), EPSILON);
+        approx_eq!(PI.sqrt().ln(), ln_gamma(0.5), EPSILON);
+        approx_eq!(0.6875, beta_reg(2., 3., 0.5), EPSILON);
+        approx_eq!(0.25_f64.sqrt(), beta_reg(0.5, 1., 0.25), EPSILON);
+        approx_eq!(1. - beta_reg(3., 2., 0.5), beta_reg(2., 3., 0.5), EPSILON);
+        approx_eq!((-2.5_f64).exp(), gamma_q(1., 2.5), EPSILON);
+        approx_eq!((-0.3_f64).exp() * 1.3, gamma_q(2., 0.3), EPSILON);
+        approx_eq!(1., gamma_q(3., 0.), EPSILON);
+    }
+
+    #[test]
+    fn test_z_to_p() {
+        const EPSILON: f64 = 1e-4;
+        approx_eq!(0.5, z_to_p(0., AltHyp::Lt), EPSILON);
+        approx_eq!(0.5, z_to_p(0., AltHyp::Gt), EPSILON);
+        approx_eq!(1., z_to_p(0., AltHyp::Ne), EPSILON);
+        approx_eq!(0.025, z_to_p(1.959964, AltHyp::Gt), EPSILON);
+        approx_eq!(0.025, z_to_p(-1.959964, AltHyp::Lt), EPSILON);
+        approx_eq!(0.05, z_to_p(-1.959964, AltHyp::Ne), EPSILON);
+    }
+
+    #[test]
+    fn test_t_to_p() {
+        const EPSILON: f64 = 1e-4;
+        approx_eq!(0.5, t_to_p(0., 10., AltHyp::Lt), EPSILON);
+        approx_eq!(0.025, t_to_p(2.228139, 10., AltHyp::Gt), EPSILON);
+        approx_eq!(0.05, t_to_p(-2.228139, 10., AltHyp::Ne), EPSILON);
+    }
+
+    #[test]
+    fn test_z_quantile() {
+        const EPSILON: f64 = 1e-4;
+        approx_eq!(0., z_quantile(0.5), EPSILON);
+        approx_eq!(1.959964, z_quantile(0.975), EPSILON);
+        approx_eq!(0.841621, z_quantile(0.8), EPSILON);
+        approx_eq!(-3.719016, z_quantile(1e-4), EPSILON);
+        approx_eq!(3.719016, z_quantile(1. - 1e-4), EPSILON);
+    }
+
+    #[test]
+    fn test_t_quantile() {
+        const EPSILON: f64 = 1e-4;
+        approx_eq!(0., t_quantile(0.5, 10.), EPSILON);
+        approx_eq!(2.228139, t_quantile(0.975, 10.), EPSILON);
+        approx_eq!(-2.228139, t_quantile(0.025, 10.), EPSILON);
+        approx_eq!(63.656741, t_quantile(0.995, 1.), EPSILON);
+        approx_eq!(1.959964, t_quantile(0.975, 1e6), EPSILON);
+        assert!(t_quantile(1.5, 10.).is_nan());
+    }
+
+    #[test]
+    fn test_f_to_p() {
+        const EPSILON: f64 = 1e-4;
+        approx_eq!(0.05, f_to_p(3.885294, 2., 12.), EPSILON);
+        approx_eq!(0.01, f_to_p(6.926608, 2., 12.), EPSILON);
+    }
+
+    #[test]
+    fn test_chi2_to_p() {
+        const EPSILON: f64 = 1e-4;
+        approx_eq!(0.05, chi2_to_p(5.991465, 2.), EPSILON);
+        approx_eq!(0.05, chi2_to_p(3.841459, 1.), EPSILON);
+    }
+
+    #[test]
+    fn test_kolmogorov_sf() {
+        const EPSILON: f64 = 1e-4;
+        approx_eq!(1., kolmogorov_sf(0.), EPSILON);
+        approx_eq!(0.05, kolmogorov_sf(1.358099), EPSILON);
+        approx_eq!(0.01, kolmogorov_sf(1.627624), EPSILON);
+    }
+
+    #[test]
+    fn test_t_quantile_vs_statrs() {
+        // The Student t distribution with 1 degree of freedom is the Cauchy distribution, with closed-form quantiles.
+        let cauchy_quantile = |q: f64| (PI * (q - 0.5)).tan();
+        rel_approx_eq!(cauchy_quantile(1e-6), t_quantile(1e-6, 1.), 1e-9);
+        rel_approx_eq!(cauchy_quantile(1. - 1e-6), t_quantile(1. - 1e-6, 1.), 1e-9);
+
+        for df in [1., 2., 5., 30., 1e3, 1e6] {
+            let dist = StudentsT::new(0., 1., df).unwrap();
+            for q in [1e-6, 0.025, 0.975, 1. - 1e-6] {
+                rel_approx_eq!(q, dist.cdf(t_quantile(q, df)), 1e-6);
+            }
+        }
+
+        // Very large degrees of freedom, where the quantile is that of the normal distribution.
+        let normal = Normal::new(0., 1.).unwrap();
+        approx_eq!(normal.inverse_cdf(0.975), t_quantile(0.975, 1e8), 1e-6);
+    }
+
+    #[test]
+    fn test_beta_reg_vs_statrs() {
+        for (a, b) in [(0.5, 0.5), (2., 3.), (5., 40.), (40., 5.)] {
+            let dist = Beta::new(a, b).unwrap();
+            // Near 0, where the value is tiny, as long as it doesn't underflow.
+            for x in [1e-10, 1e-4, 1e-2] {
+                if a * x.log10() > -300. {
+                    rel_approx_eq!(dist.cdf(x), beta_reg(a, b, x), 1e-9);
+                }
+            }
+            // Near 1, where the value is close to 1.
+            for x in [1. - 1e-2, 1. - 1e-4, 1. - 1e-10] {
+                approx_eq!(dist.cdf(x), beta_reg(a, b, x), 1e-12);
+            }
+        }
+
+        // Large parameters, as for the binomial probabilities of the bootstrap.
+        let dist = Beta::new(2_000., 3_000.).unwrap();
+        rel_approx_eq!(dist.cdf(0.4), beta_reg(2_000., 3_000., 0.4), 1e-9);
+        rel_approx_eq!(dist.cdf(0.37), beta_reg(2_000., 3_000., 0.37), 1e-6);
+    }
+
+    #[test]
+    fn test_chi2_to_p_vs_statrs() {
+        for df in [1., 10., 1e3, 1e4, 1e5, 1e6] {
+            let dist = ChiSquared::new(df).unwrap();
+            let sd = (2. * df).sqrt();
+            for x in [df - 3. * sd, df, df + 3. * sd] {
+                if x > 0. {
+                    rel_approx_eq!(dist.sf(x), chi2_to_p(x, df), 1e-6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_f_to_p_vs_statrs() {
+        for (df1, df2) in [(1., 1.), (2., 12.), (3., 1e6), (1e4, 1e4)] {
+            let dist = FisherSnedecor::new(df1, df2).unwrap();
+            for f in [0.5, 1., 3.] {
+                rel_approx_eq!(dist.sf(f), f_to_p(f, df1, df2), 1e-6);
+            }
+        }
+    }
+}
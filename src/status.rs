@@ -1,7 +1,14 @@
 //! Trait and types that support default and custom progress status reporting for benchmarks.
 
+use crate::{BenchCfg, BenchOut, RunLength};
 use std::{io::Write, time::Duration};
 
+#[cfg(feature = "serde")]
+mod json;
+
+#[cfg(feature = "serde")]
+pub use json::*;
+
 /// Provides optional status reporting closures for warm-up and execution phases.
 ///
 /// Implementors return either `Some(closure)` to report progress or `None` to skip reporting.
@@ -48,6 +55,44 @@ pub trait Status<'a> {
     where
         'a: 'b;
 
+    /// Returns an optional status closure for the warm-up phase that receives a [`Progress`] snapshot, including
+    /// the latency data collected so far.
+    ///
+    /// This is the closure invoked by the benchmarking functions. The default implementation forwards to the closure
+    /// returned by [`Self::warmup_status`].
+    fn warmup_progress<'b>(&'b mut self) -> Option<impl FnMut(&Progress<'_>) + 'b>
+    where
+        'a: 'b,
+    {
+        self.warmup_status()
+            .map(|mut s| move |p: &Progress<'_>| s(p.est_time, p.est_count, p.i))
+    }
+
+    /// Returns an optional status closure for the execution phase that receives a [`Progress`] snapshot, including
+    /// the latency data collected so far.
+    ///
+    /// This is the closure invoked by the benchmarking functions. The default implementation forwards to the closure
+    /// returned by [`Self::exec_status`].
+    fn exec_progress<'b>(&'b mut self) -> Option<impl FnMut(&Progress<'_>) + 'b>
+    where
+        'a: 'b,
+    {
+        self.exec_status()
+            .map(|mut s| move |p: &Progress<'_>| s(p.est_time, p.est_count, p.i))
+    }
+
+    /// Invoked once at the start of a benchmark run, before the iteration rate is estimated.
+    /// The default implementation does nothing.
+    fn start_status(&mut self, _cfg: &BenchCfg, _run_length: RunLength) {}
+
+    /// Invoked once the iteration rate is estimated, with the estimate in iterations per second.
+    /// The default implementation does nothing.
+    fn execs_per_sec_status(&mut self, _execs_per_sec: f64) {}
+
+    /// Invoked once at the end of a benchmark run, with the outputs of the closures.
+    /// The default implementation does nothing.
+    fn end_status(&mut self, _outs: &[BenchOut]) {}
+
     /// Partially applies `(est_time, est_count, i)` to a status closure,
     /// yielding an `FnMut(usize)` closure
     ///
@@ -62,6 +107,50 @@ pub trait Status<'a> {
     }
 }
 
+/// Snapshot of a warm-up or execution phase in progress. See [`Status::warmup_progress`] and
+/// [`Status::exec_progress`].
+#[derive(Debug, Clone, Copy)]
+pub struct Progress<'p> {
+    /// Estimated duration of the phase; `Duration::MAX` for a [`RunLength::Precision`](crate::RunLength::Precision)
+    /// without caps.
+    pub est_time: Duration,
+    /// Estimated number of iterations of the phase; `usize::MAX` for a
+    /// [`RunLength::Precision`](crate::RunLength::Precision) without caps.
+    pub est_count: usize,
+    /// Current iteration.
+    pub i: usize,
+    /// Time elapsed since the start of the phase.
+    pub elapsed: Duration,
+    /// Latency data collected so far in the phase, one output per closure.
+    pub outs: &'p [BenchOut],
+}
+
+impl Progress<'_> {
+    /// Estimated fraction of the phase completed, in `[0, 1]`: the larger of the iteration fraction and the elapsed
+    /// time fraction, as the phase ends when either estimate is reached.
+    pub fn fraction(&self) -> f64 {
+        let count_fraction = self.i as f64 / self.est_count as f64;
+        let time_fraction = self.elapsed.as_secs_f64() / self.est_time.as_secs_f64();
+        let fraction = count_fraction.max(time_fraction);
+        if fraction.is_nan() {
+            0.
+        } else {
+            fraction.min(1.)
+        }
+    }
+
+    /// Estimated time remaining in the phase, extrapolated from [`Self::fraction`]; `None` if no progress was made.
+    pub fn eta(&self) -> Option<Duration> {
+        let fraction = self.fraction();
+        (fraction > 0.).then(|| self.elapsed.mul_f64((1. - fraction) / fraction))
+    }
+
+    /// Iterations per second since the start of the phase.
+    pub fn rate(&self) -> f64 {
+        self.i as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
 pub(crate) struct NoStatus;
 
 impl<'a> Status<'a> for NoStatus {
@@ -194,11 +283,175 @@ impl<'a, W: Write> Status<'a> for DefaultStatus<'a, W> {
     }
 }
 
+/// Implementation of [`Status`] that draws a progress bar with the elapsed time, the estimated time remaining, the
+/// iteration rate, and the running median latency of each closure.
+///
+/// If `tty` is `true`, the bar is redrawn in place on a single line, which requires a terminal; otherwise, one line
+/// is written per status report, which suits logs and pipes. For standard error, `tty` can be obtained with
+/// [`IsTerminal::is_terminal`](std::io::IsTerminal::is_terminal).
+pub struct ProgressStatus<'a, W: Write> {
+    /// Writer to which status output is sent.
+    pub w: &'a mut W,
+    /// Label of the warm-up progress.
+    pub warmup_preamble: String,
+    /// Label of the execution progress.
+    pub exec_preamble: String,
+    /// Whether `w` is a terminal, on which the bar is redrawn in place.
+    pub tty: bool,
+}
+
+impl<'a, W: Write> ProgressStatus<'a, W> {
+    /// Width of the progress bar, in characters.
+    pub const BAR_WIDTH: usize = 30;
+
+    /// Creates a new `ProgressStatus` that writes status messages to `w`.
+    ///
+    /// # Arguments
+    ///
+    /// - `w` - the writer (typically stderr or a `StringWriter` from `test_support` for testing).
+    /// - `warmup_preamble` - label of the warm-up progress (e.g. `"Warming up"`).
+    /// - `exec_preamble` - label of the execution progress (e.g. `"Executing bench_run"`).
+    /// - `tty` - whether `w` is a terminal.
+    pub fn new(w: &'a mut W, warmup_preamble: String, exec_preamble: String, tty: bool) -> Self {
+        Self {
+            w,
+            warmup_preamble,
+            exec_preamble,
+            tty,
+        }
+    }
+
+    fn format_progress(preamble: &str, p: &Progress<'_>, tty: bool) -> String {
+        let fraction = p.fraction();
+        let eta = match p.eta() {
+            Some(eta) => format!("{eta:.1?}"),
+            None => "?".to_owned(),
+        };
+        let medians = p
+            .outs
+            .iter()
+            .map(|b| match b.n() {
+                0 => "-".to_owned(),
+                _ => format!("{:?}", b.median()),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        if tty {
+            let filled = (fraction * Self::BAR_WIDTH as f64).round() as usize;
+            format!(
+                "{preamble} [{}{}] {:>3.0}% {:.1?} ETA {eta} | {:.0} it/s | median {medians}",
+                "#".repeat(filled),
+                "-".repeat(Self::BAR_WIDTH - filled),
+                fraction * 100.,
+                p.elapsed,
+                p.rate(),
+            )
+        } else {
+            format!(
+                "{preamble}: {:.0}% ({} of (approx.) {} executions), elapsed {:.1?}, ETA {eta}, {:.0} it/s, median {medians}",
+                fraction * 100.,
+                p.i,
+                p.est_count,
+                p.elapsed,
+                p.rate(),
+            )
+        }
+    }
+
+    fn make_progress<'b>(
+        w: &'b mut W,
+        preamble: String,
+        tty: bool,
+    ) -> impl FnMut(&Progress<'_>) + 'b
+    where
+        'a: 'b,
+    {
+        move |p: &Progress<'_>| {
+            let line = Self::format_progress(&preamble, p, tty);
+            if tty {
+                // Returns to the start of the line and clears the previous status.
+                write!(w, "\r{line}\x1b[K")
+            } else {
+                writeln!(w, "{line}")
+            }
+            .expect("unexpected error writing to `Write` object `w`");
+            w.flush().expect("unexpected I/O error");
+        }
+    }
+
+    fn make_end_status<'b>(w: &'b mut W, tty: bool) -> impl FnOnce() + 'b
+    where
+        'a: 'b,
+    {
+        move || {
+            if tty {
+                writeln!(w).expect("unexpected error writing to `Write` object `w`");
+                w.flush().expect("unexpected I/O error");
+            }
+        }
+    }
+}
+
+impl<'a, W: Write> Status<'a> for ProgressStatus<'a, W> {
+    // Superseded by `warmup_progress`.
+    fn warmup_status<'b>(&'b mut self) -> Option<impl FnMut(Duration, usize, usize) + 'b>
+    where
+        'a: 'b,
+    {
+        None::<fn(Duration, usize, usize)>
+    }
+
+    fn end_warmup_status<'b>(&'b mut self) -> Option<impl FnOnce() + 'b>
+    where
+        'a: 'b,
+    {
+        Some(Self::make_end_status(self.w, self.tty))
+    }
+
+    // Superseded by `exec_progress`.
+    fn exec_status<'b>(&'b mut self) -> Option<impl FnMut(Duration, usize, usize) + 'b>
+    where
+        'a: 'b,
+    {
+        None::<fn(Duration, usize, usize)>
+    }
+
+    fn end_exec_status<'b>(&'b mut self) -> Option<impl FnOnce() + 'b>
+    where
+        'a: 'b,
+    {
+        Some(Self::make_end_status(self.w, self.tty))
+    }
+
+    fn warmup_progress<'b>(&'b mut self) -> Option<impl FnMut(&Progress<'_>) + 'b>
+    where
+        'a: 'b,
+    {
+        Some(Self::make_progress(
+            self.w,
+            self.warmup_preamble.clone(),
+            self.tty,
+        ))
+    }
+
+    fn exec_progress<'b>(&'b mut self) -> Option<impl FnMut(&Progress<'_>) + 'b>
+    where
+        'a: 'b,
+    {
+        Some(Self::make_progress(
+            self.w,
+            self.exec_preamble.clone(),
+            self.tty,
+        ))
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "_test")]
 mod test {
     use super::*;
-    use crate::test_support::StringWriter;
+    use crate::{BenchCfg, FpSeconds, RunLength, test_support::StringWriter};
     use std::time::Duration;
 
     #[test]
@@ -234,6 +487,130 @@ mod test {
         assert_eq!(captured, (Duration::from_secs(1), 100, 42));
     }
 
+    fn progress_outs() -> Vec<BenchOut> {
+        let cfg = BenchCfg::default();
+        vec![
+            BenchOut::from_iter(&cfg, [1e-6, 2e-6, 3e-6].into_iter().map(FpSeconds)),
+            BenchOut::new(&cfg),
+        ]
+    }
+
+    #[test]
+    fn test_progress() {
+        let outs = progress_outs();
+        let p = Progress {
+            est_time: Duration::from_secs(4),
+            est_count: 1000,
+            i: 250,
+            elapsed: Duration::from_secs(2),
+            outs: &outs,
+        };
+        // The time fraction is ahead of the count fraction.
+        assert_eq!(0.5, p.fraction());
+        assert_eq!(Some(Duration::from_secs(2)), p.eta());
+        assert_eq!(125., p.rate());
+
+        let p = Progress {
+            i: 0,
+            elapsed: Duration::ZERO,
+            ..p
+        };
+        assert_eq!(0., p.fraction());
+        assert_eq!(None, p.eta());
+
+        let p = Progress {
+            i: 2000,
+            elapsed: Duration::from_secs(3),
+            ..p
+        };
+        assert_eq!(1., p.fraction());
+        assert_eq!(Some(Duration::ZERO), p.eta());
+    }
+
+    #[test]
+    fn test_progress_status_output() {
+        let outs = progress_outs();
+        let p = Progress {
+            est_time: Duration::from_secs(4),
+            est_count: 1000,
+            i: 500,
+            elapsed: Duration::from_secs(1),
+            outs: &outs,
+        };
+
+        let mut w = StringWriter::new();
+        {
+            let mut ps = ProgressStatus::new(&mut w, "Warm".to_owned(), "Exec".to_owned(), true);
+            let mut exec_fn = ps.exec_progress().unwrap();
+            exec_fn(&p);
+            exec_fn(&p);
+        }
+        let output = w.as_str().unwrap();
+        println!("{output:?}");
+        assert_eq!(2, output.matches('\r').count());
+        assert!(!output.contains('\n'));
+        let bar = format!("[{}{}]", "#".repeat(15), "-".repeat(15));
+        assert!(output.contains(&format!("Exec {bar}  50% 1.0s ETA 1.0s")));
+        assert!(output.contains("500 it/s"));
+        assert!(output.contains("median 2.000μs, -"));
+
+        let mut w = StringWriter::new();
+        {
+            let mut ps = ProgressStatus::new(&mut w, "Warm".to_owned(), "Exec".to_owned(), false);
+            let mut warmup_fn = ps.warmup_progress().unwrap();
+            warmup_fn(&p);
+            warmup_fn(&p);
+            drop(warmup_fn);
+            ps.end_warmup_status().unwrap()();
+        }
+        let output = w.as_str().unwrap();
+        println!("{output:?}");
+        assert_eq!(2, output.lines().count());
+        assert!(!output.contains('\r'));
+        assert!(output.starts_with("Warm: 50% (500 of (approx.) 1000 executions)"));
+        assert!(output.contains("median 2.000μs, -"));
+    }
+
+    #[test]
+    fn test_progress_defaults_to_status() {
+        let mut w = StringWriter::new();
+        {
+            let mut ds = DefaultStatus::new(&mut w, "Warm".to_owned(), "Exec".to_owned());
+            let mut exec_fn = ds.exec_progress().unwrap();
+            exec_fn(&Progress {
+                est_time: Duration::from_millis(5000),
+                est_count: 10000,
+                i: 6000,
+                elapsed: Duration::ZERO,
+                outs: &[],
+            });
+        }
+        let output = w.as_str().unwrap();
+        assert!(
+            output.contains("Exec for (approx.) 5000 millis: 6000 of (approx.) 10000 executions.")
+        );
+        assert!(NoStatus.exec_progress().is_none());
+    }
+
+    #[test]
+    fn test_progress_status_bench_run() {
+        let cfg = BenchCfg::default()
+            .with_warmup_millis(0)
+            .with_status_millis(1);
+        let mut w = StringWriter::new();
+        let ps = ProgressStatus::new(&mut w, "Warm".to_owned(), "Exec".to_owned(), false);
+        let f = || std::thread::sleep(Duration::from_micros(200));
+        let out = crate::bench_run_x(&cfg, f, RunLength::Count(50), ps);
+        assert_eq!(50, out.n());
+
+        let output = w.as_str().unwrap();
+        let last = output.lines().last().expect("at least one status line");
+        println!("{output}");
+        assert!(last.starts_with("Exec: 100% (50 of"), "{last}");
+        assert!(last.contains(" it/s, median "));
+        assert!(!last.ends_with(" -"));
+    }
+
     #[test]
     fn test_default_status_new() {
         let mut w = StringWriter::new();
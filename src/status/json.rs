@@ -0,0 +1,295 @@
+//! JSON-lines event stream for the benchmark lifecycle.
+
+use super::{Progress, Status};
+use crate::{BenchCfg, BenchOut, FpSeconds, RunLength, SummaryStats};
+use serde::Serialize;
+use std::{
+    io::Write,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Implementation of [`Status`] that writes the lifecycle of a benchmark as JSON-lines events, one JSON object per
+/// line, for consumption by other tools.
+///
+/// Gated by feature **"serde"**.
+///
+/// Every event has the following fields:
+/// - `event`: one of `"run_start"`, `"execs_per_sec"`, `"warmup_progress"`, `"warmup_end"`, `"exec_progress"`,
+///   `"exec_end"`, or `"run_end"`.
+/// - `bench_id`: the benchmark id of the status object.
+/// - `timestamp`: wall-clock time of the event, in seconds since the Unix epoch.
+/// - `elapsed`: seconds since the `"run_start"` event.
+///
+/// Event-specific fields:
+/// - `"run_start"`: `cfg` ([`BenchCfg`]) and `run_length` ([`RunLength`]), as serialized by serde.
+/// - `"execs_per_sec"`: `execs_per_sec`, the estimated number of iterations per second.
+/// - `"warmup_progress"` and `"exec_progress"`: `i`, `est_count`, `est_time` (seconds), `phase_elapsed` (seconds),
+///   `fraction`, and `medians`, the running median latency in seconds of each closure (`null` if there are no
+///   observations yet).
+/// - `"run_end"`: `aborted` and `summaries`, the [`SummaryStats`] of each closure (`null` if there are no
+///   observations).
+pub struct JsonStatus<'a, W: Write> {
+    /// Writer to which events are sent.
+    pub w: &'a mut W,
+    /// Benchmark id included in each event.
+    pub bench_id: String,
+    start: Instant,
+}
+
+impl<'a, W: Write> JsonStatus<'a, W> {
+    /// Creates a new `JsonStatus` that writes events for benchmark `bench_id` to `w`.
+    pub fn new(w: &'a mut W, bench_id: String) -> Self {
+        Self {
+            w,
+            bench_id,
+            start: Instant::now(),
+        }
+    }
+
+    fn make_progress<'b>(
+        w: &'b mut W,
+        bench_id: &'b str,
+        start: Instant,
+        warmup: bool,
+    ) -> impl FnMut(&Progress<'_>) + 'b
+    where
+        'a: 'b,
+    {
+        move |p: &Progress<'_>| {
+            let progress = ProgressEvent {
+                i: p.i,
+                est_count: p.est_count,
+                est_time: p.est_time.as_secs_f64(),
+                phase_elapsed: p.elapsed.as_secs_f64(),
+                fraction: p.fraction(),
+                medians: p
+                    .outs
+                    .iter()
+                    .map(|b| (b.n() > 0).then(|| b.median()))
+                    .collect(),
+            };
+            let kind = if warmup {
+                EventKind::WarmupProgress(progress)
+            } else {
+                EventKind::ExecProgress(progress)
+            };
+            emit(w, bench_id, start, kind);
+        }
+    }
+}
+
+impl<'a, W: Write> Status<'a> for JsonStatus<'a, W> {
+    // Superseded by `warmup_progress`.
+    fn warmup_status<'b>(&'b mut self) -> Option<impl FnMut(Duration, usize, usize) + 'b>
+    where
+        'a: 'b,
+    {
+        None::<fn(Duration, usize, usize)>
+    }
+
+    fn end_warmup_status<'b>(&'b mut self) -> Option<impl FnOnce() + 'b>
+    where
+        'a: 'b,
+    {
+        let (w, bench_id, start) = (&mut *self.w, self.bench_id.as_str(), self.start);
+        Some(move || emit(w, bench_id, start, EventKind::WarmupEnd))
+    }
+
+    // Superseded by `exec_progress`.
+    fn exec_status<'b>(&'b mut self) -> Option<impl FnMut(Duration, usize, usize) + 'b>
+    where
+        'a: 'b,
+    {
+        None::<fn(Duration, usize, usize)>
+    }
+
+    fn end_exec_status<'b>(&'b mut self) -> Option<impl FnOnce() + 'b>
+    where
+        'a: 'b,
+    {
+        let (w, bench_id, start) = (&mut *self.w, self.bench_id.as_str(), self.start);
+        Some(move || emit(w, bench_id, start, EventKind::ExecEnd))
+    }
+
+    fn warmup_progress<'b>(&'b mut self) -> Option<impl FnMut(&Progress<'_>) + 'b>
+    where
+        'a: 'b,
+    {
+        Some(Self::make_progress(
+            self.w,
+            &self.bench_id,
+            self.start,
+            true,
+        ))
+    }
+
+    fn exec_progress<'b>(&'b mut self) -> Option<impl FnMut(&Progress<'_>) + 'b>
+    where
+        'a: 'b,
+    {
+        Some(Self::make_progress(
+            self.w,
+            &self.bench_id,
+            self.start,
+            false,
+        ))
+    }
+
+    fn start_status(&mut self, cfg: &BenchCfg, run_length: RunLength) {
+        self.start = Instant::now();
+        emit(
+            self.w,
+            &self.bench_id,
+            self.start,
+            EventKind::RunStart { cfg, run_length },
+        );
+    }
+
+    fn execs_per_sec_status(&mut self, execs_per_sec: f64) {
+        emit(
+            self.w,
+            &self.bench_id,
+            self.start,
+            EventKind::ExecsPerSec { execs_per_sec },
+        );
+    }
+
+    fn end_status(&mut self, outs: &[BenchOut]) {
+        let kind = EventKind::RunEnd {
+            aborted: outs.iter().any(|b| b.run_info().aborted()),
+            summaries: outs
+                .iter()
+                .map(|b| (b.n() > 0).then(|| b.summary()))
+                .collect(),
+        };
+        emit(self.w, &self.bench_id, self.start, kind);
+    }
+}
+
+#[derive(Serialize)]
+struct Event<'e> {
+    #[serde(flatten)]
+    kind: EventKind<'e>,
+    bench_id: &'e str,
+    timestamp: f64,
+    elapsed: f64,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum EventKind<'e> {
+    RunStart {
+        cfg: &'e BenchCfg,
+        run_length: RunLength,
+    },
+    ExecsPerSec {
+        execs_per_sec: f64,
+    },
+    WarmupProgress(ProgressEvent),
+    WarmupEnd,
+    ExecProgress(ProgressEvent),
+    ExecEnd,
+    RunEnd {
+        aborted: bool,
+        summaries: Vec<Option<SummaryStats>>,
+    },
+}
+
+#[derive(Serialize)]
+struct ProgressEvent {
+    i: usize,
+    est_count: usize,
+    est_time: f64,
+    phase_elapsed: f64,
+    fraction: f64,
+    medians: Vec<Option<FpSeconds>>,
+}
+
+/// Writes `kind` as a JSON line to `w`.
+fn emit(w: &mut impl Write, bench_id: &str, start: Instant, kind: EventKind<'_>) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    let event = Event {
+        kind,
+        bench_id,
+        timestamp,
+        elapsed: start.elapsed().as_secs_f64(),
+    };
+    serde_json::to_writer(&mut *w, &event).expect("unexpected error serializing status event");
+    writeln!(w).expect("unexpected error writing to `Write` object `w`");
+    w.flush().expect("unexpected I/O error");
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- status::json::test --nocapture
+mod test {
+    use super::*;
+    use crate::{
+        multi::{self, LatencySrc2},
+        test_support::StringWriter,
+    };
+    use serde_json::Value;
+    use std::thread;
+
+    #[test]
+    fn test_json_status_events() {
+        let cfg = BenchCfg::default()
+            .with_warmup_millis(20)
+            .with_status_millis(5);
+        let mut w = StringWriter::new();
+        let status = JsonStatus::new(&mut w, "sleepers".to_owned());
+        let src = LatencySrc2::new(
+            || thread::sleep(Duration::from_micros(100)),
+            || thread::sleep(Duration::from_micros(200)),
+        );
+        let out = multi::bench_run_x(&cfg, src, RunLength::Count(100), status);
+
+        let events = w
+            .as_str()
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("each line is a JSON object"))
+            .collect::<Vec<_>>();
+        let kinds = events
+            .iter()
+            .map(|e| e["event"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        println!("{kinds:?}");
+
+        // Lifecycle order.
+        let pos = |kind: &str| kinds.iter().position(|&k| k == kind).unwrap();
+        assert_eq!(0, pos("run_start"));
+        assert_eq!(1, pos("execs_per_sec"));
+        assert!(pos("warmup_progress") < pos("warmup_end"));
+        assert!(pos("warmup_end") < pos("exec_progress"));
+        assert_eq!(kinds.len() - 2, pos("exec_end"));
+        assert_eq!(kinds.len() - 1, pos("run_end"));
+
+        for e in &events {
+            assert_eq!("sleepers", e["bench_id"]);
+            assert!(e["timestamp"].as_f64().unwrap() > 1e9);
+            assert!(e["elapsed"].as_f64().unwrap() >= 0.);
+        }
+
+        let start = &events[0];
+        assert_eq!(20, start["cfg"]["warmup_millis"]);
+        assert_eq!(100, start["run_length"]["Count"]);
+        assert!(events[1]["execs_per_sec"].as_f64().unwrap() > 0.);
+
+        let last_progress = &events[kinds.len() - 3];
+        assert_eq!("exec_progress", last_progress["event"]);
+        assert_eq!(100, last_progress["i"]);
+        assert_eq!(1., last_progress["fraction"]);
+        assert_eq!(2, last_progress["medians"].as_array().unwrap().len());
+
+        let end = &events[kinds.len() - 1];
+        assert_eq!(false, end["aborted"]);
+        let summaries = end["summaries"].as_array().unwrap();
+        assert_eq!(2, summaries.len());
+        assert_eq!(out.first().n(), summaries[0]["count"]);
+        assert_eq!(out.first().median().as_f64(), summaries[0]["median"]);
+    }
+}
@@ -14,10 +14,20 @@ pub fn new_timing(hist_high: u64, hist_sigfig: u8) -> Timing {
     hist
 }
 
+/// Constructs a [`Timing`] with lowest discernible value `low` and highest trackable value `high`, which resizes
+/// automatically if `auto` is `true`.
+pub(crate) fn new_timing_with_bounds(low: u64, high: u64, sigfig: u8, auto: bool) -> Timing {
+    let mut hist = Histogram::<u64>::new_with_bounds(low, high, sigfig)
+        .expect("should not happen given histogram construction");
+    hist.auto(auto);
+    hist
+}
+
 /// Common summary statistics useful in latency testing/benchmarking.
 ///
 /// Includes sample size, mean, standard deviation, median, several percentiles, min, and max.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SummaryStats {
     /// Sample size (number of observations).
     pub count: u64,
@@ -0,0 +1,328 @@
+//! Adaptive warm-up that ends once the latencies reach a steady state.
+
+use crate::{FpSeconds, WarmupTrace};
+use std::time::{Duration, Instant};
+
+/// Determines how long the warm-up phase of a benchmark lasts
+/// (see [`BenchCfg::with_warmup_mode`](crate::BenchCfg::with_warmup_mode)).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum WarmupMode {
+    /// The warm-up lasts [`BenchCfg::warmup_millis`](crate::BenchCfg::warmup_millis) milliseconds.
+    Fixed,
+    /// The warm-up ends once the warm-up latencies reach a steady state, within the bounds of the
+    /// [`AdaptiveWarmup`] parameters.
+    Adaptive(AdaptiveWarmup),
+}
+
+/// Parameters of [`WarmupMode::Adaptive`].
+///
+/// The warm-up iterations are split into consecutive windows of [`Self::window`] iterations (batches, for batched
+/// latency sources) and the median of the aggregate latency of each window, i.e., the median over the window of the
+/// sum of the latencies of the closures in each iteration, is computed. The relative shift between the medians of
+/// two consecutive windows is `|m2 - m1| / m1`. Steady state is detected at the end of a window if the last
+/// [`Self::stable_windows`] shifts are all at most [`Self::tolerance`] and at least [`Self::min_millis`]
+/// milliseconds have elapsed since the start of the warm-up. The warm-up ends when steady state is detected or after
+/// [`Self::max_millis`] milliseconds, whichever comes first.
+///
+/// The window medians and the detected cutoff are recorded in [`RunInfo::warmup`](crate::RunInfo::warmup).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AdaptiveWarmup {
+    min_millis: u64,
+    max_millis: u64,
+    window: usize,
+    tolerance: f64,
+    stable_windows: usize,
+}
+
+impl AdaptiveWarmup {
+    /// Default number of iterations per window.
+    pub const DEFAULT_WINDOW: usize = 100;
+    /// Default maximum relative shift between the medians of consecutive windows at steady state.
+    pub const DEFAULT_TOLERANCE: f64 = 0.05;
+    /// Default number of consecutive shifts within tolerance required for steady state.
+    pub const DEFAULT_STABLE_WINDOWS: usize = 3;
+
+    /// Creates parameters for a warm-up that lasts between `min_millis` and `max_millis` milliseconds, with default
+    /// window size, tolerance, and number of stable windows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_millis > max_millis`.
+    pub fn new(min_millis: u64, max_millis: u64) -> Self {
+        assert!(
+            min_millis <= max_millis,
+            "`min_millis` must not exceed `max_millis`"
+        );
+        Self {
+            min_millis,
+            max_millis,
+            window: Self::DEFAULT_WINDOW,
+            tolerance: Self::DEFAULT_TOLERANCE,
+            stable_windows: Self::DEFAULT_STABLE_WINDOWS,
+        }
+    }
+
+    /// Minimum warm-up duration in milliseconds.
+    pub fn min_millis(&self) -> u64 {
+        self.min_millis
+    }
+
+    /// Maximum warm-up duration in milliseconds.
+    pub fn max_millis(&self) -> u64 {
+        self.max_millis
+    }
+
+    /// Number of iterations per window.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Maximum relative shift between the medians of consecutive windows at steady state.
+    pub fn tolerance(&self) -> f64 {
+        self.tolerance
+    }
+
+    /// Number of consecutive shifts within tolerance required for steady state.
+    pub fn stable_windows(&self) -> usize {
+        self.stable_windows
+    }
+
+    /// Sets the number of iterations per window.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is `0`.
+    pub fn with_window(mut self, window: usize) -> Self {
+        assert!(window > 0, "`window` must be positive");
+        self.window = window;
+        self
+    }
+
+    /// Sets the maximum relative shift between the medians of consecutive windows at steady state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tolerance` is negative or not finite.
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        assert!(
+            tolerance >= 0. && tolerance.is_finite(),
+            "`tolerance` must be non-negative and finite"
+        );
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the number of consecutive shifts within tolerance required for steady state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stable_windows` is `0`.
+    pub fn with_stable_windows(mut self, stable_windows: usize) -> Self {
+        assert!(stable_windows > 0, "`stable_windows` must be positive");
+        self.stable_windows = stable_windows;
+        self
+    }
+}
+
+/// Tracks the window medians of the warm-up latencies and detects steady state (see [`AdaptiveWarmup`]).
+pub(crate) struct SteadyStateDetector {
+    params: AdaptiveWarmup,
+    min_time: Duration,
+    start: Instant,
+    current: Vec<FpSeconds>,
+    medians: Vec<FpSeconds>,
+    stable: usize,
+    iterations: usize,
+    cutoff: Option<usize>,
+}
+
+impl SteadyStateDetector {
+    pub(crate) fn new(params: AdaptiveWarmup) -> Self {
+        Self {
+            params,
+            min_time: Duration::from_millis(params.min_millis),
+            start: Instant::now(),
+            current: Vec::with_capacity(params.window),
+            medians: Vec::new(),
+            stable: 0,
+            iterations: 0,
+            cutoff: None,
+        }
+    }
+
+    /// `true` once steady state has been detected.
+    #[inline(always)]
+    pub(crate) fn steady(&self) -> bool {
+        self.cutoff.is_some()
+    }
+
+    /// Records the aggregate latency of the next warm-up iteration.
+    pub(crate) fn observe(&mut self, latency: FpSeconds) {
+        self.iterations += 1;
+        self.current.push(latency);
+        if self.current.len() < self.params.window {
+            return;
+        }
+
+        self.current.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let median = window_median(&self.current);
+        self.current.clear();
+
+        if let Some(&prev) = self.medians.last() {
+            if rel_shift(prev, median) <= self.params.tolerance {
+                self.stable += 1;
+            } else {
+                self.stable = 0;
+            }
+        }
+        self.medians.push(median);
+
+        if self.stable >= self.params.stable_windows && self.start.elapsed() >= self.min_time {
+            self.cutoff = Some(self.iterations);
+        }
+    }
+
+    /// Consumes `self` and returns the warm-up trace.
+    pub(crate) fn trace(self) -> WarmupTrace {
+        WarmupTrace {
+            window: self.params.window,
+            medians: self.medians,
+            cutoff: self.cutoff,
+            iterations: self.iterations,
+            elapsed: self.start.elapsed(),
+        }
+    }
+}
+
+/// Median of a sorted, non-empty slice.
+fn window_median(sorted: &[FpSeconds]) -> FpSeconds {
+    let n = sorted.len();
+    if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+    }
+}
+
+/// Relative shift from `prev` to `next`; zero if both are zero.
+fn rel_shift(prev: FpSeconds, next: FpSeconds) -> f64 {
+    if prev.0 == next.0 {
+        0.
+    } else {
+        (next.0 - prev.0).abs() / prev.0
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "_test")]
+// cargo test --package bench_utils --lib --all-features -- warmup::test --nocapture
+mod test {
+    use super::*;
+
+    fn micros(x: f64) -> FpSeconds {
+        FpSeconds(x * 1e-6)
+    }
+
+    #[test]
+    fn test_steady_state_constant() {
+        let params = AdaptiveWarmup::new(0, 1000)
+            .with_window(10)
+            .with_stable_windows(2);
+        let mut detector = SteadyStateDetector::new(params);
+
+        // Steady state needs 3 windows: 2 shifts within tolerance.
+        for _ in 0..29 {
+            detector.observe(micros(5.));
+            assert!(!detector.steady());
+        }
+        detector.observe(micros(5.));
+        assert!(detector.steady());
+
+        let trace = detector.trace();
+        assert_eq!(10, trace.window());
+        assert_eq!(&[micros(5.); 3], trace.medians());
+        assert_eq!(Some(30), trace.cutoff());
+        assert_eq!(30, trace.iterations());
+        assert!(trace.steady());
+    }
+
+    #[test]
+    fn test_steady_state_after_decay() {
+        let params = AdaptiveWarmup::new(0, 1000)
+            .with_window(10)
+            .with_tolerance(0.02)
+            .with_stable_windows(2);
+        let mut detector = SteadyStateDetector::new(params);
+
+        // Latency halves every window for 5 windows, then stays at 1µs with small noise.
+        let latency = |i: usize| {
+            let decay = 2_f64.powi(5 - (i / 10).min(5) as i32);
+            micros(decay * (1. + 0.001 * (i % 3) as f64))
+        };
+        let mut i = 0;
+        while !detector.steady() {
+            assert!(i < 1000, "steady state not detected");
+            detector.observe(latency(i));
+            i += 1;
+        }
+
+        let trace = detector.trace();
+        println!("trace={trace:?}");
+        // Windows 5, 6, and 7 are at steady state.
+        assert_eq!(Some(80), trace.cutoff());
+        assert_eq!(8, trace.medians().len());
+        assert!(trace.medians()[0].0 > 30. * trace.medians()[7].0);
+    }
+
+    #[test]
+    fn test_steady_state_min_millis() {
+        let params = AdaptiveWarmup::new(60_000, 60_000).with_window(5);
+        let mut detector = SteadyStateDetector::new(params);
+        for _ in 0..100 {
+            detector.observe(micros(1.));
+        }
+        assert!(!detector.steady());
+
+        let trace = detector.trace();
+        assert_eq!(20, trace.medians().len());
+        assert_eq!(None, trace.cutoff());
+        assert!(!trace.steady());
+    }
+
+    #[test]
+    fn test_steady_state_oscillating() {
+        let params = AdaptiveWarmup::new(0, 1000).with_window(4);
+        let mut detector = SteadyStateDetector::new(params);
+        for i in 0..400 {
+            let x = if (i / 4_usize).is_multiple_of(2) {
+                1.
+            } else {
+                2.
+            };
+            detector.observe(micros(x));
+        }
+        assert!(!detector.steady());
+    }
+
+    #[test]
+    fn test_adaptive_warmup_builder() {
+        let params = AdaptiveWarmup::new(10, 20)
+            .with_window(7)
+            .with_tolerance(0.1)
+            .with_stable_windows(4);
+        assert_eq!(10, params.min_millis());
+        assert_eq!(20, params.max_millis());
+        assert_eq!(7, params.window());
+        assert_eq!(0.1, params.tolerance());
+        assert_eq!(4, params.stable_windows());
+
+        assert!(std::panic::catch_unwind(|| AdaptiveWarmup::new(2, 1)).is_err());
+        assert!(std::panic::catch_unwind(|| AdaptiveWarmup::new(1, 2).with_window(0)).is_err());
+        assert!(
+            std::panic::catch_unwind(|| AdaptiveWarmup::new(1, 2).with_tolerance(f64::NAN))
+                .is_err()
+        );
+    }
+}